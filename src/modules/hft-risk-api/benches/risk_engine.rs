@@ -2,7 +2,13 @@
 //!
 //! Performance benchmarks for the risk assessment engine
 
-use criterion::{black_box, criterion_group, criterion_main, Criterion, BatchSize};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use hft_risk_api::models::{RiskFinding, Severity};
+use hft_risk_api::risk::engine::{EngineConfig, RiskEngine};
+use hft_risk_api::risk::scoring::ScoringModel;
 
 /// Benchmark SIMD operations
 fn benchmark_simd_operations(c: &mut Criterion) {
@@ -115,6 +121,56 @@ fn benchmark_risk_calculation(c: &mut Criterion) {
     group.finish();
 }
 
+/// Batch scoring (one SIMD lane per contract) vs scoring each request with the scalar kernel
+fn benchmark_batch_scoring(c: &mut Criterion) {
+    let mut group = c.benchmark_group("batch_scoring");
+
+    const SEVERITIES: [Severity; 5] =
+        [Severity::Info, Severity::Low, Severity::Medium, Severity::High, Severity::Critical];
+    let mut rng = StdRng::seed_from_u64(42);
+    let contracts: Vec<Vec<RiskFinding>> = (0..1000)
+        .map(|_| {
+            (0..rng.gen_range(0..=10))
+                .map(|_| RiskFinding {
+                    rule_id: "REENT-001".to_string(),
+                    category: "OWASP-SC05".to_string(),
+                    severity: SEVERITIES[rng.gen_range(0..SEVERITIES.len())].clone(),
+                    description: String::new(),
+                    location: None,
+                    origin: Default::default(),
+                    confidence: rng.gen_range(0.0..=1.0),
+                })
+                .collect()
+        })
+        .collect();
+    let batch: Vec<&[RiskFinding]> = contracts.iter().map(Vec::as_slice).collect();
+
+    let simd = RiskEngine::new();
+    let scalar = RiskEngine::with_config(EngineConfig {
+        enable_simd: false,
+        ..EngineConfig::default()
+    });
+
+    for model in [ScoringModel::NoisyOr, ScoringModel::WeightedSum { saturation: 0.5 }] {
+        group.bench_function(BenchmarkId::new("per_request_scalar", model.name()), |b| {
+            b.iter(|| {
+                for findings in &batch {
+                    black_box(scalar.score(findings, &model));
+                }
+            });
+        });
+
+        group.bench_function(
+            BenchmarkId::new(format!("batch_{}", simd.kernel().name()), model.name()),
+            |b| {
+                b.iter(|| black_box(simd.score_batch(&batch, &model)));
+            },
+        );
+    }
+
+    group.finish();
+}
+
 /// Benchmark serialization
 fn benchmark_serialization(c: &mut Criterion) {
     let mut group = c.benchmark_group("serialization");
@@ -148,6 +204,7 @@ criterion_group!(
     benchmark_simd_operations,
     benchmark_memory_pool,
     benchmark_risk_calculation,
+    benchmark_batch_scoring,
     benchmark_serialization
 );
 criterion_main!(benches);
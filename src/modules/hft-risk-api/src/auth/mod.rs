@@ -0,0 +1,249 @@
+//! Authentication - API keys with per-tenant policy
+//!
+//! Features:
+//! - Keys hashed at rest, loaded from a JSON file and/or Redis
+//! - Per-key tier, allowed endpoints, rate limit, default scoring profile and policy
+//! - 401 for missing or unknown keys, 403 for endpoints outside a key's policy
+//!
+//! The authenticated [`ApiKey`] is attached to the request extensions so
+//! handlers can read the tenant policy.
+
+pub mod store;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::{Request, Response};
+
+use crate::cache::RedisCache;
+use crate::handlers::risk_handler::error_response;
+
+pub use store::{hash_key, read_key_file, ApiKey, ApiKeyStore, Tier};
+
+/// Endpoints reachable without a key, even when authentication is required
+const PUBLIC_PATHS: &[&str] = &["/health", "/metrics"];
+
+/// Prefix of endpoints that always require a key
+const ADMIN_PREFIX: &str = "/api/v1/admin/";
+
+/// Authentication configuration
+#[derive(Debug, Clone, Default)]
+pub struct AuthConfig {
+    /// Reject anonymous requests to non-public endpoints
+    ///
+    /// When off, anonymous callers may still use the assessment endpoints
+    /// (limited per IP); admin endpoints always need a key.
+    pub required: bool,
+}
+
+/// Why a request was not authenticated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthError {
+    /// No key presented where one is required
+    MissingKey,
+    /// Key unknown or disabled
+    InvalidKey,
+    /// Key valid but not allowed on this endpoint
+    Forbidden { key_id: String },
+}
+
+impl AuthError {
+    /// 401/403 error response
+    pub fn to_response(&self) -> Response<Full<Bytes>> {
+        match self {
+            AuthError::MissingKey => {
+                let mut response = error_response(
+                    401,
+                    "unauthorized",
+                    "API key required",
+                    Some("Send the key in X-API-Key or Authorization: Bearer".to_string()),
+                );
+                response
+                    .headers_mut()
+                    .insert("WWW-Authenticate", hyper::header::HeaderValue::from_static("Bearer"));
+                response
+            }
+            AuthError::InvalidKey => error_response(401, "unauthorized", "Invalid API key", None),
+            AuthError::Forbidden { key_id } => error_response(
+                403,
+                "forbidden",
+                "Endpoint not allowed for this API key",
+                Some(format!("Key {} is not permitted on this endpoint", key_id)),
+            ),
+        }
+    }
+}
+
+/// Resolves the API key of each request against the key store
+pub struct Authenticator {
+    config: AuthConfig,
+    store: Arc<ApiKeyStore>,
+}
+
+impl Authenticator {
+    /// Create an authenticator over a key store
+    pub fn new(config: AuthConfig, store: Arc<ApiKeyStore>) -> Self {
+        Self { config, store }
+    }
+
+    /// Key store shared with the Redis refresh task
+    pub fn store(&self) -> &Arc<ApiKeyStore> {
+        &self.store
+    }
+
+    /// Authenticate a request
+    ///
+    /// Returns the key when one was presented, `None` for permitted
+    /// anonymous access.
+    pub fn authenticate<B>(&self, req: &Request<B>) -> Result<Option<Arc<ApiKey>>, AuthError> {
+        let path = req.uri().path();
+        if PUBLIC_PATHS.contains(&path) {
+            return Ok(None);
+        }
+
+        let Some(presented) = api_key(req) else {
+            if self.config.required || path.starts_with(ADMIN_PREFIX) {
+                return Err(AuthError::MissingKey);
+            }
+            return Ok(None);
+        };
+
+        let key = self.store.lookup(presented).ok_or(AuthError::InvalidKey)?;
+        if !key.allows(path) {
+            return Err(AuthError::Forbidden { key_id: key.id.clone() });
+        }
+
+        Ok(Some(key))
+    }
+}
+
+/// API key from `X-API-Key` or an `Authorization: Bearer` header
+pub fn api_key<B>(req: &Request<B>) -> Option<&str> {
+    let headers = req.headers();
+    headers
+        .get("x-api-key")
+        .and_then(|value| value.to_str().ok())
+        .or_else(|| {
+            headers
+                .get(hyper::header::AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "))
+        })
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+}
+
+/// Reload Redis-provisioned keys every `interval`
+///
+/// Keeps the last good set when Redis is unreachable, so an outage does not
+/// lock partners out.
+pub fn spawn_key_refresh(
+    store: Arc<ApiKeyStore>,
+    redis: Arc<RedisCache>,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            match redis.get_api_keys().await {
+                Ok(records) => {
+                    let keys = records
+                        .iter()
+                        .filter_map(|record| match serde_json::from_str::<ApiKey>(record) {
+                            Ok(key) => Some(key),
+                            Err(e) => {
+                                tracing::warn!("Skipping malformed API key record: {}", e);
+                                None
+                            }
+                        })
+                        .collect();
+                    store.set_redis_keys(keys);
+                }
+                Err(e) => tracing::warn!("Failed to refresh API keys from Redis: {}", e),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn authenticator(required: bool) -> Authenticator {
+        let keys = vec![
+            ApiKey {
+                id: "acme".to_string(),
+                key_sha256: hash_key("sk_acme"),
+                tier: Tier::Pro,
+                allowed_endpoints: vec![],
+                rate_limit: None,
+                scoring_profile: None,
+                policy_profile: None,
+                rules: None,
+                enabled: true,
+            },
+            ApiKey {
+                id: "ops".to_string(),
+                key_sha256: hash_key("sk_ops"),
+                tier: Tier::Admin,
+                allowed_endpoints: vec![],
+                rate_limit: None,
+                scoring_profile: None,
+                policy_profile: None,
+                rules: None,
+                enabled: true,
+            },
+        ];
+        Authenticator::new(AuthConfig { required }, Arc::new(ApiKeyStore::new(keys)))
+    }
+
+    fn request(path: &str, key: Option<&str>) -> Request<()> {
+        let mut builder = Request::builder().uri(path);
+        if let Some(key) = key {
+            builder = builder.header("Authorization", format!("Bearer {}", key));
+        }
+        builder.body(()).unwrap()
+    }
+
+    #[test]
+    fn test_admin_endpoints_require_admin_key() {
+        let auth = authenticator(false);
+
+        assert_eq!(
+            auth.authenticate(&request("/api/v1/admin/cache/clear", None)).unwrap_err(),
+            AuthError::MissingKey
+        );
+        assert_eq!(
+            auth.authenticate(&request("/api/v1/admin/cache/clear", Some("sk_acme"))).unwrap_err(),
+            AuthError::Forbidden { key_id: "acme".to_string() }
+        );
+        let ops = auth
+            .authenticate(&request("/api/v1/admin/cache/clear", Some("sk_ops")))
+            .unwrap()
+            .unwrap();
+        assert_eq!(ops.id, "ops");
+
+        let response = AuthError::Forbidden { key_id: "acme".to_string() }.to_response();
+        assert_eq!(response.status(), 403);
+    }
+
+    #[test]
+    fn test_anonymous_access_follows_config() {
+        let open = authenticator(false);
+        assert!(open.authenticate(&request("/api/v1/risk/assess", None)).unwrap().is_none());
+        assert_eq!(
+            open.authenticate(&request("/api/v1/risk/assess", Some("sk_wrong"))).unwrap_err(),
+            AuthError::InvalidKey
+        );
+
+        let closed = authenticator(true);
+        assert_eq!(
+            closed.authenticate(&request("/api/v1/risk/assess", None)).unwrap_err(),
+            AuthError::MissingKey
+        );
+        assert!(closed.authenticate(&request("/health", None)).unwrap().is_none());
+    }
+}
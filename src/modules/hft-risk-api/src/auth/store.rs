@@ -0,0 +1,236 @@
+//! API Key Store - Hashed API keys loaded from a file or Redis
+//!
+//! Only the SHA-256 hex digest of each key is stored; a presented key is
+//! hashed and looked up by digest. Generate a digest with
+//! `printf %s "$KEY" | sha256sum`.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::scanner::selection::RuleSelection;
+
+use super::ADMIN_PREFIX;
+
+/// Customer tier of an API key
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tier {
+    #[default]
+    Free,
+    Pro,
+    Enterprise,
+    /// Operators; the only tier allowed on admin endpoints by default
+    Admin,
+}
+
+/// A registered API key and its tenant policy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKey {
+    /// Stable key identifier, safe to log and meter against
+    pub id: String,
+    /// SHA-256 hex digest of the secret key
+    pub key_sha256: String,
+    #[serde(default)]
+    pub tier: Tier,
+    /// Path prefixes this key may call (`*` for all); defaults to every
+    /// endpoint when empty. Admin endpoints need the admin tier either way.
+    #[serde(default)]
+    pub allowed_endpoints: Vec<String>,
+    /// Requests per rate limit window, overriding the global per-key limit
+    #[serde(default)]
+    pub rate_limit: Option<u64>,
+    /// Scoring profile applied when a request does not choose one
+    #[serde(default)]
+    pub scoring_profile: Option<String>,
+    /// Policy applied when a request does not choose one
+    #[serde(default)]
+    pub policy_profile: Option<String>,
+    /// Rule selection applied before the request's own
+    #[serde(default)]
+    pub rules: Option<RuleSelection>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl ApiKey {
+    /// Whether this key may call `path`
+    pub fn allows(&self, path: &str) -> bool {
+        if self.tier != Tier::Admin && path.starts_with(ADMIN_PREFIX) {
+            return false;
+        }
+
+        self.allowed_endpoints.is_empty()
+            || self
+                .allowed_endpoints
+                .iter()
+                .any(|prefix| prefix == "*" || path.starts_with(prefix.as_str()))
+    }
+}
+
+/// Key file layout: `{"keys": [ApiKey, ...]}`
+#[derive(Debug, Deserialize)]
+struct KeyFile {
+    keys: Vec<ApiKey>,
+}
+
+/// SHA-256 hex digest of a presented key
+pub fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// Read API keys from a JSON key file
+pub fn read_key_file(path: &Path) -> io::Result<Vec<ApiKey>> {
+    let data = fs::read(path)?;
+    let file: KeyFile = serde_json::from_slice(&data)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Ok(file.keys)
+}
+
+/// In-memory index of API keys by digest
+///
+/// File and Redis keys are tracked separately so a Redis refresh never drops
+/// keys provisioned through the file.
+#[derive(Default)]
+pub struct ApiKeyStore {
+    file_keys: Vec<ApiKey>,
+    redis_keys: RwLock<Vec<ApiKey>>,
+    by_hash: RwLock<HashMap<String, Arc<ApiKey>>>,
+}
+
+impl ApiKeyStore {
+    /// Create a store from file-provisioned keys
+    pub fn new(file_keys: Vec<ApiKey>) -> Self {
+        let store = Self {
+            file_keys,
+            ..Self::default()
+        };
+        store.rebuild();
+        store
+    }
+
+    /// Replace the keys loaded from Redis
+    pub fn set_redis_keys(&self, keys: Vec<ApiKey>) {
+        *self.redis_keys.write() = keys;
+        self.rebuild();
+    }
+
+    fn rebuild(&self) {
+        let redis_keys = self.redis_keys.read();
+        let index = self
+            .file_keys
+            .iter()
+            .chain(redis_keys.iter())
+            .filter(|key| key.enabled)
+            .map(|key| (key.key_sha256.to_ascii_lowercase(), Arc::new(key.clone())))
+            .collect();
+        *self.by_hash.write() = index;
+    }
+
+    /// Look up an enabled key by its secret
+    pub fn lookup(&self, key: &str) -> Option<Arc<ApiKey>> {
+        self.by_hash.read().get(&hash_key(key)).cloned()
+    }
+
+    /// Number of enabled keys
+    pub fn len(&self) -> usize {
+        self.by_hash.read().len()
+    }
+
+    /// Whether no keys are registered
+    pub fn is_empty(&self) -> bool {
+        self.by_hash.read().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_file_and_lookup() {
+        let path = std::env::temp_dir().join(format!("keys-{}.json", uuid::Uuid::new_v4()));
+        let contents = format!(
+            r#"{{"keys": [
+                {{"id": "acme", "key_sha256": "{}", "tier": "pro", "rate_limit": 5000}},
+                {{"id": "revoked", "key_sha256": "{}", "enabled": false}}
+            ]}}"#,
+            hash_key("sk_acme"),
+            hash_key("sk_revoked"),
+        );
+        fs::write(&path, contents).unwrap();
+        let keys = read_key_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let store = ApiKeyStore::new(keys);
+        assert_eq!(store.len(), 1);
+        assert!(store.lookup("sk_revoked").is_none());
+        assert!(store.lookup("sk_other").is_none());
+
+        let acme = store.lookup("sk_acme").unwrap();
+        assert_eq!(acme.tier, Tier::Pro);
+        assert_eq!(acme.rate_limit, Some(5000));
+        assert!(acme.allows("/api/v1/risk/assess"));
+        assert!(!acme.allows("/api/v1/admin/cache/clear"));
+    }
+
+    #[test]
+    fn test_redis_refresh_keeps_file_keys() {
+        let key = |id: &str, secret: &str| ApiKey {
+            id: id.to_string(),
+            key_sha256: hash_key(secret),
+            tier: Tier::Admin,
+            allowed_endpoints: vec!["/api/v1/admin/".to_string()],
+            rate_limit: None,
+            scoring_profile: None,
+            policy_profile: None,
+            rules: None,
+            enabled: true,
+        };
+
+        let store = ApiKeyStore::new(vec![key("ops", "sk_ops")]);
+        store.set_redis_keys(vec![key("partner", "sk_partner")]);
+        assert!(store.lookup("sk_ops").is_some());
+        assert!(store.lookup("sk_partner").is_some());
+
+        store.set_redis_keys(vec![]);
+        assert!(store.lookup("sk_ops").is_some());
+        assert!(store.lookup("sk_partner").is_none());
+
+        let ops = store.lookup("sk_ops").unwrap();
+        assert!(ops.allows("/api/v1/admin/cache/clear"));
+        assert!(!ops.allows("/api/v1/risk/assess"));
+    }
+
+    #[test]
+    fn test_admin_paths_need_admin_tier_whatever_the_endpoint_list() {
+        let key = |tier: Tier, endpoint: &str| ApiKey {
+            id: "acme".to_string(),
+            key_sha256: hash_key("sk_acme"),
+            tier,
+            allowed_endpoints: vec![endpoint.to_string()],
+            rate_limit: None,
+            scoring_profile: None,
+            policy_profile: None,
+            rules: None,
+            enabled: true,
+        };
+
+        for endpoint in ["*", "/api/v1/", "/api/v1/admin/"] {
+            let enterprise = key(Tier::Enterprise, endpoint);
+            assert!(!enterprise.allows("/api/v1/admin/cache/clear"), "{}", endpoint);
+            assert!(key(Tier::Admin, endpoint).allows("/api/v1/admin/cache/clear"), "{}", endpoint);
+        }
+        assert!(key(Tier::Enterprise, "*").allows("/api/v1/risk/assess"));
+    }
+}
@@ -0,0 +1,429 @@
+//! Redis Connection Pool - Shared auto-reconnecting connections
+//!
+//! Holds `pool_size` multiplexed connections and hands them out round-robin.
+//! Depending on the [`RedisTopology`] these are [`ConnectionManager`]s to a
+//! standalone server or a Sentinel-resolved master, or cluster connections
+//! that route each command to the node owning its slot. Every command runs
+//! under a timeout, and command outcomes feed a health tracker reported on
+//! `/health`.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use parking_lot::{Mutex, RwLock};
+use redis::aio::{ConnectionLike, ConnectionManager};
+use redis::cluster::ClusterClient;
+use redis::cluster_async::ClusterConnection;
+use redis::cluster_routing::{Route, RoutingInfo, SingleNodeRoutingInfo, SlotAddr};
+use redis::sentinel::Sentinel;
+use redis::{Client, Cmd, FromRedisValue, Pipeline, RedisError, RedisFuture, Value};
+use serde::Serialize;
+
+use super::{RedisConfig, RedisTopology};
+
+/// Base of the exponential reconnect backoff
+const RECONNECT_EXPONENT_BASE: u64 = 2;
+
+/// Consecutive failures after which the pool reports unhealthy
+const UNHEALTHY_AFTER_FAILURES: u64 = 3;
+
+/// A pooled connection to a standalone/Sentinel master or to a cluster
+#[derive(Clone)]
+pub enum CacheConnection {
+    Managed(ConnectionManager),
+    Cluster(ClusterConnection),
+}
+
+impl ConnectionLike for CacheConnection {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        match self {
+            CacheConnection::Managed(conn) => conn.req_packed_command(cmd),
+            CacheConnection::Cluster(conn) => conn.req_packed_command(cmd),
+        }
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        match self {
+            CacheConnection::Managed(conn) => conn.req_packed_commands(cmd, offset, count),
+            CacheConnection::Cluster(conn) => conn.req_packed_commands(cmd, offset, count),
+        }
+    }
+
+    fn get_db(&self) -> i64 {
+        match self {
+            CacheConnection::Managed(conn) => conn.get_db(),
+            CacheConnection::Cluster(conn) => conn.get_db(),
+        }
+    }
+}
+
+impl CacheConnection {
+    /// Run a command on the node owning `route`'s slot
+    ///
+    /// Outside cluster mode there is only one node and the route is ignored.
+    pub async fn query_routed<T: FromRedisValue>(
+        &mut self,
+        cmd: &Cmd,
+        route: Option<&Route>,
+    ) -> Result<T, RedisError> {
+        match (self, route) {
+            (CacheConnection::Cluster(conn), Some(route)) => {
+                let routing =
+                    RoutingInfo::SingleNode(SingleNodeRoutingInfo::SpecificNode(*route));
+                let value = conn.route_command(cmd, routing).await?;
+                T::from_redis_value(&value)
+            }
+            (conn, _) => cmd.query_async(conn).await,
+        }
+    }
+}
+
+/// Pool of shared Redis connections
+pub struct ConnectionPool {
+    topology: RedisTopology,
+    url: String,
+    connections: RwLock<Vec<CacheConnection>>,
+    next: AtomicUsize,
+    size: usize,
+    command_timeout: Duration,
+    reconnect_backoff_ms: u64,
+    reconnect_retries: usize,
+    /// Set while a Sentinel master re-resolution is in flight
+    resolving: AtomicBool,
+    health: HealthTracker,
+}
+
+impl ConnectionPool {
+    /// Open `pool_size` connections according to the configured topology
+    ///
+    /// Fails if any connection cannot be established within the connection
+    /// timeout, so startup does not hang on an unreachable server.
+    pub async fn connect(config: &RedisConfig) -> Result<Self, RedisError> {
+        let pool = Self {
+            topology: config.topology.clone(),
+            url: config.url.clone(),
+            connections: RwLock::new(Vec::new()),
+            next: AtomicUsize::new(0),
+            size: config.pool_size.max(1),
+            command_timeout: Duration::from_secs(config.connection_timeout_secs),
+            reconnect_backoff_ms: config.reconnect_backoff_ms,
+            reconnect_retries: config.reconnect_retries,
+            resolving: AtomicBool::new(false),
+            health: HealthTracker::default(),
+        };
+
+        let connections = pool.open_connections().await?;
+        *pool.connections.write() = connections;
+        Ok(pool)
+    }
+
+    /// Establish a fresh set of connections
+    async fn open_connections(&self) -> Result<Vec<CacheConnection>, RedisError> {
+        let mut connections = Vec::with_capacity(self.size);
+
+        match &self.topology {
+            RedisTopology::Cluster { nodes } => {
+                let client = ClusterClient::builder(nodes.clone())
+                    .retries(self.reconnect_retries as u32)
+                    .min_retry_wait(self.reconnect_backoff_ms)
+                    .retry_wait_formula(self.reconnect_backoff_ms, RECONNECT_EXPONENT_BASE)
+                    .build()?;
+
+                for _ in 0..self.size {
+                    let conn = self.with_timeout(client.get_async_connection()).await?;
+                    connections.push(CacheConnection::Cluster(conn));
+                }
+            }
+            RedisTopology::Standalone | RedisTopology::Sentinel { .. } => {
+                let client = self.master_client().await?;
+
+                for _ in 0..self.size {
+                    let manager = self
+                        .with_timeout(client.get_connection_manager_with_backoff(
+                            RECONNECT_EXPONENT_BASE,
+                            self.reconnect_backoff_ms,
+                            self.reconnect_retries,
+                        ))
+                        .await?;
+                    connections.push(CacheConnection::Managed(manager));
+                }
+            }
+        }
+
+        Ok(connections)
+    }
+
+    /// Client for the current write master
+    ///
+    /// For Sentinel this asks the sentinels where the master lives now; for
+    /// a cluster it returns a client for the first reachable seed node,
+    /// which is enough for pub/sub since cluster PUBLISH reaches every node.
+    pub async fn master_client(&self) -> Result<Client, RedisError> {
+        match &self.topology {
+            RedisTopology::Standalone => Client::open(self.url.clone()),
+            RedisTopology::Cluster { nodes } => {
+                let mut last_error = None;
+                for node in nodes {
+                    let client = Client::open(node.as_str())?;
+                    match self.with_timeout(client.get_multiplexed_tokio_connection()).await {
+                        Ok(_) => return Ok(client),
+                        Err(e) => last_error = Some(e),
+                    }
+                }
+                Err(last_error.unwrap_or_else(|| {
+                    RedisError::from((
+                        redis::ErrorKind::InvalidClientConfig,
+                        "No cluster nodes configured",
+                    ))
+                }))
+            }
+            RedisTopology::Sentinel { sentinels, service_name } => {
+                let mut sentinel = Sentinel::build(sentinels.clone())?;
+                self.with_timeout(sentinel.async_master_for(service_name, None))
+                    .await
+            }
+        }
+    }
+
+    /// Whether commands are routed across a Redis Cluster
+    pub fn is_cluster(&self) -> bool {
+        matches!(self.topology, RedisTopology::Cluster { .. })
+    }
+
+    /// Number of pooled connections
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Next connection in round-robin order
+    pub fn get(&self) -> CacheConnection {
+        let connections = self.connections.read();
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % connections.len();
+        connections[index].clone()
+    }
+
+    /// Run a command on a pooled connection under the command timeout
+    pub async fn run<T, F, Fut>(&self, command: F) -> Result<T, RedisError>
+    where
+        F: FnOnce(CacheConnection) -> Fut,
+        Fut: Future<Output = Result<T, RedisError>>,
+    {
+        let result = self.with_timeout(command(self.get())).await;
+
+        match &result {
+            Ok(_) => self.health.record_success(),
+            Err(e) => {
+                self.health.record_failure(e);
+                if self.needs_failover(e) {
+                    self.refresh_master().await;
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Apply the command timeout to a Redis future
+    async fn with_timeout<T>(
+        &self,
+        future: impl Future<Output = Result<T, RedisError>>,
+    ) -> Result<T, RedisError> {
+        match tokio::time::timeout(self.command_timeout, future).await {
+            Ok(result) => result,
+            Err(_) => Err(timeout_error("Redis command timed out")),
+        }
+    }
+
+    /// Whether an error suggests the Sentinel master has moved
+    fn needs_failover(&self, error: &RedisError) -> bool {
+        matches!(self.topology, RedisTopology::Sentinel { .. })
+            && (error.kind() == redis::ErrorKind::ReadOnly
+                || error.is_connection_dropped()
+                || error.is_connection_refusal()
+                || error.is_timeout())
+    }
+
+    /// Re-resolve the Sentinel master and swap in new connections
+    ///
+    /// Only one caller performs the refresh; concurrent failures return
+    /// their error immediately.
+    async fn refresh_master(&self) {
+        if self
+            .resolving
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            return;
+        }
+
+        match self.open_connections().await {
+            Ok(connections) => {
+                *self.connections.write() = connections;
+                tracing::info!("Reconnected to Redis master after failover");
+            }
+            Err(e) => tracing::warn!("Failed to re-resolve Redis master: {}", e),
+        }
+
+        self.resolving.store(false, Ordering::Release);
+    }
+
+    /// One route per cluster master, for commands such as SCAN that only
+    /// see the keys of the node they run on
+    ///
+    /// Outside cluster mode this is a single `None` (the only node).
+    pub async fn node_routes(&self) -> Result<Vec<Option<Route>>, RedisError> {
+        if !self.is_cluster() {
+            return Ok(vec![None]);
+        }
+
+        let slots: Vec<Value> = self
+            .run(|mut conn| async move {
+                redis::cmd("CLUSTER").arg("SLOTS").query_async(&mut conn).await
+            })
+            .await?;
+
+        Ok(master_start_slots(&slots)
+            .into_iter()
+            .map(|slot| Some(Route::new(slot, SlotAddr::Master)))
+            .collect())
+    }
+
+    /// Current health of the pool
+    pub fn health(&self) -> RedisHealth {
+        self.health.snapshot(self.size())
+    }
+}
+
+/// First slot of each distinct master in a `CLUSTER SLOTS` reply
+fn master_start_slots(slots: &[Value]) -> Vec<u16> {
+    let mut masters: Vec<(Vec<u8>, i64)> = Vec::new();
+    let mut starts = Vec::new();
+
+    for range in slots {
+        let Value::Bulk(items) = range else { continue };
+        let (Some(Value::Int(start)), Some(Value::Bulk(master))) = (items.first(), items.get(2))
+        else {
+            continue;
+        };
+        let (Some(Value::Data(host)), Some(Value::Int(port))) = (master.first(), master.get(1))
+        else {
+            continue;
+        };
+
+        let node = (host.clone(), *port);
+        if !masters.contains(&node) {
+            masters.push(node);
+            starts.push(*start as u16);
+        }
+    }
+
+    starts
+}
+
+/// Health of the Redis connection pool
+#[derive(Debug, Clone, Serialize)]
+pub struct RedisHealth {
+    pub healthy: bool,
+    pub pool_size: usize,
+    pub consecutive_failures: u64,
+    pub total_failures: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// Unix timestamp of the last successful command
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_success_at: Option<i64>,
+}
+
+/// Lock-free counters updated on every command
+#[derive(Default)]
+struct HealthTracker {
+    consecutive_failures: AtomicU64,
+    total_failures: AtomicU64,
+    last_success_at: AtomicU64,
+    last_error: Mutex<Option<String>>,
+}
+
+impl HealthTracker {
+    fn record_success(&self) {
+        self.consecutive_failures.store(0, Ordering::Relaxed);
+        self.last_success_at
+            .store(chrono::Utc::now().timestamp() as u64, Ordering::Relaxed);
+    }
+
+    fn record_failure(&self, error: &RedisError) {
+        self.consecutive_failures.fetch_add(1, Ordering::Relaxed);
+        self.total_failures.fetch_add(1, Ordering::Relaxed);
+        *self.last_error.lock() = Some(error.to_string());
+    }
+
+    fn snapshot(&self, pool_size: usize) -> RedisHealth {
+        let consecutive_failures = self.consecutive_failures.load(Ordering::Relaxed);
+        let last_success_at = self.last_success_at.load(Ordering::Relaxed);
+
+        RedisHealth {
+            healthy: consecutive_failures < UNHEALTHY_AFTER_FAILURES,
+            pool_size,
+            consecutive_failures,
+            total_failures: self.total_failures.load(Ordering::Relaxed),
+            last_error: self.last_error.lock().clone(),
+            last_success_at: (last_success_at > 0).then_some(last_success_at as i64),
+        }
+    }
+}
+
+/// Build the error returned when a connect or command exceeds its timeout
+fn timeout_error(message: &'static str) -> RedisError {
+    RedisError::from((redis::ErrorKind::IoError, message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_health_tracker_thresholds() {
+        let tracker = HealthTracker::default();
+        let error = timeout_error("Redis command timed out");
+
+        assert!(tracker.snapshot(4).healthy);
+
+        for _ in 0..UNHEALTHY_AFTER_FAILURES {
+            tracker.record_failure(&error);
+        }
+        let health = tracker.snapshot(4);
+        assert!(!health.healthy);
+        assert_eq!(health.pool_size, 4);
+        assert!(health.last_error.unwrap().contains("timed out"));
+
+        tracker.record_success();
+        let health = tracker.snapshot(4);
+        assert!(health.healthy);
+        assert_eq!(health.total_failures, UNHEALTHY_AFTER_FAILURES);
+        assert!(health.last_success_at.is_some());
+    }
+
+    #[test]
+    fn test_master_start_slots_dedupes_masters() {
+        let node = |host: &str, port: i64| {
+            Value::Bulk(vec![Value::Data(host.as_bytes().to_vec()), Value::Int(port)])
+        };
+        let range = |start: i64, end: i64, master: Value| {
+            Value::Bulk(vec![Value::Int(start), Value::Int(end), master])
+        };
+
+        let slots = vec![
+            range(0, 5460, node("10.0.0.1", 6379)),
+            range(5461, 10922, node("10.0.0.2", 6379)),
+            range(10923, 12000, node("10.0.0.3", 6379)),
+            range(12001, 16383, node("10.0.0.1", 6379)),
+        ];
+
+        assert_eq!(master_start_slots(&slots), vec![0, 5461, 10923]);
+    }
+}
@@ -0,0 +1,92 @@
+//! Cache Invalidation - Scoped invalidation messages shared across replicas
+//!
+//! Invalidations are applied to the local (L1) cache, purged from Redis (L2)
+//! and broadcast over Redis pub/sub so that every replica drops its own L1
+//! entries for the same scope.
+
+use serde::{Deserialize, Serialize};
+
+/// Pub/sub channel carrying invalidation messages
+pub const INVALIDATION_CHANNEL: &str = "risk:invalidate";
+
+/// Scope of a cache invalidation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "scope", rename_all = "lowercase")]
+pub enum CacheInvalidation {
+    /// A single `(chain, address)` assessment
+    Entry { chain: String, address: String },
+    /// Every assessment on a chain
+    Chain { chain: String },
+    /// Every cached assessment
+    All,
+}
+
+impl CacheInvalidation {
+    /// Check that the scope only contains characters allowed in cache keys
+    ///
+    /// Chain names end up in Redis SCAN patterns, so glob metacharacters and
+    /// key separators are rejected rather than escaped.
+    pub fn validate(&self) -> Result<(), String> {
+        let valid_chain = |chain: &str| {
+            !chain.is_empty()
+                && chain
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        };
+
+        match self {
+            CacheInvalidation::Entry { chain, address } => {
+                if !valid_chain(chain) {
+                    return Err(format!("Invalid chain: {}", chain));
+                }
+                if address.is_empty() || address.contains(':') {
+                    return Err(format!("Invalid address: {}", address));
+                }
+                Ok(())
+            }
+            CacheInvalidation::Chain { chain } => {
+                if valid_chain(chain) {
+                    Ok(())
+                } else {
+                    Err(format!("Invalid chain: {}", chain))
+                }
+            }
+            CacheInvalidation::All => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalidation_wire_format() {
+        let entry = CacheInvalidation::Entry {
+            chain: "ethereum".to_string(),
+            address: "0xAbC".to_string(),
+        };
+        let json = serde_json::to_string(&entry).unwrap();
+        assert_eq!(json, r#"{"scope":"entry","chain":"ethereum","address":"0xAbC"}"#);
+        assert_eq!(serde_json::from_str::<CacheInvalidation>(&json).unwrap(), entry);
+
+        let all: CacheInvalidation = serde_json::from_str(r#"{"scope":"all"}"#).unwrap();
+        assert_eq!(all, CacheInvalidation::All);
+    }
+
+    #[test]
+    fn test_invalidation_rejects_glob_characters() {
+        let chain = CacheInvalidation::Chain { chain: "eth*".to_string() };
+        assert!(chain.validate().is_err());
+
+        let entry = CacheInvalidation::Entry {
+            chain: "bsc".to_string(),
+            address: "0x1:0x2".to_string(),
+        };
+        assert!(entry.validate().is_err());
+
+        assert!(CacheInvalidation::Chain { chain: "base-sepolia".to_string() }
+            .validate()
+            .is_ok());
+    }
+}
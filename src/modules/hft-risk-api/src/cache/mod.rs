@@ -1,59 +1,166 @@
 //! Redis Cache - High-performance Redis integration with connection pooling
 //!
 //! Features:
-//! - Connection pooling for concurrent access
+//! - Pooled auto-reconnecting connections with per-command timeouts
+//! - Standalone, Redis Cluster and Sentinel topologies
 //! - Pipeline-based batch operations
 //! - TTL-based caching with automatic expiration
 //! - Binary serialization with MessagePack
+//! - Prefix-scoped purges and pub/sub invalidation across replicas
 
+pub mod connection;
+pub mod invalidation;
+pub mod snapshot;
+
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 use std::time::Duration;
 
-use redis::{aio::MultiplexedConnection, AsyncCommands, Client};
-use serde::{Serialize};
-
-use crate::models::{RiskAssessmentResponse};
+use futures::StreamExt;
+use redis::{AsyncCommands, Client};
+
+use crate::models::{RiskAssessmentResponse, UsageCounters};
+
+pub use connection::{CacheConnection, ConnectionPool, RedisHealth};
+pub use invalidation::{CacheInvalidation, INVALIDATION_CHANNEL};
+
+/// How the Redis deployment is reached
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RedisTopology {
+    /// A single server at [`RedisConfig::url`]
+    Standalone,
+    /// A Redis Cluster, discovered from the seed node URLs
+    Cluster { nodes: Vec<String> },
+    /// A master resolved through Sentinel, re-resolved on failover
+    Sentinel {
+        sentinels: Vec<String>,
+        service_name: String,
+    },
+}
 
 /// Redis cache configuration
 #[derive(Debug, Clone)]
 pub struct RedisConfig {
-    /// Redis connection URL
+    /// Redis connection URL (standalone topology)
     pub url: String,
-    /// Connection pool size
+    /// Deployment topology
+    pub topology: RedisTopology,
+    /// Number of pooled multiplexed connections
     pub pool_size: usize,
     /// Default TTL for cached entries (seconds)
     pub default_ttl_secs: u64,
     /// Maximum batch size for pipeline operations
     pub max_batch_size: usize,
-    /// Connection timeout
+    /// Timeout for connecting and for each command
     pub connection_timeout_secs: u64,
+    /// Reconnect backoff factor (milliseconds), doubled on every retry
+    pub reconnect_backoff_ms: u64,
+    /// Reconnect attempts before a command fails with a connection error
+    pub reconnect_retries: usize,
 }
 
 impl Default for RedisConfig {
     fn default() -> Self {
         Self {
             url: "redis://127.0.0.1:6379".to_string(),
+            topology: RedisTopology::Standalone,
             pool_size: 10,
             default_ttl_secs: 300, // 5 minutes
             max_batch_size: 100,
             connection_timeout_secs: 5,
+            reconnect_backoff_ms: 100,
+            reconnect_retries: 6,
         }
     }
 }
 
 /// Redis cache manager with connection pooling
 pub struct RedisCache {
-    /// Redis client
-    client: Client,
     /// Connection pool
-    connections: Arc<tokio::sync::Semaphore>,
+    pool: Arc<ConnectionPool>,
     /// Configuration
     config: RedisConfig,
+    /// Sliding-window rate limit script, loaded lazily by EVALSHA
+    rate_limit_script: redis::Script,
+}
+
+/// Key prefix shared by every cached risk assessment
+pub const RISK_KEY_PREFIX: &str = "risk";
+
+/// Number of keys requested per SCAN iteration during purges
+const PURGE_SCAN_COUNT: usize = 500;
+
+/// Hash of API key records (field: key id, value: JSON record)
+pub const API_KEYS_HASH: &str = "auth:keys";
+
+/// Key prefix for hourly usage counters
+pub const USAGE_KEY_PREFIX: &str = "usage";
+
+/// Key prefix for rate limit windows
+pub const RATE_LIMIT_KEY_PREFIX: &str = "ratelimit";
+
+/// Redis key of one API key's usage in the hour starting at `hour`
+///
+/// The key id is hash-tagged so all hours of a key share a cluster slot and
+/// can be read or written in one pipeline.
+pub fn usage_key(key_id: &str, hour: i64) -> String {
+    format!("{}:{{{}}}:{}", USAGE_KEY_PREFIX, key_id, hour)
+}
+
+/// Atomic sliding window over a sorted set of request timestamps
+///
+/// Uses the server clock so every replica agrees on the window. Rejected
+/// requests are not recorded, so a client that backs off recovers as soon as
+/// the oldest request leaves the window. Returns `{allowed, count, reset_ms}`.
+const SLIDING_WINDOW_SCRIPT: &str = r#"
+redis.replicate_commands()
+local key = KEYS[1]
+local limit = tonumber(ARGV[1])
+local window = tonumber(ARGV[2])
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+
+redis.call('ZREMRANGEBYSCORE', key, '-inf', now - window)
+local count = redis.call('ZCARD', key)
+local allowed = 0
+if count < limit then
+    redis.call('ZADD', key, now, ARGV[3])
+    count = count + 1
+    allowed = 1
+end
+redis.call('PEXPIRE', key, window)
+
+local reset = window
+local oldest = redis.call('ZRANGE', key, 0, 0, 'WITHSCORES')
+if oldest[2] then
+    reset = tonumber(oldest[2]) + window - now
+end
+return {allowed, count, reset}
+"#;
+
+/// Outcome of a sliding-window rate limit check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlidingWindow {
+    /// Whether the request was admitted
+    pub allowed: bool,
+    /// Requests in the window, including this one if admitted
+    pub count: u64,
+    /// Milliseconds until the oldest request leaves the window
+    pub reset_ms: u64,
 }
 
 /// Cache key generator for risk assessments
 pub fn risk_cache_key(chain: &str, contract_address: &str) -> String {
-    format!("risk:{}:{}", chain, contract_address.to_lowercase())
+    format!("{}:{}:{}", RISK_KEY_PREFIX, chain, contract_address.to_lowercase())
+}
+
+/// SCAN pattern matching the cached assessments covered by an invalidation
+pub fn risk_cache_pattern(invalidation: &CacheInvalidation) -> String {
+    match invalidation {
+        CacheInvalidation::Entry { chain, address } => risk_cache_key(chain, address),
+        CacheInvalidation::Chain { chain } => format!("{}:{}:*", RISK_KEY_PREFIX, chain),
+        CacheInvalidation::All => format!("{}:*", RISK_KEY_PREFIX),
+    }
 }
 
 /// Cache key for batch operations
@@ -64,46 +171,69 @@ pub fn batch_cache_key(request_id: &str) -> String {
 impl RedisCache {
     /// Create a new Redis cache manager
     pub async fn new(config: RedisConfig) -> Result<Self, redis::RedisError> {
-        let client = Client::open(config.url.clone())?;
-        
-        // Test connection
-        let _: MultiplexedConnection = client.get_multiplexed_async_connection().await?;
+        // Connections are established up front, failing fast if Redis is unreachable
+        let pool = Arc::new(ConnectionPool::connect(&config).await?);
 
         Ok(Self {
-            client,
-            connections: Arc::new(tokio::sync::Semaphore::new(config.pool_size)),
+            pool,
             config,
+            rate_limit_script: redis::Script::new(SLIDING_WINDOW_SCRIPT),
         })
     }
 
-    /// Get a connection from the pool
-    async fn get_connection(&self) -> Result<MultiplexedConnection, redis::RedisError> {
-        let _permit = self
-            .connections
-            .clone()
-            .acquire_owned()
-            .await
-            .map_err(|_| redis::RedisError::from((
-                redis::ErrorKind::IoError,
-                "Failed to acquire connection from pool"
-            )))?;
+    /// Health of the connection pool
+    pub fn health(&self) -> RedisHealth {
+        self.pool.health()
+    }
 
-        self.client.get_multiplexed_async_connection().await
+    /// Split keys into pipeline batches
+    ///
+    /// In cluster mode a pipeline must stay on one slot, so keys are grouped
+    /// by slot and the groups are sent concurrently; the cluster connection
+    /// multiplexes them onto one socket per node. Keys are deliberately not
+    /// hash-tagged by chain, which would pin a whole chain to a single shard.
+    /// Batches are capped at `max_batch_size`.
+    fn pipeline_groups(&self, keys: &[String]) -> Vec<Vec<usize>> {
+        let groups: Vec<Vec<usize>> = if self.pool.is_cluster() {
+            let mut by_slot: BTreeMap<u16, Vec<usize>> = BTreeMap::new();
+            for (index, key) in keys.iter().enumerate() {
+                by_slot
+                    .entry(redis::cluster_routing::get_slot(key.as_bytes()))
+                    .or_default()
+                    .push(index);
+            }
+            by_slot.into_values().collect()
+        } else {
+            vec![(0..keys.len()).collect()]
+        };
+
+        groups
+            .into_iter()
+            .flat_map(|group| {
+                group
+                    .chunks(self.config.max_batch_size.max(1))
+                    .map(|chunk| chunk.to_vec())
+                    .collect::<Vec<_>>()
+            })
+            .collect()
     }
 
-    /// Get a cached risk assessment
+    /// Get a cached risk assessment and how long ago it was cached
     pub async fn get_risk_assessment(
         &self,
         chain: &str,
         contract_address: &str,
-    ) -> Result<Option<RiskAssessmentResponse>, redis::RedisError> {
+    ) -> Result<Option<(RiskAssessmentResponse, Duration)>, redis::RedisError> {
         let key = risk_cache_key(chain, contract_address);
-        let mut conn = self.get_connection().await?;
+        let data = self
+            .pool
+            .run(|mut conn| async move { conn.get::<_, Option<Vec<u8>>>(key).await })
+            .await?;
 
-        match conn.get::<&str, Option<Vec<u8>>>(&key).await? {
+        match data {
             Some(data) => {
                 match rmp_serde::from_slice::<CachedRiskResponse>(&data) {
-                    Ok(cached) => Ok(Some(cached.to_response())),
+                    Ok(cached) => Ok(Some((cached.to_response(), cached.age()))),
                     Err(_) => Ok(None),
                 }
             }
@@ -121,7 +251,6 @@ impl RedisCache {
     ) -> Result<(), redis::RedisError> {
         let key = risk_cache_key(chain, contract_address);
         let ttl = ttl_secs.unwrap_or(self.config.default_ttl_secs);
-        let mut conn = self.get_connection().await?;
 
         let cached = CachedRiskResponse::from_response(response);
         let data = rmp_serde::to_vec(&cached).map_err(|e| {
@@ -132,18 +261,23 @@ impl RedisCache {
             ))
         })?;
 
-        conn.set_ex(key, data, ttl).await
+        self.pool
+            .run(|mut conn| async move { conn.set_ex(key, data, ttl).await })
+            .await
     }
 
     /// Delete a cached risk assessment
+    ///
+    /// Returns the number of keys removed (0 or 1).
     pub async fn delete_risk_assessment(
         &self,
         chain: &str,
         contract_address: &str,
-    ) -> Result<(), redis::RedisError> {
+    ) -> Result<u64, redis::RedisError> {
         let key = risk_cache_key(chain, contract_address);
-        let mut conn = self.get_connection().await?;
-        conn.del(key).await
+        self.pool
+            .run(|mut conn| async move { conn.del(key).await })
+            .await
     }
 
     /// Batch get risk assessments using pipeline
@@ -155,26 +289,35 @@ impl RedisCache {
             return Ok(vec![]);
         }
 
-        let mut conn = self.get_connection().await?;
-        let mut pipeline = redis::pipe();
-
-        for (chain, address) in keys {
-            let key = risk_cache_key(chain, address);
-            pipeline.get(&key);
-        }
+        let redis_keys: Vec<String> = keys
+            .iter()
+            .map(|(chain, address)| risk_cache_key(chain, address))
+            .collect();
+        let groups = self.pipeline_groups(&redis_keys);
 
-        let results: Vec<Option<Vec<u8>>> = pipeline.query_async(&mut conn).await?;
+        let batches = groups.iter().map(|group| {
+            let mut pipeline = redis::pipe();
+            for &index in group {
+                pipeline.get(&redis_keys[index]);
+            }
+            self.pool.run(|mut conn| async move {
+                pipeline
+                    .query_async::<_, Vec<Option<Vec<u8>>>>(&mut conn)
+                    .await
+            })
+        });
+        let results = futures::future::try_join_all(batches).await?;
 
-        let responses: Vec<Option<RiskAssessmentResponse>> = results
-            .into_iter()
-            .map(|data| {
-                data.and_then(|d| {
+        let mut responses: Vec<Option<RiskAssessmentResponse>> = vec![None; keys.len()];
+        for (group, values) in groups.iter().zip(results) {
+            for (&index, data) in group.iter().zip(values) {
+                responses[index] = data.and_then(|d| {
                     rmp_serde::from_slice::<CachedRiskResponse>(&d)
                         .ok()
                         .map(|c| c.to_response())
-                })
-            })
-            .collect();
+                });
+            }
+        }
 
         Ok(responses)
     }
@@ -190,59 +333,126 @@ impl RedisCache {
         }
 
         let ttl = ttl_secs.unwrap_or(self.config.default_ttl_secs);
-        let mut conn = self.get_connection().await?;
-        let mut pipeline = redis::pipe();
-
-        for (chain, address, response) in items {
-            let key = risk_cache_key(chain, address);
-            let cached = CachedRiskResponse::from_response(response);
-            
-            if let Ok(data) = rmp_serde::to_vec(&cached) {
-                pipeline.set_ex(key, data, ttl);
+        let redis_keys: Vec<String> = items
+            .iter()
+            .map(|(chain, address, _)| risk_cache_key(chain, address))
+            .collect();
+        let groups = self.pipeline_groups(&redis_keys);
+
+        let batches = groups.iter().map(|group| {
+            let mut pipeline = redis::pipe();
+            for &index in group {
+                let cached = CachedRiskResponse::from_response(&items[index].2);
+                
+                if let Ok(data) = rmp_serde::to_vec(&cached) {
+                    pipeline.set_ex(&redis_keys[index], data, ttl).ignore();
+                }
             }
-        }
+            self.pool
+                .run(|mut conn| async move { pipeline.query_async::<_, ()>(&mut conn).await })
+        });
 
-        pipeline.query_async(&mut conn).await
+        futures::future::try_join_all(batches).await?;
+        Ok(())
     }
 
-    /// Increment rate limit counter
-    pub async fn increment_rate_limit(
+    /// Record a request in the sliding window at `key` if under `limit`
+    ///
+    /// The check and the insert run as one Lua script, so concurrent
+    /// replicas cannot overshoot the limit.
+    pub async fn sliding_window_hit(
         &self,
         key: &str,
-        window_secs: u64,
-    ) -> Result<i64, redis::RedisError> {
-        let mut conn = self.get_connection().await?;
-        
-        let count: i64 = conn.incr(key, 1).await?;
-        
-        // Set expiry on first increment
-        if count == 1 {
-            let _: () = conn.expire(key, window_secs as i64).await?;
+        limit: u64,
+        window: Duration,
+    ) -> Result<SlidingWindow, redis::RedisError> {
+        let mut invocation = self.rate_limit_script.prepare_invoke();
+        invocation
+            .key(format!("{}:{}", RATE_LIMIT_KEY_PREFIX, key))
+            .arg(limit)
+            .arg(window.as_millis() as u64)
+            .arg(uuid::Uuid::new_v4().to_string());
+
+        let (allowed, count, reset_ms): (u8, u64, u64) = self
+            .pool
+            .run(|mut conn| async move { invocation.invoke_async(&mut conn).await })
+            .await?;
+
+        Ok(SlidingWindow {
+            allowed: allowed == 1,
+            count,
+            reset_ms,
+        })
+    }
+
+    /// Add hourly usage deltas for one API key
+    pub async fn add_usage(
+        &self,
+        key_id: &str,
+        hours: &[(i64, UsageCounters)],
+        ttl_secs: u64,
+    ) -> Result<(), redis::RedisError> {
+        if hours.is_empty() {
+            return Ok(());
         }
-        
-        Ok(count)
+
+        let mut pipeline = redis::pipe();
+        for (hour, delta) in hours {
+            let key = usage_key(key_id, *hour);
+            for (field, value) in UsageCounters::FIELDS.iter().zip(delta.values()) {
+                if value > 0 {
+                    pipeline.hincr(&key, *field, value).ignore();
+                }
+            }
+            pipeline.expire(&key, ttl_secs as i64).ignore();
+        }
+
+        self.pool
+            .run(|mut conn| async move { pipeline.query_async::<_, ()>(&mut conn).await })
+            .await
     }
 
-    /// Check if rate limit is exceeded
-    pub async fn check_rate_limit(
+    /// Hourly usage of one API key, in the order of `hours`
+    pub async fn get_usage(
         &self,
-        key: &str,
-        max_requests: i64,
-    ) -> Result<bool, redis::RedisError> {
-        let mut conn = self.get_connection().await?;
-        let count: Option<i64> = conn.get(key).await?;
-        
-        match count {
-            Some(c) => Ok(c >= max_requests),
-            None => Ok(false),
+        key_id: &str,
+        hours: &[i64],
+    ) -> Result<Vec<UsageCounters>, redis::RedisError> {
+        if hours.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut pipeline = redis::pipe();
+        for hour in hours {
+            pipeline.hgetall(usage_key(key_id, *hour));
         }
+
+        let results: Vec<HashMap<String, u64>> = self
+            .pool
+            .run(|mut conn| async move { pipeline.query_async(&mut conn).await })
+            .await?;
+
+        Ok(results
+            .iter()
+            .map(|fields| {
+                UsageCounters::from_fields(fields.iter().map(|(field, value)| (field.as_str(), *value)))
+            })
+            .collect())
+    }
+
+    /// Raw JSON records of Redis-provisioned API keys
+    pub async fn get_api_keys(&self) -> Result<Vec<String>, redis::RedisError> {
+        self.pool
+            .run(|mut conn| async move { conn.hvals(API_KEYS_HASH).await })
+            .await
     }
 
     /// Get cache statistics
     pub async fn get_stats(&self) -> Result<RedisStats, redis::RedisError> {
-        let mut conn = self.get_connection().await?;
-        
-        let info: String = redis::cmd("INFO").query_async(&mut conn).await?;
+        let info: String = self
+            .pool
+            .run(|mut conn| async move { redis::cmd("INFO").query_async(&mut conn).await })
+            .await?;
         
         // Parse basic info
         let used_memory = info
@@ -288,15 +498,171 @@ impl RedisCache {
 
     /// Ping Redis server
     pub async fn ping(&self) -> Result<String, redis::RedisError> {
-        let mut conn = self.get_connection().await?;
-        redis::cmd("PING").query_async(&mut conn).await
+        self.pool
+            .run(|mut conn| async move { redis::cmd("PING").query_async(&mut conn).await })
+            .await
+    }
+
+    /// Delete every cached risk assessment on a chain
+    ///
+    /// Returns the number of keys removed.
+    pub async fn purge_chain(&self, chain: &str) -> Result<u64, redis::RedisError> {
+        self.purge_matching(&risk_cache_pattern(&CacheInvalidation::Chain {
+            chain: chain.to_string(),
+        }))
+        .await
+    }
+
+    /// Delete every cached risk assessment
+    ///
+    /// Only keys under [`RISK_KEY_PREFIX`] are touched, so other data sharing
+    /// the Redis instance survives. Returns the number of keys removed.
+    pub async fn purge_all(&self) -> Result<u64, redis::RedisError> {
+        self.purge_matching(&risk_cache_pattern(&CacheInvalidation::All))
+            .await
+    }
+
+    /// Remove the cached assessments covered by an invalidation
+    pub async fn invalidate(
+        &self,
+        invalidation: &CacheInvalidation,
+    ) -> Result<u64, redis::RedisError> {
+        match invalidation {
+            CacheInvalidation::Entry { chain, address } => {
+                self.delete_risk_assessment(chain, address).await
+            }
+            CacheInvalidation::Chain { chain } => self.purge_chain(chain).await,
+            CacheInvalidation::All => self.purge_all().await,
+        }
+    }
+
+    /// Incrementally SCAN for keys matching `pattern` and UNLINK them
+    ///
+    /// Avoids KEYS and FLUSHALL so a purge never blocks Redis or wipes
+    /// unrelated data. In cluster mode every master is scanned, since SCAN
+    /// only sees the keys of the node it runs on.
+    async fn purge_matching(&self, pattern: &str) -> Result<u64, redis::RedisError> {
+        let mut removed: u64 = 0;
+
+        for route in self.pool.node_routes().await? {
+            removed += self.purge_node(pattern, route.as_ref()).await?;
+        }
+
+        Ok(removed)
+    }
+
+    /// Purge matching keys from a single node
+    async fn purge_node(
+        &self,
+        pattern: &str,
+        route: Option<&redis::cluster_routing::Route>,
+    ) -> Result<u64, redis::RedisError> {
+        let mut cursor: u64 = 0;
+        let mut removed: u64 = 0;
+
+        loop {
+            let mut scan = redis::cmd("SCAN");
+            scan.arg(cursor)
+                .arg("MATCH")
+                .arg(pattern)
+                .arg("COUNT")
+                .arg(PURGE_SCAN_COUNT);
+            let (next, keys): (u64, Vec<String>) = self
+                .pool
+                .run(|mut conn| async move { conn.query_routed(&scan, route).await })
+                .await?;
+
+            if !keys.is_empty() {
+                let count: u64 = self
+                    .pool
+                    .run(|mut conn| async move {
+                        redis::cmd("UNLINK").arg(&keys).query_async(&mut conn).await
+                    })
+                    .await?;
+                removed += count;
+            }
+
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+
+        Ok(removed)
     }
 
-    /// Flush all cached data (use with caution)
-    pub async fn flush_all(&self) -> Result<(), redis::RedisError> {
-        let mut conn = self.get_connection().await?;
-        redis::cmd("FLUSHALL").query_async(&mut conn).await
+    /// Broadcast an invalidation to every replica
+    pub async fn publish_invalidation(
+        &self,
+        invalidation: &CacheInvalidation,
+    ) -> Result<(), redis::RedisError> {
+        let payload = serde_json::to_string(invalidation).map_err(|e| {
+            redis::RedisError::from((
+                redis::ErrorKind::IoError,
+                "Serialization error",
+                e.to_string(),
+            ))
+        })?;
+
+        let _receivers: i64 = self
+            .pool
+            .run(|mut conn| async move { conn.publish(INVALIDATION_CHANNEL, payload).await })
+            .await?;
+        Ok(())
     }
+
+    /// Subscribe to invalidations published by any replica
+    ///
+    /// Runs until the returned task is aborted, re-subscribing after
+    /// connection loss. `on_invalidation` is called for every valid message,
+    /// including the ones this replica published itself.
+    pub fn spawn_invalidation_listener<F>(&self, on_invalidation: F) -> tokio::task::JoinHandle<()>
+    where
+        F: Fn(CacheInvalidation) + Send + Sync + 'static,
+    {
+        let pool = Arc::clone(&self.pool);
+
+        tokio::spawn(async move {
+            loop {
+                // Resolve the target on every (re)subscribe to follow failovers
+                let result = match pool.master_client().await {
+                    Ok(client) => listen_invalidations(&client, &on_invalidation).await,
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = result {
+                    tracing::warn!("Invalidation listener disconnected: {}", e);
+                }
+                tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+        })
+    }
+}
+
+/// Consume invalidation messages until the subscription ends
+async fn listen_invalidations<F>(client: &Client, on_invalidation: &F) -> Result<(), redis::RedisError>
+where
+    F: Fn(CacheInvalidation),
+{
+    let mut pubsub = client.get_async_connection().await?.into_pubsub();
+    pubsub.subscribe(INVALIDATION_CHANNEL).await?;
+
+    let mut messages = pubsub.on_message();
+    while let Some(msg) = messages.next().await {
+        let payload: String = match msg.get_payload() {
+            Ok(payload) => payload,
+            Err(e) => {
+                tracing::warn!("Malformed invalidation payload: {}", e);
+                continue;
+            }
+        };
+
+        match serde_json::from_str::<CacheInvalidation>(&payload) {
+            Ok(invalidation) => on_invalidation(invalidation),
+            Err(e) => tracing::warn!("Ignoring invalid invalidation message: {}", e),
+        }
+    }
+
+    Ok(())
 }
 
 /// Cached risk response for serialization
@@ -308,6 +674,10 @@ struct CachedRiskResponse {
     pub findings: Vec<crate::models::RiskFinding>,
     pub timestamp: String,
     pub cached_at: i64,
+    /// Ruleset that produced the findings; empty for entries written before
+    /// versioning, which therefore never match the current ruleset
+    #[serde(default)]
+    pub ruleset_version: String,
 }
 
 impl CachedRiskResponse {
@@ -319,9 +689,16 @@ impl CachedRiskResponse {
             findings: response.findings.clone(),
             timestamp: response.timestamp.clone(),
             cached_at: chrono::Utc::now().timestamp(),
+            ruleset_version: response.ruleset_version.clone(),
         }
     }
 
+    /// Time since the entry was written; zero when the writer's clock is ahead
+    fn age(&self) -> Duration {
+        let secs = chrono::Utc::now().timestamp().saturating_sub(self.cached_at);
+        Duration::from_secs(secs.max(0) as u64)
+    }
+
     fn to_response(&self) -> RiskAssessmentResponse {
         use crate::models::RiskLevel;
         
@@ -337,6 +714,16 @@ impl CachedRiskResponse {
             findings: self.findings.clone(),
             processing_time_ms: 0.0, // Cached response doesn't include processing time
             timestamp: self.timestamp.clone(),
+            ruleset_version: self.ruleset_version.clone(),
+            // Scores are recomputed for the requested model when served
+            scoring_model: Default::default(),
+            explanation: Default::default(),
+            rules: Default::default(),
+            partial: false,
+            timed_out_rules: Vec::new(),
+            rule_timings_ms: Default::default(),
+            policy: None,
+            compiler: None,
         }
     }
 }
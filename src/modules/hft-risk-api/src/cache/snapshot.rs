@@ -0,0 +1,237 @@
+//! Cache Snapshot - Persist the in-process cache across restarts
+//!
+//! The local assessment cache is dumped to a compact MessagePack file on
+//! shutdown and reloaded on startup, so a fresh deploy does not start from a
+//! cold cache. Expired entries and entries produced by a different ruleset
+//! are dropped on load.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::CachedRiskResponse;
+use crate::models::RiskAssessmentResponse;
+
+/// Snapshot file format version
+const SNAPSHOT_FORMAT: u32 = 1;
+
+/// A single cached assessment with its age
+#[derive(Debug, Clone)]
+pub struct SnapshotEntry {
+    /// Local cache key
+    pub key: String,
+    /// Cached response
+    pub response: RiskAssessmentResponse,
+    /// Time since the entry was cached
+    pub age: Duration,
+}
+
+/// On-disk snapshot of the local cache
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CacheSnapshot {
+    format: u32,
+    /// Unix timestamp when the snapshot was written
+    written_at: i64,
+    entries: Vec<(String, CachedRiskResponse)>,
+}
+
+impl CacheSnapshot {
+    /// Build a snapshot from live cache entries
+    pub fn from_entries(entries: impl IntoIterator<Item = SnapshotEntry>) -> Self {
+        let now = chrono::Utc::now().timestamp();
+
+        let entries = entries
+            .into_iter()
+            .map(|entry| {
+                let mut cached = CachedRiskResponse::from_response(&entry.response);
+                cached.cached_at = now - entry.age.as_secs() as i64;
+                (entry.key, cached)
+            })
+            .collect();
+
+        Self {
+            format: SNAPSHOT_FORMAT,
+            written_at: now,
+            entries,
+        }
+    }
+
+    /// Number of entries in the snapshot
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the snapshot has no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Keep only entries still within `ttl` and produced by `ruleset_version`
+    pub fn into_entries(self, ruleset_version: &str, ttl: Duration) -> Vec<SnapshotEntry> {
+        let now = chrono::Utc::now().timestamp();
+
+        self.entries
+            .into_iter()
+            .filter(|(_, cached)| cached.ruleset_version == ruleset_version)
+            .filter_map(|(key, cached)| {
+                let age = Duration::from_secs(now.saturating_sub(cached.cached_at).max(0) as u64);
+                if age >= ttl {
+                    return None;
+                }
+                Some(SnapshotEntry {
+                    key,
+                    response: cached.to_response(),
+                    age,
+                })
+            })
+            .collect()
+    }
+
+    /// Write the snapshot atomically (temp file + rename)
+    pub fn write_to(&self, path: &Path) -> io::Result<()> {
+        let data = rmp_serde::to_vec(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, data)?;
+        fs::rename(&tmp, path)
+    }
+
+    /// Read a snapshot written by [`CacheSnapshot::write_to`]
+    pub fn read_from(path: &Path) -> io::Result<Self> {
+        let data = fs::read(path)?;
+        let snapshot: Self = rmp_serde::from_slice(&data)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        if snapshot.format != SNAPSHOT_FORMAT {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unsupported snapshot format {}", snapshot.format),
+            ));
+        }
+
+        Ok(snapshot)
+    }
+}
+
+/// Parse a warm-list file of `chain:address` lines
+///
+/// Blank lines and lines starting with `#` are ignored.
+pub fn read_warm_list(path: &Path) -> io::Result<Vec<(String, String)>> {
+    let contents = fs::read_to_string(path)?;
+
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let (chain, address) = line.split_once(':')?;
+            let (chain, address) = (chain.trim(), address.trim());
+            if chain.is_empty() || address.is_empty() {
+                tracing::warn!("Skipping malformed warm-list line: {}", line);
+                return None;
+            }
+            Some((chain.to_string(), address.to_string()))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Location, RiskFinding, RiskLevel, Severity};
+
+    fn response(ruleset_version: &str) -> RiskAssessmentResponse {
+        RiskAssessmentResponse {
+            contract_address: "0xabc".to_string(),
+            risk_score: 42.0,
+            risk_level: RiskLevel::Medium,
+            findings: vec![
+                RiskFinding {
+                    rule_id: "DELEG-001".to_string(),
+                    category: "OWASP-SC03".to_string(),
+                    severity: Severity::High,
+                    description: "delegatecall".to_string(),
+                    location: Some(Location::Bytecode { offset: 17, selector: None }),
+                    origin: Default::default(),
+                    confidence: 0.85,
+                },
+                RiskFinding {
+                    rule_id: "INPUT-001".to_string(),
+                    category: "OWASP-SC10".to_string(),
+                    severity: Severity::Medium,
+                    description: "validation".to_string(),
+                    location: None,
+                    origin: Default::default(),
+                    confidence: 0.5,
+                },
+            ],
+            processing_time_ms: 1.0,
+            timestamp: "2026-01-01T00:00:00Z".to_string(),
+            ruleset_version: ruleset_version.to_string(),
+            scoring_model: Default::default(),
+            explanation: Default::default(),
+            rules: Default::default(),
+            partial: false,
+            timed_out_rules: Vec::new(),
+            rule_timings_ms: Default::default(),
+            policy: None,
+            compiler: None,
+        }
+    }
+
+    #[test]
+    fn test_snapshot_roundtrip_drops_stale_entries() {
+        let snapshot = CacheSnapshot::from_entries(vec![
+            SnapshotEntry {
+                key: "ethereum:0xabc".to_string(),
+                response: response("v1"),
+                age: Duration::from_secs(10),
+            },
+            SnapshotEntry {
+                key: "ethereum:0xold".to_string(),
+                response: response("v1"),
+                age: Duration::from_secs(600),
+            },
+            SnapshotEntry {
+                key: "bsc:0xabc".to_string(),
+                response: response("v0"),
+                age: Duration::from_secs(10),
+            },
+        ]);
+
+        let path = std::env::temp_dir().join(format!("snapshot-{}.msgpack", uuid::Uuid::new_v4()));
+        snapshot.write_to(&path).unwrap();
+        let loaded = CacheSnapshot::read_from(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.len(), 3);
+        let entries = loaded.into_entries("v1", Duration::from_secs(300));
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].key, "ethereum:0xabc");
+        assert_eq!(entries[0].response.risk_score, 42.0);
+        let findings = &entries[0].response.findings;
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0].location, Some(Location::Bytecode { offset: 17, selector: None }));
+        assert_eq!(findings[1].location, None);
+    }
+
+    #[test]
+    fn test_read_warm_list() {
+        let path = std::env::temp_dir().join(format!("warm-{}.txt", uuid::Uuid::new_v4()));
+        fs::write(&path, "# hot tokens\nethereum:0xA0b8\n\nbsc : 0x55d3\nmalformed\n").unwrap();
+        let list = read_warm_list(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            list,
+            vec![
+                ("ethereum".to_string(), "0xA0b8".to_string()),
+                ("bsc".to_string(), "0x55d3".to_string()),
+            ]
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use serde::{Deserialize, Serialize};
+use crate::cache::RedisTopology;
 
 /// Application configuration
 #[derive(Debug, Clone)]
@@ -6,8 +6,61 @@ pub struct AppConfig {
     pub bind_address: String,
     pub target_latency_ms: u64,
     pub redis_url: String,
+    pub redis_pool_size: usize,
+    /// Standalone, cluster or sentinel deployment
+    pub redis_topology: RedisTopology,
     pub max_concurrent_requests: usize,
     pub worker_threads: usize,
+    /// Snapshot file for the local cache, saved on shutdown and loaded on startup
+    pub cache_snapshot_path: Option<String>,
+    /// File of `chain:address` lines to pre-assess before reporting ready
+    pub cache_warm_list_path: Option<String>,
+    pub rate_limit_enabled: bool,
+    pub rate_limit_window_secs: u64,
+    /// Requests per window for each API key
+    pub rate_limit_per_key: u64,
+    /// Requests per window for each anonymous client IP
+    pub rate_limit_per_ip: u64,
+    /// Trust `X-Forwarded-For` from a fronting proxy
+    pub trust_forwarded_for: bool,
+    /// Serve the `/api/v1/admin/` cache endpoints
+    pub admin_api_enabled: bool,
+    /// Reject anonymous requests to the assessment endpoints
+    pub auth_required: bool,
+    /// JSON file of hashed API keys
+    pub auth_keys_file: Option<String>,
+    /// Also load API keys from the Redis `auth:keys` hash
+    pub auth_keys_redis: bool,
+    /// Interval between reloads of Redis-provisioned keys
+    pub auth_refresh_secs: u64,
+    /// Interval between usage flushes to Redis
+    pub usage_flush_secs: u64,
+    /// Directory for hourly usage exports
+    pub usage_export_dir: Option<String>,
+    /// Usage export format (`csv` or `jsonl`)
+    pub usage_export_format: String,
+    /// Identifies this replica in usage exports
+    pub replica_id: String,
+    /// Default scoring model name
+    pub scoring_model: String,
+    /// Use SIMD scoring kernels; `false` forces the scalar path
+    pub simd_enabled: bool,
+    /// Directory of TOML/JSON policy profiles added to the built-in ones
+    pub policy_dir: Option<String>,
+    /// Policy applied when neither the request nor the API key picks one
+    pub default_policy: Option<String>,
+    /// TOML/JSON file selecting the rules to scan with and their overrides
+    pub rules_config: Option<String>,
+    /// Deadline for scanning one contract; later rules are reported as timed out
+    pub scanner_timeout_ms: u64,
+    /// Threads running scanner rules in parallel; 0 uses one per CPU
+    pub scanner_threads: usize,
+    /// Inputs smaller than this many bytes have their rules run in turn
+    pub scanner_parallel_min_bytes: usize,
+    /// Directory of YAML/TOML custom rule files
+    pub rules_dir: Option<String>,
+    /// Interval between checks of `rules_dir` for changes; 0 disables reloading
+    pub rules_reload_secs: u64,
 }
 
 impl AppConfig {
@@ -22,6 +75,11 @@ impl AppConfig {
                 .unwrap_or(10),
             redis_url: std::env::var("REDIS_URL")
                 .unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string()),
+            redis_pool_size: std::env::var("REDIS_POOL_SIZE")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(10),
+            redis_topology: redis_topology_from_env(),
             max_concurrent_requests: std::env::var("MAX_CONCURRENT_REQUESTS")
                 .ok()
                 .and_then(|s| s.parse().ok())
@@ -29,7 +87,156 @@ impl AppConfig {
             worker_threads: std::env::var("WORKER_THREADS")
                 .ok()
                 .and_then(|s| s.parse().ok())
-                .unwrap_or_else(|| num_cpus::get()),
+                .unwrap_or_else(num_cpus::get),
+            cache_snapshot_path: std::env::var("CACHE_SNAPSHOT_PATH").ok(),
+            cache_warm_list_path: std::env::var("CACHE_WARM_LIST").ok(),
+            rate_limit_enabled: std::env::var("RATE_LIMIT_ENABLED")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(true),
+            rate_limit_window_secs: std::env::var("RATE_LIMIT_WINDOW_SECS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(60),
+            rate_limit_per_key: std::env::var("RATE_LIMIT_PER_KEY")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(1200),
+            rate_limit_per_ip: std::env::var("RATE_LIMIT_PER_IP")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(300),
+            trust_forwarded_for: std::env::var("TRUST_FORWARDED_FOR")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(false),
+            admin_api_enabled: std::env::var("ADMIN_API_ENABLED")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(false),
+            auth_required: std::env::var("AUTH_REQUIRED")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(false),
+            auth_keys_file: std::env::var("AUTH_KEYS_FILE").ok(),
+            auth_keys_redis: std::env::var("AUTH_KEYS_REDIS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(false),
+            auth_refresh_secs: std::env::var("AUTH_REFRESH_SECS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(30),
+            usage_flush_secs: std::env::var("USAGE_FLUSH_SECS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(10),
+            usage_export_dir: std::env::var("USAGE_EXPORT_DIR").ok(),
+            usage_export_format: std::env::var("USAGE_EXPORT_FORMAT")
+                .unwrap_or_else(|_| "jsonl".to_string()),
+            replica_id: std::env::var("REPLICA_ID")
+                .or_else(|_| std::env::var("HOSTNAME"))
+                .unwrap_or_else(|_| "local".to_string()),
+            scoring_model: std::env::var("SCORING_MODEL")
+                .unwrap_or_else(|_| "noisy_or".to_string()),
+            simd_enabled: std::env::var("SIMD_ENABLED")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(true),
+            policy_dir: std::env::var("POLICY_DIR").ok(),
+            default_policy: std::env::var("DEFAULT_POLICY").ok(),
+            rules_config: std::env::var("RULES_CONFIG").ok(),
+            scanner_timeout_ms: std::env::var("SCANNER_TIMEOUT_MS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(3000),
+            scanner_threads: std::env::var("SCANNER_THREADS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(0),
+            scanner_parallel_min_bytes: std::env::var("SCANNER_PARALLEL_MIN_BYTES")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(8192),
+            rules_dir: std::env::var("RULES_DIR").ok(),
+            rules_reload_secs: std::env::var("RULES_RELOAD_SECS")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(30),
         }
     }
 }
+
+/// Build the Redis topology from `REDIS_MODE` and its companion variables
+///
+/// `REDIS_MODE=cluster` reads seed nodes from `REDIS_CLUSTER_NODES`;
+/// `REDIS_MODE=sentinel` reads `REDIS_SENTINELS` and `REDIS_SENTINEL_SERVICE`.
+/// Anything else uses `REDIS_URL` directly.
+fn redis_topology_from_env() -> RedisTopology {
+    parse_redis_topology(
+        std::env::var("REDIS_MODE").ok().as_deref(),
+        std::env::var("REDIS_CLUSTER_NODES").ok().as_deref(),
+        std::env::var("REDIS_SENTINELS").ok().as_deref(),
+        std::env::var("REDIS_SENTINEL_SERVICE").ok().as_deref(),
+    )
+}
+
+fn parse_redis_topology(
+    mode: Option<&str>,
+    cluster_nodes: Option<&str>,
+    sentinels: Option<&str>,
+    sentinel_service: Option<&str>,
+) -> RedisTopology {
+    let split = |list: Option<&str>| -> Vec<String> {
+        list.unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+
+    match mode.map(str::to_ascii_lowercase).as_deref() {
+        Some("cluster") => RedisTopology::Cluster {
+            nodes: split(cluster_nodes),
+        },
+        Some("sentinel") => RedisTopology::Sentinel {
+            sentinels: split(sentinels),
+            service_name: sentinel_service.unwrap_or("mymaster").to_string(),
+        },
+        _ => RedisTopology::Standalone,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_redis_topology() {
+        assert_eq!(parse_redis_topology(None, None, None, None), RedisTopology::Standalone);
+
+        assert_eq!(
+            parse_redis_topology(
+                Some("cluster"),
+                Some("redis://10.0.0.1:7000, redis://10.0.0.2:7000,"),
+                None,
+                None,
+            ),
+            RedisTopology::Cluster {
+                nodes: vec![
+                    "redis://10.0.0.1:7000".to_string(),
+                    "redis://10.0.0.2:7000".to_string(),
+                ],
+            }
+        );
+
+        assert_eq!(
+            parse_redis_topology(Some("Sentinel"), None, Some("redis://s1:26379"), None),
+            RedisTopology::Sentinel {
+                sentinels: vec!["redis://s1:26379".to_string()],
+                service_name: "mymaster".to_string(),
+            }
+        );
+    }
+}
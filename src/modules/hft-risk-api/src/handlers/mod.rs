@@ -1,6 +1,9 @@
 //! HTTP Request Handlers
 
+pub mod policy_handler;
 pub mod risk_handler;
+pub mod rules_handler;
+pub mod usage_handler;
 
 use std::time::Instant;
 
@@ -23,15 +26,26 @@ pub async fn assess_risk(
         risk_level: RiskLevel::Medium,
         findings: vec![
             RiskFinding {
+                rule_id: "AC-001".to_string(),
                 category: "ACCESS_CONTROL".to_string(),
                 severity: Severity::Medium,
                 description: "Owner privileges detected".to_string(),
                 location: None,
+                origin: Default::default(),
                 confidence: 0.85,
             },
         ],
         processing_time_ms: start.elapsed().as_secs_f64() * 1000.0,
         timestamp: chrono::Utc::now().to_rfc3339(),
+        ruleset_version: String::new(),
+        scoring_model: Default::default(),
+        explanation: Default::default(),
+        rules: Default::default(),
+        partial: false,
+        timed_out_rules: Vec::new(),
+        rule_timings_ms: Default::default(),
+        policy: None,
+        compiler: None,
     };
     
     let body = serde_json::to_string(&response).unwrap();
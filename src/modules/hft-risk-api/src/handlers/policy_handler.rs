@@ -0,0 +1,77 @@
+//! Policy Handler - Policy selection and the policy listing endpoint
+
+use std::sync::OnceLock;
+
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::{Request, Response};
+use serde_json::json;
+
+use crate::auth::ApiKey;
+use crate::models::RiskAssessmentResponse;
+use crate::policy::{Policy, PolicyRegistry, PolicySelection, TokenFacts};
+
+/// Global policy registry, set once at startup
+static POLICIES: OnceLock<PolicyRegistry> = OnceLock::new();
+
+/// Policy applied when neither the request nor the API key picks one
+static DEFAULT_POLICY: OnceLock<String> = OnceLock::new();
+
+/// Initialize the policy registry and the optional default policy
+pub fn init_policies(registry: PolicyRegistry, default_policy: Option<String>) {
+    let _ = POLICIES.set(registry);
+    if let Some(default_policy) = default_policy {
+        let _ = DEFAULT_POLICY.set(default_policy);
+    }
+}
+
+/// The policy registry; built-in profiles only until initialized
+pub fn policies() -> &'static PolicyRegistry {
+    POLICIES.get_or_init(PolicyRegistry::default)
+}
+
+/// Policy chosen by the request, else the API key's default, else the
+/// configured default; `None` when no policy applies
+pub fn select_policy(
+    selection: Option<&PolicySelection>,
+    api_key: Option<&ApiKey>,
+) -> Result<Option<Policy>, String> {
+    let fallback = api_key
+        .and_then(|key| key.policy_profile.clone())
+        .or_else(|| DEFAULT_POLICY.get().cloned())
+        .map(PolicySelection::Name);
+
+    selection
+        .or(fallback.as_ref())
+        .map(|selection| policies().resolve(selection))
+        .transpose()
+}
+
+/// Attach the decision of `policy` to an assessment
+pub fn apply_policy(response: &mut RiskAssessmentResponse, policy: &Policy, facts: Option<&TokenFacts>) {
+    let decision = policy.evaluate(response, facts.unwrap_or(&TokenFacts::default()));
+    response.policy = Some(decision);
+}
+
+/// Available policies: `GET /api/v1/policies`
+pub async fn list_policies(
+    _req: Request<hyper::body::Incoming>,
+) -> Result<Response<Full<Bytes>>, std::convert::Infallible> {
+    let body = json!({
+        "policies": policies()
+            .iter()
+            .map(|policy| json!({
+                "id": policy.id,
+                "name": policy.name,
+                "description": policy.description,
+                "clauses": policy.clauses,
+            }))
+            .collect::<Vec<_>>(),
+        "default": DEFAULT_POLICY.get(),
+    });
+
+    Ok(Response::builder()
+        .header("Content-Type", "application/json")
+        .body(Full::new(Bytes::from(body.to_string())))
+        .unwrap())
+}
@@ -1,5 +1,7 @@
 //! Risk Handler - HTTP request handlers for risk assessment endpoints
 
+use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Instant;
 
 use bytes::Bytes;
@@ -7,107 +9,307 @@ use http_body_util::{BodyExt, Full};
 use hyper::{Request, Response};
 use serde_json::json;
 
-use crate::models::{RiskAssessmentRequest, RiskAssessmentResponse, RiskLevel};
+use crate::auth::ApiKey;
+use crate::cache::CacheInvalidation;
+use crate::handlers::policy_handler::{apply_policy, select_policy};
+use crate::handlers::usage_handler::record_usage;
+use crate::models::{
+    BatchAssessmentRequest, BatchAssessmentResponse, ErrorResponse, RiskAssessmentRequest, UsageCounters,
+};
 use crate::risk::engine::RiskEngine;
+use crate::policy::PolicySelection;
+use crate::risk::scoring::{ScoringModel, ScoringSelection};
+use crate::scanner::input::SourceInput;
+use crate::scanner::selection::{EffectiveRules, RuleSelection};
+
+/// Most contracts accepted in one batch request
+const MAX_BATCH_SIZE: usize = 1000;
+
+/// Largest submitted source accepted, summed over all files
+const MAX_SOURCE_BYTES: usize = 4 * 1024 * 1024;
 
 /// Global risk engine instance (singleton pattern)
 use std::sync::OnceLock;
 static RISK_ENGINE: OnceLock<RiskEngine> = OnceLock::new();
 
 /// Initialize the risk engine
-pub fn init_engine() {
-    let _ = RISK_ENGINE.get_or_init(RiskEngine::new);
+pub fn init_engine(engine: RiskEngine) {
+    let _ = RISK_ENGINE.set(engine);
 }
 
 /// Get the risk engine instance
-fn get_engine() -> &'static RiskEngine {
+pub fn get_engine() -> &'static RiskEngine {
     RISK_ENGINE.get().expect("Risk engine not initialized")
 }
 
+/// Build a JSON [`ErrorResponse`]
+pub fn error_response(
+    status: u16,
+    code: &str,
+    error: &str,
+    details: Option<String>,
+) -> Response<Full<Bytes>> {
+    let body = ErrorResponse {
+        code: code.to_string(),
+        error: error.to_string(),
+        details,
+    };
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Full::new(Bytes::from(serde_json::to_string(&body).unwrap_or_default())))
+        .unwrap()
+}
+
+/// Parse `a=1&b=2` query parameters
+pub fn query_params(uri: &hyper::Uri) -> HashMap<String, String> {
+    uri.query()
+        .unwrap_or("")
+        .split('&')
+        .filter_map(|pair| {
+            let mut parts = pair.splitn(2, '=');
+            let key = parts.next()?.to_string();
+            let value = parts.next()?.to_string();
+            Some((key, value))
+        })
+        .collect()
+}
+
+/// Scoring model chosen by the request, else the API key's default, else the engine's
+fn select_scoring_model(
+    selection: Option<&ScoringSelection>,
+    api_key: Option<&ApiKey>,
+) -> Result<ScoringModel, String> {
+    let key_default = api_key
+        .and_then(|key| key.scoring_profile.clone())
+        .map(ScoringSelection::Name);
+
+    get_engine().resolve_scoring(selection.or(key_default.as_ref()))
+}
+
+/// Rules narrowed by the API key's selection, then the request's
+fn select_rules(
+    selection: Option<&RuleSelection>,
+    api_key: Option<&ApiKey>,
+) -> Result<EffectiveRules, String> {
+    let layers: Vec<&RuleSelection> = api_key
+        .and_then(|key| key.rules.as_ref())
+        .into_iter()
+        .chain(selection)
+        .collect();
+
+    get_engine().resolve_rules(&layers)
+}
+
+/// Check submitted source is scannable and within [`MAX_SOURCE_BYTES`]
+fn validate_source(source: Option<&SourceInput>) -> Result<(), String> {
+    let Some(source) = source else {
+        return Ok(());
+    };
+    if source.len() > MAX_SOURCE_BYTES {
+        return Err(format!("source is {} bytes, at most {} accepted", source.len(), MAX_SOURCE_BYTES));
+    }
+    source.validate()
+}
+
 /// Assess risk for a contract
 pub async fn assess_risk(
     req: Request<hyper::body::Incoming>,
 ) -> Result<Response<Full<Bytes>>, std::convert::Infallible> {
-    let start = Instant::now();
+    let api_key = req.extensions().get::<Arc<ApiKey>>().cloned();
 
     // Parse request body
     let body_bytes = match req.collect().await {
         Ok(body) => body.to_bytes(),
         Err(_) => {
-            let error_response = json!({
-                "error": "Failed to read request body"
-            });
-            return Ok(Response::builder()
-                .status(400)
-                .header("Content-Type", "application/json")
-                .body(Full::new(Bytes::from(error_response.to_string())))
-                .unwrap());
+            return Ok(error_response(400, "invalid_request", "Failed to read request body", None));
         }
     };
 
     let request: RiskAssessmentRequest = match serde_json::from_slice(&body_bytes) {
         Ok(req) => req,
         Err(e) => {
-            let error_response = json!({
-                "error": "Invalid request body",
-                "details": e.to_string()
-            });
-            return Ok(Response::builder()
-                .status(400)
-                .header("Content-Type", "application/json")
-                .body(Full::new(Bytes::from(error_response.to_string())))
-                .unwrap());
+            return Ok(error_response(400, "invalid_request", "Invalid request body", Some(e.to_string())));
         }
     };
 
+    let model = match select_scoring_model(request.scoring_model.as_ref(), api_key.as_deref()) {
+        Ok(model) => model,
+        Err(e) => return Ok(error_response(400, "invalid_request", "Invalid scoring model", Some(e))),
+    };
+    let policy = match select_policy(request.policy.as_ref(), api_key.as_deref()) {
+        Ok(policy) => policy,
+        Err(e) => return Ok(error_response(400, "invalid_request", "Invalid policy", Some(e))),
+    };
+    let rules = match select_rules(request.rules.as_ref(), api_key.as_deref()) {
+        Ok(rules) => rules,
+        Err(e) => return Ok(error_response(400, "invalid_request", "Invalid rule selection", Some(e))),
+    };
+    if let Err(e) = validate_source(request.source.as_ref()) {
+        return Ok(error_response(400, "invalid_request", "Invalid source", Some(e)));
+    }
+    let token_data = request.token_data.clone();
+
     // Perform risk assessment
     let engine = get_engine();
-    let response = engine.assess(request).await;
+    let (mut response, provenance) = engine.assess_with_provenance(request, &model, &rules).await;
+    if let Some(policy) = &policy {
+        apply_policy(&mut response, policy, token_data.as_ref());
+    }
 
     let body = match serde_json::to_string(&response) {
         Ok(json) => json,
         Err(e) => {
-            let error_response = json!({
-                "error": "Failed to serialize response",
-                "details": e.to_string()
-            });
-            return Ok(Response::builder()
-                .status(500)
-                .header("Content-Type", "application/json")
-                .body(Full::new(Bytes::from(error_response.to_string())))
-                .unwrap());
+            return Ok(error_response(500, "internal_error", "Failed to serialize response", Some(e.to_string())));
         }
     };
+    record_usage(api_key.as_deref(), UsageCounters::for_assessment(provenance, body.len() as u64));
 
     // Add performance headers
-    Ok(Response::builder()
+    let mut builder = Response::builder()
         .header("Content-Type", "application/json")
         .header("X-Processing-Time-Ms", format!("{:.3}", response.processing_time_ms))
-        .header("X-Cache-Status", if response.processing_time_ms < 1.0 { "HIT" } else { "MISS" })
+        .header("X-Cache-Status", if provenance.is_cache_hit() { "HIT" } else { "MISS" });
+    if let Some(policy) = &response.policy {
+        builder = builder.header("X-Policy-Decision", policy.decision.as_str());
+    }
+
+    Ok(builder.body(Full::new(Bytes::from(body))).unwrap())
+}
+
+/// Assess many contracts in one request: `POST /api/v1/risk/batch`
+///
+/// The scoring model and policy apply to every contract; scores for the
+/// whole batch are computed together with the batch SIMD kernels.
+pub async fn assess_batch(
+    req: Request<hyper::body::Incoming>,
+) -> Result<Response<Full<Bytes>>, std::convert::Infallible> {
+    let start = Instant::now();
+    let api_key = req.extensions().get::<Arc<ApiKey>>().cloned();
+
+    let body_bytes = match req.collect().await {
+        Ok(body) => body.to_bytes(),
+        Err(_) => {
+            return Ok(error_response(400, "invalid_request", "Failed to read request body", None));
+        }
+    };
+
+    let batch: BatchAssessmentRequest = match serde_json::from_slice(&body_bytes) {
+        Ok(batch) => batch,
+        Err(e) => {
+            return Ok(error_response(400, "invalid_request", "Invalid request body", Some(e.to_string())));
+        }
+    };
+    if batch.contracts.is_empty() || batch.contracts.len() > MAX_BATCH_SIZE {
+        return Ok(error_response(
+            400,
+            "invalid_request",
+            "Invalid batch size",
+            Some(format!("contracts must hold 1 to {} entries", MAX_BATCH_SIZE)),
+        ));
+    }
+
+    let model = match select_scoring_model(batch.scoring_model.as_ref(), api_key.as_deref()) {
+        Ok(model) => model,
+        Err(e) => return Ok(error_response(400, "invalid_request", "Invalid scoring model", Some(e))),
+    };
+    let policy = match select_policy(batch.policy.as_ref(), api_key.as_deref()) {
+        Ok(policy) => policy,
+        Err(e) => return Ok(error_response(400, "invalid_request", "Invalid policy", Some(e))),
+    };
+    let rules = match select_rules(batch.rules.as_ref(), api_key.as_deref()) {
+        Ok(rules) => rules,
+        Err(e) => return Ok(error_response(400, "invalid_request", "Invalid rule selection", Some(e))),
+    };
+
+    let mut token_data = Vec::with_capacity(batch.contracts.len());
+    let requests = batch
+        .contracts
+        .into_iter()
+        .map(|item| {
+            token_data.push(item.token_data);
+            RiskAssessmentRequest {
+                contract_address: item.contract_address,
+                chain: item.chain,
+                transaction_data: None,
+                amount: None,
+                scoring_model: None,
+                policy: None,
+                token_data: None,
+                rules: None,
+                source: None,
+            }
+        })
+        .collect();
+
+    let assessed = get_engine().assess_batch(requests, &model, &rules).await;
+
+    let mut usage = UsageCounters::default();
+    let mut results = Vec::with_capacity(assessed.len());
+    for ((mut response, provenance), facts) in assessed.into_iter().zip(token_data) {
+        if let Some(policy) = &policy {
+            apply_policy(&mut response, policy, facts.as_ref());
+        }
+        usage.add(&UsageCounters::for_assessment(provenance, 0));
+        results.push(response);
+    }
+
+    let body = BatchAssessmentResponse {
+        count: results.len(),
+        results,
+        processing_time_ms: start.elapsed().as_secs_f64() * 1000.0,
+    };
+    let body = match serde_json::to_string(&body) {
+        Ok(json) => json,
+        Err(e) => {
+            return Ok(error_response(500, "internal_error", "Failed to serialize response", Some(e.to_string())));
+        }
+    };
+    usage.batch_items = usage.assessments;
+    usage.bytes_served = body.len() as u64;
+    record_usage(api_key.as_deref(), usage);
+
+    Ok(Response::builder()
+        .header("Content-Type", "application/json")
+        .header("X-Processing-Time-Ms", format!("{:.3}", start.elapsed().as_secs_f64() * 1000.0))
         .body(Full::new(Bytes::from(body)))
         .unwrap())
 }
 
+/// Rule selection from comma-separated `include`/`exclude` query parameters
+fn rules_from_query(params: &HashMap<String, String>) -> Option<RuleSelection> {
+    let list = |name: &str| -> Vec<String> {
+        params
+            .get(name)
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|rule| !rule.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let selection = RuleSelection {
+        include: list("include"),
+        exclude: list("exclude"),
+        ..RuleSelection::default()
+    };
+    (selection != RuleSelection::default()).then_some(selection)
+}
+
 /// Assess a specific contract by address (GET endpoint)
 pub async fn assess_contract(
     req: Request<hyper::body::Incoming>,
 ) -> Result<Response<Full<Bytes>>, std::convert::Infallible> {
     let start = Instant::now();
+    let api_key = req.extensions().get::<Arc<ApiKey>>().cloned();
 
     // Extract contract address from query params or path
     let uri = req.uri();
-    let query = uri.query().unwrap_or("");
-    
-    // Parse query parameters
-    let params: std::collections::HashMap<String, String> = query
-        .split('&')
-        .filter_map(|pair| {
-            let mut parts = pair.splitn(2, '=');
-            let key = parts.next()?.to_string();
-            let value = parts.next()?.to_string();
-            Some((key, value))
-        })
-        .collect();
+    let params = query_params(uri);
 
     let contract_address = params.get("address").cloned().unwrap_or_else(|| {
         // Try to extract from path
@@ -123,35 +325,52 @@ pub async fn assess_contract(
         chain,
         transaction_data: None,
         amount: None,
+        scoring_model: params.get("scoring_model").cloned().map(ScoringSelection::Name),
+        policy: params.get("policy").cloned().map(PolicySelection::Name),
+        token_data: None,
+        rules: rules_from_query(&params),
+        source: None,
+    };
+
+    let model = match select_scoring_model(request.scoring_model.as_ref(), api_key.as_deref()) {
+        Ok(model) => model,
+        Err(e) => return Ok(error_response(400, "invalid_request", "Invalid scoring model", Some(e))),
+    };
+    let policy = match select_policy(request.policy.as_ref(), api_key.as_deref()) {
+        Ok(policy) => policy,
+        Err(e) => return Ok(error_response(400, "invalid_request", "Invalid policy", Some(e))),
+    };
+    let rules = match select_rules(request.rules.as_ref(), api_key.as_deref()) {
+        Ok(rules) => rules,
+        Err(e) => return Ok(error_response(400, "invalid_request", "Invalid rule selection", Some(e))),
     };
 
     let engine = get_engine();
-    let response = engine.assess(request).await;
+    let (mut response, provenance) = engine.assess_with_provenance(request, &model, &rules).await;
+    if let Some(policy) = &policy {
+        apply_policy(&mut response, policy, None);
+    }
 
     let body = match serde_json::to_string(&response) {
         Ok(json) => json,
         Err(e) => {
-            let error_response = json!({
-                "error": "Failed to serialize response",
-                "details": e.to_string()
-            });
-            return Ok(Response::builder()
-                .status(500)
-                .header("Content-Type", "application/json")
-                .body(Full::new(Bytes::from(error_response.to_string())))
-                .unwrap());
+            return Ok(error_response(500, "internal_error", "Failed to serialize response", Some(e.to_string())));
         }
     };
+    record_usage(api_key.as_deref(), UsageCounters::for_assessment(provenance, body.len() as u64));
 
     let total_time = start.elapsed().as_secs_f64() * 1000.0;
 
-    Ok(Response::builder()
+    let mut builder = Response::builder()
         .header("Content-Type", "application/json")
         .header("X-Total-Time-Ms", format!("{:.3}", total_time))
         .header("X-Risk-Score", format!("{:.1}", response.risk_score))
-        .header("X-Risk-Level", format!("{:?}", response.risk_level))
-        .body(Full::new(Bytes::from(body)))
-        .unwrap())
+        .header("X-Risk-Level", format!("{:?}", response.risk_level));
+    if let Some(policy) = &response.policy {
+        builder = builder.header("X-Policy-Decision", policy.decision.as_str());
+    }
+
+    Ok(builder.body(Full::new(Bytes::from(body))).unwrap())
 }
 
 /// Get cache statistics
@@ -178,7 +397,10 @@ pub async fn clear_cache(
     _req: Request<hyper::body::Incoming>,
 ) -> Result<Response<Full<Bytes>>, std::convert::Infallible> {
     let engine = get_engine();
-    engine.clear_cache();
+
+    if let Err(e) = engine.invalidate(&CacheInvalidation::All).await {
+        return Ok(error_response(502, "cache_unavailable", "Failed to clear shared cache", Some(e.to_string())));
+    }
 
     let response = json!({
         "status": "success",
@@ -190,3 +412,47 @@ pub async fn clear_cache(
         .body(Full::new(Bytes::from(response.to_string())))
         .unwrap())
 }
+
+/// Invalidate cached assessments on every replica
+///
+/// Body is a [`CacheInvalidation`]: `{"scope":"entry","chain":..,"address":..}`,
+/// `{"scope":"chain","chain":..}` or `{"scope":"all"}`.
+pub async fn invalidate_cache(
+    req: Request<hyper::body::Incoming>,
+) -> Result<Response<Full<Bytes>>, std::convert::Infallible> {
+    let body_bytes = match req.collect().await {
+        Ok(body) => body.to_bytes(),
+        Err(_) => {
+            return Ok(error_response(400, "invalid_request", "Failed to read request body", None));
+        }
+    };
+
+    let invalidation: CacheInvalidation = match serde_json::from_slice(&body_bytes)
+        .map_err(|e| e.to_string())
+        .and_then(|inv: CacheInvalidation| inv.validate().map(|_| inv))
+    {
+        Ok(inv) => inv,
+        Err(e) => {
+            return Ok(error_response(400, "invalid_request", "Invalid request body", Some(e)));
+        }
+    };
+
+    let engine = get_engine();
+    let removed = match engine.invalidate(&invalidation).await {
+        Ok(removed) => removed,
+        Err(e) => {
+            return Ok(error_response(502, "cache_unavailable", "Failed to invalidate shared cache", Some(e.to_string())));
+        }
+    };
+
+    let response = json!({
+        "status": "success",
+        "invalidation": invalidation,
+        "shared_keys_removed": removed,
+    });
+
+    Ok(Response::builder()
+        .header("Content-Type", "application/json")
+        .body(Full::new(Bytes::from(response.to_string())))
+        .unwrap())
+}
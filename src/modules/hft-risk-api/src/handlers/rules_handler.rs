@@ -0,0 +1,24 @@
+//! Rules Handler - Scanner rule catalogue endpoint
+
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::{Request, Response};
+use serde_json::json;
+
+use crate::handlers::risk_handler::get_engine;
+
+/// Rule catalogue: `GET /api/v1/rules`
+pub async fn list_rules(
+    _req: Request<hyper::body::Incoming>,
+) -> Result<Response<Full<Bytes>>, std::convert::Infallible> {
+    let engine = get_engine();
+    let body = json!({
+        "rules": engine.rule_catalogue(),
+        "ruleset_version": engine.ruleset_version(),
+    });
+
+    Ok(Response::builder()
+        .header("Content-Type", "application/json")
+        .body(Full::new(Bytes::from(body.to_string())))
+        .unwrap())
+}
@@ -0,0 +1,166 @@
+//! Usage Handler - Per-key usage reporting
+
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::{Request, Response};
+use serde_json::json;
+
+use crate::auth::{ApiKey, Tier};
+use crate::handlers::risk_handler::{error_response, query_params};
+use crate::models::UsageCounters;
+use crate::usage::{UsageMeter, MAX_QUERY_RANGE_SECS};
+
+/// Global usage meter, set once at startup
+static USAGE_METER: OnceLock<UsageMeter> = OnceLock::new();
+
+/// Initialize the usage meter
+pub fn init_meter(meter: UsageMeter) -> &'static UsageMeter {
+    USAGE_METER.get_or_init(|| meter)
+}
+
+/// Record usage for an authenticated request; anonymous requests are not metered
+pub fn record_usage(api_key: Option<&ApiKey>, delta: UsageCounters) {
+    if let (Some(meter), Some(key)) = (USAGE_METER.get(), api_key) {
+        meter.record(&key.id, delta);
+    }
+}
+
+/// Parse a unix timestamp, RFC 3339 time or `YYYY-MM-DD` date (UTC);
+/// timestamps chrono cannot represent are rejected
+fn parse_time(value: &str) -> Option<i64> {
+    if let Ok(timestamp) = value.parse::<i64>() {
+        return chrono::DateTime::from_timestamp(timestamp, 0).map(|_| timestamp);
+    }
+    if let Ok(time) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Some(time.timestamp());
+    }
+    chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|time| time.and_utc().timestamp())
+}
+
+/// `from` and `to` of a usage query
+///
+/// `from` defaults to 24 hours before `to`, which defaults to `now`; the
+/// range may span at most [`MAX_QUERY_RANGE_SECS`].
+fn time_range(params: &HashMap<String, String>, now: i64) -> Result<(i64, i64), String> {
+    let time_param = |name: &str| match params.get(name) {
+        Some(value) => parse_time(value)
+            .map(Some)
+            .ok_or_else(|| format!("Invalid {}: {}", name, value)),
+        None => Ok(None),
+    };
+    let to = time_param("to")?.unwrap_or(now);
+    let from = match time_param("from")? {
+        Some(from) => from,
+        None => to
+            .checked_sub(24 * 3600)
+            .ok_or_else(|| "to is too early for the default range".to_string())?,
+    };
+    match to.checked_sub(from) {
+        Some(span) if (0..=MAX_QUERY_RANGE_SECS).contains(&span) => Ok((from, to)),
+        _ => Err("from must precede to by at most 31 days".to_string()),
+    }
+}
+
+/// Usage of the calling key: `GET /api/v1/usage?from=..&to=..`
+///
+/// `from` defaults to 24 hours before `to`, which defaults to now. Admin keys
+/// may pass `key=<id>` to read another key's usage.
+pub async fn get_usage(
+    req: Request<hyper::body::Incoming>,
+) -> Result<Response<Full<Bytes>>, std::convert::Infallible> {
+    let Some(api_key) = req.extensions().get::<Arc<ApiKey>>().cloned() else {
+        return Ok(error_response(401, "unauthorized", "API key required", None));
+    };
+    let Some(meter) = USAGE_METER.get() else {
+        return Ok(error_response(503, "unavailable", "Usage metering is disabled", None));
+    };
+
+    let params = query_params(req.uri());
+    let (from, to) = match time_range(&params, chrono::Utc::now().timestamp()) {
+        Ok(range) => range,
+        Err(e) => return Ok(error_response(400, "invalid_request", "Invalid time range", Some(e))),
+    };
+
+    let key_id = match params.get("key") {
+        Some(other) if *other != api_key.id => {
+            if api_key.tier != Tier::Admin {
+                return Ok(error_response(
+                    403,
+                    "forbidden",
+                    "Only admin keys may read other keys' usage",
+                    None,
+                ));
+            }
+            other.clone()
+        }
+        _ => api_key.id.clone(),
+    };
+
+    let hourly = meter.query(&key_id, from, to).await;
+    let mut totals = UsageCounters::default();
+    for (_, counters) in &hourly {
+        totals.add(counters);
+    }
+
+    let to_rfc3339 = |timestamp: i64| {
+        chrono::DateTime::from_timestamp(timestamp, 0)
+            .map(|time| time.to_rfc3339())
+            .unwrap_or_default()
+    };
+    let body = json!({
+        "key_id": key_id,
+        "from": to_rfc3339(from),
+        "to": to_rfc3339(to),
+        "totals": totals,
+        "hourly": hourly
+            .iter()
+            .map(|(hour, counters)| json!({ "hour_start": to_rfc3339(*hour), "usage": counters }))
+            .collect::<Vec<_>>(),
+    });
+
+    Ok(Response::builder()
+        .header("Content-Type", "application/json")
+        .body(Full::new(Bytes::from(body.to_string())))
+        .unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_time_formats() {
+        assert_eq!(parse_time("1767225600"), Some(1_767_225_600));
+        assert_eq!(parse_time("2026-01-01T00:00:00Z"), Some(1_767_225_600));
+        assert_eq!(parse_time("2026-01-01"), Some(1_767_225_600));
+        assert_eq!(parse_time("yesterday"), None);
+        assert_eq!(parse_time("-1"), Some(-1));
+        assert_eq!(parse_time("9223372036854775807"), None);
+        assert_eq!(parse_time("-9223372036854775808"), None);
+    }
+
+    #[test]
+    fn test_time_range_rejects_extreme_values() {
+        let params = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+        };
+        let now = 1_767_225_600;
+
+        assert_eq!(time_range(&params(&[]), now), Ok((now - 24 * 3600, now)));
+        assert_eq!(
+            time_range(&params(&[("from", "2026-01-01"), ("to", "2026-01-31")]), now),
+            Ok((now, now + 30 * 24 * 3600))
+        );
+        assert!(time_range(&params(&[("from", "-1"), ("to", "9223372036854775807")]), now).is_err());
+        assert!(time_range(&params(&[("from", "-9223372036854775808"), ("to", "0")]), now).is_err());
+        assert!(time_range(&params(&[("to", "-9223372036854775808")]), now).is_err());
+        assert!(time_range(&params(&[("from", "0"), ("to", "2026-01-01")]), now).is_err());
+        assert!(time_range(&params(&[("from", "2026-01-02"), ("to", "2026-01-01")]), now).is_err());
+    }
+}
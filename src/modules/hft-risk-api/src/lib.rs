@@ -0,0 +1,20 @@
+//! HFT Risk API - Real-time smart contract risk assessment
+//!
+//! Library crate shared by the HTTP server binary and the benchmarks.
+
+pub mod auth;
+pub mod cache;
+pub mod config;
+pub mod handlers;
+pub mod models;
+pub mod policy;
+pub mod pool;
+pub mod ratelimit;
+pub mod risk;
+pub mod scanner;
+pub mod usage;
+
+// The file keeps its own `mod tests` wrapper from before it was compiled
+#[cfg(test)]
+#[allow(clippy::module_inception)]
+mod tests;
@@ -1,6 +1,9 @@
 use std::convert::Infallible;
-use std::net::SocketAddr;
-use std::time::Instant;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
 
 use bytes::Bytes;
 use http_body_util::Full;
@@ -10,17 +13,31 @@ use hyper::{Request, Response};
 use hyper_util::rt::TokioIo;
 use tokio::net::TcpListener;
 
-mod config;
-mod handlers;
-mod models;
-mod risk;
-mod scanner;
-mod pool;
-mod cache;
+use hft_risk_api::auth::{read_key_file, ApiKeyStore, AuthConfig, Authenticator};
+use hft_risk_api::cache::snapshot::read_warm_list;
+use hft_risk_api::cache::{RedisCache, RedisConfig};
+use hft_risk_api::config::AppConfig;
+use hft_risk_api::handlers::{policy_handler, risk_handler, rules_handler, usage_handler};
+use hft_risk_api::handlers::risk_handler::{error_response, get_engine, init_engine};
+use hft_risk_api::policy::PolicyRegistry;
+use hft_risk_api::ratelimit::{client_ip, RateLimitConfig, RateLimitSubject, RateLimiter};
+use hft_risk_api::risk::engine::{EngineConfig, RiskEngine};
+use hft_risk_api::risk::scoring::ScoringModel;
+use hft_risk_api::scanner::dsl::spawn_rule_reload;
+use hft_risk_api::scanner::selection::RuleSelection;
+use hft_risk_api::usage::{spawn_usage_tasks, ExportFormat, UsageExporter, UsageMeter};
 
-use crate::config::AppConfig;
-use crate::handlers::risk_handler;
-use crate::handlers::risk_handler::init_engine;
+/// Set once the cache has been restored and warmed
+static READY: AtomicBool = AtomicBool::new(false);
+
+/// Request rate limiter shared by all connections
+static RATE_LIMITER: OnceLock<RateLimiter> = OnceLock::new();
+
+/// API key authenticator shared by all connections
+static AUTHENTICATOR: OnceLock<Authenticator> = OnceLock::new();
+
+/// Whether the admin endpoints are served; unknown paths otherwise
+static ADMIN_API_ENABLED: AtomicBool = AtomicBool::new(false);
 
 /// Main entry point
 #[tokio::main]
@@ -28,11 +45,163 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     // Initialize logging
     tracing_subscriber::fmt::init();
     
-    // Initialize risk engine
-    init_engine();
-    
     // Load configuration
     let config = AppConfig::from_env();
+    ADMIN_API_ENABLED.store(config.admin_api_enabled, Ordering::Relaxed);
+    
+    // Connect to the shared cache; the engine still serves from its local
+    // cache if Redis is unavailable
+    let redis = match RedisCache::new(RedisConfig {
+        url: config.redis_url.clone(),
+        topology: config.redis_topology.clone(),
+        pool_size: config.redis_pool_size,
+        ..RedisConfig::default()
+    })
+    .await
+    {
+        Ok(cache) => Some(Arc::new(cache)),
+        Err(e) => {
+            tracing::warn!("Redis unavailable, running with local cache only: {}", e);
+            None
+        }
+    };
+    
+    // Load API keys; Redis-provisioned keys are refreshed in the background
+    let file_keys = match &config.auth_keys_file {
+        Some(path) => read_key_file(Path::new(path)).unwrap_or_else(|e| {
+            tracing::warn!("Failed to read API key file {}: {}", path, e);
+            Vec::new()
+        }),
+        None => Vec::new(),
+    };
+    let key_store = Arc::new(ApiKeyStore::new(file_keys));
+    if let (true, Some(cache)) = (config.auth_keys_redis, &redis) {
+        hft_risk_api::auth::spawn_key_refresh(
+            Arc::clone(&key_store),
+            Arc::clone(cache),
+            Duration::from_secs(config.auth_refresh_secs),
+        );
+    }
+    let _ = AUTHENTICATOR.set(Authenticator::new(
+        AuthConfig {
+            required: config.auth_required,
+        },
+        key_store,
+    ));
+    
+    // Meter per-key usage; counters are summed across replicas in Redis
+    let meter = usage_handler::init_meter(UsageMeter::new(redis.clone()));
+    let exporter = config.usage_export_dir.as_ref().map(|dir| {
+        let format = ExportFormat::parse(&config.usage_export_format).unwrap_or_else(|| {
+            tracing::warn!("Unknown usage export format {}, using jsonl", config.usage_export_format);
+            ExportFormat::Jsonl
+        });
+        UsageExporter::new(dir, format, config.replica_id.clone())
+    });
+    spawn_usage_tasks(meter, Duration::from_secs(config.usage_flush_secs), exporter);
+    
+    // Share rate limit windows across replicas through Redis
+    let _ = RATE_LIMITER.set(RateLimiter::new(
+        RateLimitConfig {
+            enabled: config.rate_limit_enabled,
+            window: Duration::from_secs(config.rate_limit_window_secs),
+            per_key: config.rate_limit_per_key,
+            per_ip: config.rate_limit_per_ip,
+            trust_forwarded_for: config.trust_forwarded_for,
+        },
+        redis.clone(),
+    ));
+    
+    // Load custom policies alongside the built-in profiles
+    let mut policies = PolicyRegistry::default();
+    if let Some(dir) = &config.policy_dir {
+        match policies.load_dir(Path::new(dir)) {
+            Ok((loaded, errors)) => {
+                tracing::info!("Loaded {} policies from {}", loaded, dir);
+                for e in errors {
+                    tracing::warn!("Skipped policy file {}", e);
+                }
+            }
+            Err(e) => tracing::warn!("Failed to read policy directory {}: {}", dir, e),
+        }
+    }
+    let default_policy = config.default_policy.clone().filter(|id| {
+        let known = policies.get(id).is_some();
+        if !known {
+            tracing::warn!("Unknown default policy {}, evaluating policies only on request", id);
+        }
+        known
+    });
+    policy_handler::init_policies(policies, default_policy);
+    
+    // Initialize risk engine
+    let scoring_model = ScoringModel::named(&config.scoring_model).unwrap_or_else(|| {
+        tracing::warn!("Unknown scoring model {}, using noisy_or", config.scoring_model);
+        ScoringModel::default()
+    });
+    let rules = match &config.rules_config {
+        Some(path) => RuleSelection::read_file(Path::new(path)).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring rules config {}", e);
+            RuleSelection::default()
+        }),
+        None => RuleSelection::default(),
+    };
+    let engine = RiskEngine::with_config(EngineConfig {
+        scoring_model,
+        enable_simd: config.simd_enabled,
+        rules,
+        scan_timeout_ms: config.scanner_timeout_ms,
+        scan_threads: config.scanner_threads,
+        scan_parallel_min_bytes: config.scanner_parallel_min_bytes,
+        rules_dir: config.rules_dir.as_ref().map(PathBuf::from),
+        ..EngineConfig::default()
+    });
+    let engine = match &redis {
+        Some(cache) => engine.with_redis(Arc::clone(cache)),
+        None => engine,
+    };
+    init_engine(engine);
+    
+    // Pick up added, edited and removed custom rule files
+    if let (Some(dir), true) = (&config.rules_dir, config.rules_reload_secs > 0) {
+        spawn_rule_reload(
+            PathBuf::from(dir),
+            Duration::from_secs(config.rules_reload_secs),
+            || get_engine().reload_rules(),
+        );
+    }
+    
+    // Drop local entries whenever any replica invalidates
+    if let Some(cache) = &redis {
+        cache.spawn_invalidation_listener(|invalidation| {
+            get_engine().apply_invalidation(&invalidation);
+        });
+    }
+    
+    // Restore the local cache from the previous run
+    if let Some(path) = &config.cache_snapshot_path {
+        match get_engine().load_snapshot(Path::new(path)) {
+            Ok(restored) => tracing::info!("Restored {} cache entries from {}", restored, path),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => tracing::warn!("Failed to load cache snapshot {}: {}", path, e),
+        }
+    }
+    
+    // Pre-assess the warm list in the background; /health reports ready once done
+    let warm_list = match &config.cache_warm_list_path {
+        Some(path) => read_warm_list(Path::new(path)).unwrap_or_else(|e| {
+            tracing::warn!("Failed to read warm list {}: {}", path, e);
+            Vec::new()
+        }),
+        None => Vec::new(),
+    };
+    tokio::spawn(async move {
+        if !warm_list.is_empty() {
+            let warmed = get_engine().warm(warm_list).await;
+            tracing::info!("Warmed {} contracts", warmed);
+        }
+        READY.store(true, Ordering::Release);
+    });
     
     // Bind to address
     let addr: SocketAddr = config.bind_address.parse()?;
@@ -41,37 +210,134 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     tracing::info!("HFT Risk API server starting on {}", addr);
     tracing::info!("Target latency: {}ms", config.target_latency_ms);
     
-    // Accept incoming connections
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+    
+    // Accept incoming connections until asked to stop
     loop {
-        let (stream, _) = listener.accept().await?;
+        let (stream, peer) = tokio::select! {
+            accepted = listener.accept() => accepted?,
+            _ = &mut shutdown => break,
+        };
         let io = TokioIo::new(stream);
         
         // Spawn a task to handle the connection
         tokio::task::spawn(async move {
             if let Err(err) = http1::Builder::new()
-                .serve_connection(io, service_fn(handle_request))
+                .serve_connection(io, service_fn(move |req| handle_request(req, peer.ip())))
                 .await
             {
                 tracing::error!("Error serving connection: {:?}", err);
             }
         });
     }
+    
+    tracing::info!("Shutting down");
+    
+    // Push the last usage deltas before exiting
+    meter.flush().await;
+    
+    // Persist the local cache for the next start
+    if let Some(path) = &config.cache_snapshot_path {
+        match get_engine().save_snapshot(Path::new(path)) {
+            Ok(saved) => tracing::info!("Saved {} cache entries to {}", saved, path),
+            Err(e) => tracing::warn!("Failed to save cache snapshot {}: {}", path, e),
+        }
+    }
+    
+    Ok(())
+}
+
+/// Resolve on Ctrl-C or SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+    
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
 }
 
 /// Main request handler
 async fn handle_request(
     req: Request<hyper::body::Incoming>,
+    peer: IpAddr,
 ) -> Result<Response<Full<Bytes>>, Infallible> {
     let start = Instant::now();
     
-    let response = match req.uri().path() {
+    // Disabled admin routes answer before authentication so anonymous
+    // callers cannot tell them apart from unknown paths
+    if req.uri().path().starts_with("/api/v1/admin/") && !ADMIN_API_ENABLED.load(Ordering::Relaxed) {
+        return not_found();
+    }
+    
+    let api_key = match AUTHENTICATOR.get().map(|auth| auth.authenticate(&req)) {
+        Some(Err(e)) => return Ok(e.to_response()),
+        Some(Ok(key)) => key,
+        None => None,
+    };
+    
+    // Probes and scrapes are never limited; keyed requests are limited per
+    // key only, so customers sharing a NAT or proxy do not throttle each other
+    let path = req.uri().path();
+    let decision = match RATE_LIMITER.get() {
+        Some(limiter) if limiter.config().enabled && path != "/health" && path != "/metrics" => {
+            let (subject, limit) = match &api_key {
+                Some(key) => (RateLimitSubject::ApiKey(key.id.clone()), key.rate_limit),
+                None => (
+                    RateLimitSubject::Ip(client_ip(&req, peer, limiter.config().trust_forwarded_for)),
+                    None,
+                ),
+            };
+            let decision = limiter.check(&subject, limit).await;
+            if !decision.allowed {
+                return Ok(decision.rejection());
+            }
+            Some(decision)
+        }
+        _ => None,
+    };
+    
+    // Handlers read the tenant policy from the request extensions
+    let mut req = req;
+    if let Some(key) = api_key {
+        req.extensions_mut().insert(key);
+    }
+    
+    let mut response = match req.uri().path() {
         "/health" => health_check(),
         "/api/v1/risk/assess" => risk_handler::assess_risk(req).await,
         "/api/v1/risk/contract" => risk_handler::assess_contract(req).await,
+        "/api/v1/risk/batch" => risk_handler::assess_batch(req).await,
+        "/api/v1/admin/cache/stats" => risk_handler::cache_stats(req).await,
+        "/api/v1/admin/cache/clear" => risk_handler::clear_cache(req).await,
+        "/api/v1/admin/cache/invalidate" => risk_handler::invalidate_cache(req).await,
+        "/api/v1/policies" => policy_handler::list_policies(req).await,
+        "/api/v1/rules" => rules_handler::list_rules(req).await,
+        "/api/v1/usage" => usage_handler::get_usage(req).await,
         "/metrics" => metrics_handler(),
         _ => not_found(),
     };
     
+    if let (Some(decision), Ok(response)) = (decision, response.as_mut()) {
+        decision.apply_headers(response);
+    }
+    
     let elapsed = start.elapsed();
     tracing::debug!("Request processed in {:?}", elapsed);
     
@@ -79,9 +345,23 @@ async fn handle_request(
 }
 
 /// Health check endpoint
+///
+/// Returns 503 with status `warming` until the warm list has been assessed.
+/// Redis problems are reported but do not fail the check, since the engine
+/// keeps serving from its local cache.
 fn health_check() -> Result<Response<Full<Bytes>>, Infallible> {
-    let body = r#"{"status":"healthy","service":"hft-risk-api"}"#;
+    let ready = READY.load(Ordering::Acquire);
+    let engine = get_engine();
+    let body = serde_json::json!({
+        "status": if ready { "healthy" } else { "warming" },
+        "service": "hft-risk-api",
+        "ruleset_version": engine.ruleset_version(),
+        "simd_kernel": engine.kernel().name(),
+        "redis": engine.redis().map(|redis| redis.health()),
+    })
+    .to_string();
     Ok(Response::builder()
+        .status(if ready { 200 } else { 503 })
         .header("Content-Type", "application/json")
         .body(Full::new(Bytes::from(body)))
         .unwrap())
@@ -99,10 +379,5 @@ fn metrics_handler() -> Result<Response<Full<Bytes>>, Infallible> {
 
 /// 404 Not Found handler
 fn not_found() -> Result<Response<Full<Bytes>>, Infallible> {
-    let body = r#"{"error":"Not Found"}"#;
-    Ok(Response::builder()
-        .status(404)
-        .header("Content-Type", "application/json")
-        .body(Full::new(Bytes::from(body)))
-        .unwrap())
+    Ok(error_response(404, "not_found", "Not Found", None))
 }
@@ -1,5 +1,12 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
+use crate::policy::{PolicyDecision, PolicySelection, TokenFacts};
+use crate::risk::scoring::{ScoringModel, ScoringSelection};
+use crate::scanner::input::SourceInput;
+use crate::scanner::selection::{EffectiveRules, RuleSelection};
+
 /// Risk assessment request
 #[derive(Debug, Clone, Deserialize)]
 pub struct RiskAssessmentRequest {
@@ -13,6 +20,58 @@ pub struct RiskAssessmentRequest {
     /// Optional: Amount being transacted
     #[serde(skip_serializing_if = "Option::is_none")]
     pub amount: Option<f64>,
+    /// Optional: Scoring model name or full model with parameters
+    #[serde(default)]
+    pub scoring_model: Option<ScoringSelection>,
+    /// Optional: Policy name or full inline policy to evaluate
+    #[serde(default)]
+    pub policy: Option<PolicySelection>,
+    /// Optional: Token facts tested by policy clauses
+    #[serde(default)]
+    pub token_data: Option<TokenFacts>,
+    /// Optional: Rules to check and per-rule overrides
+    #[serde(default)]
+    pub rules: Option<RuleSelection>,
+    /// Optional: Verified source, a single file or solc standard-JSON input,
+    /// scanned alongside the bytecode
+    #[serde(default)]
+    pub source: Option<SourceInput>,
+}
+
+/// Batch risk assessment request
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchAssessmentRequest {
+    /// Contracts to assess
+    pub contracts: Vec<BatchItem>,
+    /// Optional: Scoring model applied to every contract
+    #[serde(default)]
+    pub scoring_model: Option<ScoringSelection>,
+    /// Optional: Policy evaluated for every contract
+    #[serde(default)]
+    pub policy: Option<PolicySelection>,
+    /// Optional: Rules checked for every contract
+    #[serde(default)]
+    pub rules: Option<RuleSelection>,
+}
+
+/// One contract of a batch request
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchItem {
+    pub contract_address: String,
+    pub chain: String,
+    /// Optional: Token facts tested by policy clauses
+    #[serde(default)]
+    pub token_data: Option<TokenFacts>,
+}
+
+/// Batch risk assessment response
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchAssessmentResponse {
+    /// Assessments in request order
+    pub results: Vec<RiskAssessmentResponse>,
+    pub count: usize,
+    /// Processing time of the whole batch in milliseconds
+    pub processing_time_ms: f64,
 }
 
 /// Risk assessment response
@@ -30,10 +89,112 @@ pub struct RiskAssessmentResponse {
     pub processing_time_ms: f64,
     /// Timestamp
     pub timestamp: String,
+    /// Version of the scanner ruleset that produced the findings
+    pub ruleset_version: String,
+    /// Scoring model and parameters used for `risk_score`
+    pub scoring_model: ScoringModel,
+    /// How each finding contributed to `risk_score`
+    pub explanation: ScoreExplanation,
+    /// Rules checked and the overrides applied to their findings
+    pub rules: EffectiveRules,
+    /// Whether some rules missed the scan deadline, so findings may be missing
+    pub partial: bool,
+    /// Ids of the checked rules that missed the scan deadline
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub timed_out_rules: Vec<String>,
+    /// Run time of each checked rule in milliseconds; only set when this
+    /// request scanned the contract
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub rule_timings_ms: BTreeMap<String, f64>,
+    /// Policy decision, when a policy was selected
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy: Option<PolicyDecision>,
+    /// Compiler versions the submitted source admits
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compiler: Option<CompilerRange>,
 }
 
-/// Risk level classification
+/// Compiler range resolved from the `pragma solidity` directives of the
+/// submitted source
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CompilerRange {
+    /// Each directive as written, e.g. `>=0.6.0 <0.8.0`
+    pub pragmas: Vec<String>,
+    /// Versions admitted by every directive, e.g. `>=0.7.6 <0.8.0`; empty
+    /// if they conflict
+    pub resolved: String,
+    /// Oldest admitted version
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_version: Option<String>,
+}
+
+/// Attribution of a risk score to its findings
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ScoreExplanation {
+    /// Per-finding contributions, in the order of `findings`
+    pub contributions: Vec<FindingContribution>,
+    /// Level threshold the score crossed
+    pub threshold: LevelThreshold,
+}
+
+/// How one finding contributed to a score
+#[derive(Debug, Clone, Serialize)]
+pub struct FindingContribution {
+    /// Index into `findings`
+    pub finding_index: usize,
+    pub category: String,
+    /// Severity as used in scoring (0-1)
+    pub severity_value: f32,
+    pub confidence: f32,
+    /// Weight under the scoring model, including any category factor
+    pub weight: f32,
+    /// Score lost if this finding were removed (leave-one-out)
+    pub marginal_contribution: f32,
+}
+
+/// The risk level band a score falls in
+#[derive(Debug, Clone, Serialize)]
+pub struct LevelThreshold {
+    pub level: RiskLevel,
+    /// Lowest score of the level, i.e. the threshold crossed
+    pub crossed: f32,
+    /// Score at which the next level starts, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next: Option<f32>,
+}
+
+impl Default for LevelThreshold {
+    fn default() -> Self {
+        LevelThreshold::for_score(0.0)
+    }
+}
+
+impl LevelThreshold {
+    /// Band containing `score`
+    pub fn for_score(score: f32) -> Self {
+        let level = RiskLevel::from_score(score);
+        Self {
+            crossed: level.min_score(),
+            next: level.next_threshold(),
+            level,
+        }
+    }
+}
+
+/// Error body returned by every endpoint
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    /// Stable machine-readable error code
+    pub code: String,
+    /// Human-readable summary
+    pub error: String,
+    /// Optional: Underlying cause
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+}
+
+/// Risk level classification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum RiskLevel {
     Low,
@@ -43,34 +204,101 @@ pub enum RiskLevel {
 }
 
 impl RiskLevel {
+    /// Lowest score classified as Medium
+    pub const MEDIUM_THRESHOLD: f32 = 25.0;
+    /// Lowest score classified as High
+    pub const HIGH_THRESHOLD: f32 = 50.0;
+    /// Lowest score classified as Critical
+    pub const CRITICAL_THRESHOLD: f32 = 75.0;
+
     pub fn from_score(score: f32) -> Self {
         match score {
-            s if s < 25.0 => RiskLevel::Low,
-            s if s < 50.0 => RiskLevel::Medium,
-            s if s < 75.0 => RiskLevel::High,
+            s if s < Self::MEDIUM_THRESHOLD => RiskLevel::Low,
+            s if s < Self::HIGH_THRESHOLD => RiskLevel::Medium,
+            s if s < Self::CRITICAL_THRESHOLD => RiskLevel::High,
             _ => RiskLevel::Critical,
         }
     }
+
+    /// Lowest score of this level
+    pub fn min_score(&self) -> f32 {
+        match self {
+            RiskLevel::Low => 0.0,
+            RiskLevel::Medium => Self::MEDIUM_THRESHOLD,
+            RiskLevel::High => Self::HIGH_THRESHOLD,
+            RiskLevel::Critical => Self::CRITICAL_THRESHOLD,
+        }
+    }
+
+    /// Score at which the next level starts
+    pub fn next_threshold(&self) -> Option<f32> {
+        match self {
+            RiskLevel::Low => Some(Self::MEDIUM_THRESHOLD),
+            RiskLevel::Medium => Some(Self::HIGH_THRESHOLD),
+            RiskLevel::High => Some(Self::CRITICAL_THRESHOLD),
+            RiskLevel::Critical => None,
+        }
+    }
 }
 
 /// Individual risk finding
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RiskFinding {
+    /// Stable id of the rule that produced the finding, e.g. `REENT-001`
+    pub rule_id: String,
     /// OWASP category or custom category
     pub category: String,
     /// Severity of the finding
     pub severity: Severity,
     /// Description of the issue
     pub description: String,
-    /// Optional: Line number or location
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub location: Option<String>,
+    /// Where the issue was found, when the rule can pinpoint it
+    pub location: Option<Location>,
+    /// Input the finding was produced from
+    #[serde(default)]
+    pub origin: Origin,
     /// Confidence level (0-1)
     pub confidence: f32,
 }
 
-/// Severity levels
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Position of a finding in the scanned contract
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Location {
+    /// 1-based line/column range in the source, end exclusive
+    Source {
+        start_line: u32,
+        start_column: u32,
+        end_line: u32,
+        end_column: u32,
+    },
+    /// Instruction offset in the bytecode, with the selector of the
+    /// function it belongs to when the dispatcher could be resolved
+    Bytecode {
+        offset: u32,
+        selector: Option<String>,
+    },
+}
+
+/// Input a finding was produced from
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Origin {
+    /// Deployed bytecode
+    #[default]
+    Bytecode,
+    /// Submitted source; `file` is the source unit name of a standard-JSON
+    /// input file
+    Source {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        file: Option<String>,
+    },
+    /// Transaction data
+    Transaction,
+}
+
+/// Severity levels, ordered from least to most severe
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Severity {
     Info,
@@ -80,6 +308,68 @@ pub enum Severity {
     Critical,
 }
 
+/// Metered usage of one API key over a period
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageCounters {
+    /// Assessments served, single or batched
+    pub assessments: u64,
+    /// Contracts submitted through batch requests
+    pub batch_items: u64,
+    /// Assessments served from the local or shared cache
+    pub cache_hits: u64,
+    /// Assessments that required a fresh scan
+    pub cold_scans: u64,
+    /// Response body bytes
+    pub bytes_served: u64,
+}
+
+impl UsageCounters {
+    /// Field names, in the order of [`UsageCounters::values`]
+    pub const FIELDS: [&'static str; 5] =
+        ["assessments", "batch_items", "cache_hits", "cold_scans", "bytes_served"];
+
+    /// Counter values, in the order of [`UsageCounters::FIELDS`]
+    pub fn values(&self) -> [u64; 5] {
+        [
+            self.assessments,
+            self.batch_items,
+            self.cache_hits,
+            self.cold_scans,
+            self.bytes_served,
+        ]
+    }
+
+    /// Build counters from `(field, value)` pairs, ignoring unknown fields
+    pub fn from_fields<'a>(fields: impl IntoIterator<Item = (&'a str, u64)>) -> Self {
+        let mut counters = Self::default();
+        for (field, value) in fields {
+            match field {
+                "assessments" => counters.assessments = value,
+                "batch_items" => counters.batch_items = value,
+                "cache_hits" => counters.cache_hits = value,
+                "cold_scans" => counters.cold_scans = value,
+                "bytes_served" => counters.bytes_served = value,
+                _ => {}
+            }
+        }
+        counters
+    }
+
+    /// Add another set of counters
+    pub fn add(&mut self, other: &UsageCounters) {
+        self.assessments += other.assessments;
+        self.batch_items += other.batch_items;
+        self.cache_hits += other.cache_hits;
+        self.cold_scans += other.cold_scans;
+        self.bytes_served += other.bytes_served;
+    }
+
+    /// Whether every counter is zero
+    pub fn is_empty(&self) -> bool {
+        self.values().iter().all(|&value| value == 0)
+    }
+}
+
 /// Contract metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContractMetadata {
@@ -0,0 +1,154 @@
+//! Built-in policy profiles
+//!
+//! Equivalent to the JavaScript policy pack: a `block*` rule becomes a clause
+//! whose action follows the pack's violation severity (critical blocks,
+//! anything else warns), and `maxTaxPercent`/`minLiquidityUSD` warn. Every
+//! profile also blocks on a critical finding, and the stricter profiles warn
+//! on compilers older than 0.8 (no built-in overflow checks).
+
+use super::{Condition, Decision, Policy, PolicyClause};
+use crate::models::Severity;
+
+/// Token rules of one policy pack profile
+struct PackRules {
+    block_upgradeable: bool,
+    block_mintable: bool,
+    block_blacklist: bool,
+    block_pausable: bool,
+    max_tax_percent: f64,
+    min_liquidity_usd: f64,
+    require_verified: bool,
+    warn_old_compiler: bool,
+}
+
+fn clause(id: &str, condition: Condition, action: Decision) -> PolicyClause {
+    PolicyClause {
+        id: id.to_string(),
+        condition,
+        action,
+        message: None,
+    }
+}
+
+fn profile(id: &str, name: &str, description: &str, rules: PackRules) -> Policy {
+    let mut clauses = vec![clause(
+        "critical_finding",
+        Condition::FindingSeverity {
+            min_severity: Severity::Critical,
+            category: None,
+        },
+        Decision::Block,
+    )];
+    if rules.warn_old_compiler {
+        clauses.push(clause(
+            "min_compiler_version",
+            Condition::CompilerBelow {
+                version: "0.8.0".to_string(),
+            },
+            Decision::Warn,
+        ));
+    }
+
+    let flags = [
+        (rules.block_upgradeable, "block_upgradeable", Condition::Upgradeable, Decision::Warn),
+        (rules.block_mintable, "block_mintable", Condition::Mintable, Decision::Block),
+        (rules.block_blacklist, "block_blacklist", Condition::Blacklist, Decision::Block),
+        (rules.block_pausable, "block_pausable", Condition::Pausable, Decision::Warn),
+    ];
+    for (enabled, id, condition, action) in flags {
+        if enabled {
+            clauses.push(clause(id, condition, action));
+        }
+    }
+
+    clauses.push(clause(
+        "max_tax_percent",
+        Condition::SellTaxAbove {
+            percent: rules.max_tax_percent,
+        },
+        Decision::Warn,
+    ));
+    clauses.push(clause(
+        "min_liquidity_usd",
+        Condition::LiquidityBelow {
+            usd: rules.min_liquidity_usd,
+        },
+        Decision::Warn,
+    ));
+    if rules.require_verified {
+        clauses.push(clause("require_verified", Condition::Unverified, Decision::Warn));
+    }
+
+    Policy {
+        id: id.to_string(),
+        name: name.to_string(),
+        description: description.to_string(),
+        clauses,
+    }
+}
+
+/// `conservative`, `moderate`, `aggressive` and `defi_yield`
+pub fn profiles() -> Vec<Policy> {
+    vec![
+        profile(
+            "conservative",
+            "Conservative",
+            "Maximum safety - blocks any suspicious patterns",
+            PackRules {
+                block_upgradeable: true,
+                block_mintable: true,
+                block_blacklist: true,
+                block_pausable: true,
+                max_tax_percent: 0.0,
+                min_liquidity_usd: 100_000.0,
+                require_verified: true,
+                warn_old_compiler: true,
+            },
+        ),
+        profile(
+            "moderate",
+            "Moderate",
+            "Balanced approach - allows verified tokens with warnings",
+            PackRules {
+                block_upgradeable: false,
+                block_mintable: true,
+                block_blacklist: true,
+                block_pausable: false,
+                max_tax_percent: 5.0,
+                min_liquidity_usd: 50_000.0,
+                require_verified: false,
+                warn_old_compiler: true,
+            },
+        ),
+        profile(
+            "aggressive",
+            "Aggressive",
+            "High risk tolerance - only blocks critical issues",
+            PackRules {
+                block_upgradeable: false,
+                block_mintable: false,
+                block_blacklist: false,
+                block_pausable: false,
+                max_tax_percent: 10.0,
+                min_liquidity_usd: 10_000.0,
+                require_verified: false,
+                warn_old_compiler: false,
+            },
+        ),
+        profile(
+            "defi_yield",
+            "DeFi Yield",
+            "Optimized for yield farming - blocks honeypots only",
+            PackRules {
+                block_upgradeable: false,
+                block_mintable: false,
+                block_blacklist: true,
+                block_pausable: false,
+                max_tax_percent: 3.0,
+                min_liquidity_usd: 200_000.0,
+                require_verified: true,
+                warn_old_compiler: false,
+            },
+        ),
+    ]
+}
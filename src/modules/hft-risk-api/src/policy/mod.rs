@@ -0,0 +1,607 @@
+//! Policy Engine - ALLOW/WARN/BLOCK decisions on top of risk assessments
+//!
+//! A policy is a list of clauses, each a condition on the assessment or on
+//! the token facts supplied with the request, plus the action taken when the
+//! condition holds. The decision is the strictest action of the violated
+//! clauses. Clauses whose facts were not supplied are reported as
+//! unevaluated rather than violated, as are the rules of a partial scan,
+//! which can at best be allowed with a warning.
+//!
+//! Built-in profiles mirror the JavaScript policy pack; custom profiles are
+//! loaded from TOML or JSON files.
+
+pub mod builtin;
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::{RiskAssessmentResponse, Severity};
+
+/// Outcome of a policy evaluation, ordered from least to most strict
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Decision {
+    Allow,
+    Warn,
+    Block,
+}
+
+impl Decision {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Decision::Allow => "ALLOW",
+            Decision::Warn => "WARN",
+            Decision::Block => "BLOCK",
+        }
+    }
+}
+
+/// Catalogue id of the DELEGATECALL rule, whose findings mark a proxy
+const DELEGATECALL_RULE_ID: &str = "DELEG-001";
+
+/// Token facts a policy may test, supplied by the caller
+///
+/// Upgradeability is also inferred from `DELEG-001` findings when not given.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TokenFacts {
+    pub upgradeable: Option<bool>,
+    pub mintable: Option<bool>,
+    pub blacklist: Option<bool>,
+    pub pausable: Option<bool>,
+    /// Source verified on a block explorer
+    pub verified: Option<bool>,
+    pub sell_tax_percent: Option<f64>,
+    pub liquidity_usd: Option<f64>,
+    /// Solidity compiler version, e.g. `0.8.19` or `v0.7.6+commit.7338295f`
+    pub compiler_version: Option<String>,
+}
+
+/// What a clause tests
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "when", rename_all = "snake_case")]
+pub enum Condition {
+    /// Any finding at or above a severity, optionally in one category
+    FindingSeverity {
+        min_severity: Severity,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        category: Option<String>,
+    },
+    /// Risk score at or above a value
+    ScoreAtLeast { score: f32 },
+    /// Upgradeable proxy
+    Upgradeable,
+    /// Owner can mint
+    Mintable,
+    /// Owner can blacklist holders
+    Blacklist,
+    /// Owner can pause transfers
+    Pausable,
+    /// Source not verified
+    Unverified,
+    /// Compiled with a version older than `version`
+    CompilerBelow { version: String },
+    /// Sell tax above `percent`
+    SellTaxAbove { percent: f64 },
+    /// Liquidity below `usd`
+    LiquidityBelow { usd: f64 },
+}
+
+/// One policy clause
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolicyClause {
+    /// Identifier reported in violations
+    pub id: String,
+    #[serde(flatten)]
+    pub condition: Condition,
+    /// `WARN` or `BLOCK`
+    pub action: Decision,
+    /// Overrides the generated violation message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// A named set of clauses
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Policy {
+    /// Identifier used to select the policy; defaults to the file name
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub clauses: Vec<PolicyClause>,
+}
+
+/// A violated clause
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Violation {
+    pub clause: String,
+    pub action: Decision,
+    pub message: String,
+}
+
+/// Result of evaluating a policy against an assessment
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicyDecision {
+    pub policy_id: String,
+    pub policy_name: String,
+    pub decision: Decision,
+    pub violations: Vec<Violation>,
+    /// Clauses skipped because the facts they test were not supplied, and
+    /// rules the scan timed out before running
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unevaluated: Vec<String>,
+    pub clauses_applied: usize,
+}
+
+/// Violation recorded when the scan timed out before every rule ran
+const PARTIAL_SCAN_CLAUSE: &str = "partial_scan";
+
+/// Parse `major.minor.patch` from a compiler version such as `v0.8.19+commit.c7dfd78e`
+pub fn parse_compiler_version(version: &str) -> Option<(u32, u32, u32)> {
+    let version = version.trim().trim_start_matches('v');
+    let core = version.split(['+', '-']).next()?;
+    let mut parts = core.split('.').map(|part| part.parse::<u32>());
+    let major = parts.next()?.ok()?;
+    let minor = parts.next().unwrap_or(Ok(0)).ok()?;
+    let patch = parts.next().unwrap_or(Ok(0)).ok()?;
+    Some((major, minor, patch))
+}
+
+impl Condition {
+    /// Violation message if violated, `None` if satisfied, `Err` if the
+    /// facts it tests were not supplied
+    fn check(&self, response: &RiskAssessmentResponse, facts: &TokenFacts) -> Result<Option<String>, ()> {
+        let flag = |value: Option<bool>, message: &str| match value {
+            Some(true) => Ok(Some(message.to_string())),
+            Some(false) => Ok(None),
+            None => Err(()),
+        };
+
+        match self {
+            Condition::FindingSeverity { min_severity, category } => {
+                let matching = response
+                    .findings
+                    .iter()
+                    .filter(|finding| finding.severity >= *min_severity)
+                    .filter(|finding| category.as_ref().is_none_or(|c| &finding.category == c))
+                    .count();
+                Ok((matching > 0).then(|| {
+                    format!(
+                        "{} finding(s) at or above {} severity",
+                        matching,
+                        severity_name(min_severity)
+                    )
+                }))
+            }
+            Condition::ScoreAtLeast { score } => Ok((response.risk_score >= *score).then(|| {
+                format!("Risk score {:.1} is at least {:.1}", response.risk_score, score)
+            })),
+            Condition::Upgradeable => {
+                let inferred = response
+                    .findings
+                    .iter()
+                    .any(|finding| finding.rule_id == DELEGATECALL_RULE_ID)
+                    .then_some(true);
+                flag(facts.upgradeable.or(inferred), "Token is upgradeable")
+            }
+            Condition::Mintable => flag(facts.mintable, "Token has mint function"),
+            Condition::Blacklist => flag(facts.blacklist, "Token has blacklist function"),
+            Condition::Pausable => flag(facts.pausable, "Token is pausable"),
+            Condition::Unverified => flag(facts.verified.map(|verified| !verified), "Source is not verified"),
+            Condition::CompilerBelow { version } => {
+                let used = facts.compiler_version.as_deref().ok_or(())?;
+                let (Some(parsed), Some(minimum)) =
+                    (parse_compiler_version(used), parse_compiler_version(version))
+                else {
+                    return Err(());
+                };
+                Ok((parsed < minimum)
+                    .then(|| format!("Compiler {} is older than {}", used, version)))
+            }
+            Condition::SellTaxAbove { percent } => {
+                let tax = facts.sell_tax_percent.ok_or(())?;
+                Ok((tax > *percent).then(|| format!("Tax {}% exceeds limit {}%", tax, percent)))
+            }
+            Condition::LiquidityBelow { usd } => {
+                let liquidity = facts.liquidity_usd.ok_or(())?;
+                Ok((liquidity < *usd)
+                    .then(|| format!("Liquidity ${} below minimum ${}", liquidity, usd)))
+            }
+        }
+    }
+}
+
+fn severity_name(severity: &Severity) -> &'static str {
+    match severity {
+        Severity::Info => "info",
+        Severity::Low => "low",
+        Severity::Medium => "medium",
+        Severity::High => "high",
+        Severity::Critical => "critical",
+    }
+}
+
+impl Policy {
+    /// Check clause ids and parameters
+    pub fn validate(&self) -> Result<(), String> {
+        if self.id.is_empty() {
+            return Err("Policy id must not be empty".to_string());
+        }
+        let mut seen = HashSet::new();
+        for clause in &self.clauses {
+            if clause.id.is_empty() || !seen.insert(clause.id.as_str()) {
+                return Err(format!("Clause ids must be unique and non-empty: {:?}", clause.id));
+            }
+            if clause.action == Decision::Allow {
+                return Err(format!("Clause {} action must be WARN or BLOCK", clause.id));
+            }
+            let valid = match &clause.condition {
+                Condition::ScoreAtLeast { score } => score.is_finite(),
+                Condition::CompilerBelow { version } => parse_compiler_version(version).is_some(),
+                Condition::SellTaxAbove { percent } => percent.is_finite(),
+                Condition::LiquidityBelow { usd } => usd.is_finite(),
+                _ => true,
+            };
+            if !valid {
+                return Err(format!("Clause {} has an invalid parameter", clause.id));
+            }
+        }
+        Ok(())
+    }
+
+    /// Evaluate against an assessment and the caller's token facts
+    pub fn evaluate(&self, response: &RiskAssessmentResponse, facts: &TokenFacts) -> PolicyDecision {
+        let mut violations = Vec::new();
+        let mut unevaluated = Vec::new();
+
+        for clause in &self.clauses {
+            match clause.condition.check(response, facts) {
+                Ok(Some(message)) => violations.push(Violation {
+                    clause: clause.id.clone(),
+                    action: clause.action,
+                    message: clause.message.clone().unwrap_or(message),
+                }),
+                Ok(None) => {}
+                Err(()) => unevaluated.push(clause.id.clone()),
+            }
+        }
+
+        // Rules that never ran could have found anything
+        if response.partial {
+            unevaluated.extend(response.timed_out_rules.iter().cloned());
+            violations.push(Violation {
+                clause: PARTIAL_SCAN_CLAUSE.to_string(),
+                action: Decision::Warn,
+                message: format!(
+                    "Scan timed out before {} rule(s) ran: {}",
+                    response.timed_out_rules.len(),
+                    response.timed_out_rules.join(", ")
+                ),
+            });
+        }
+
+        PolicyDecision {
+            policy_id: self.id.clone(),
+            policy_name: self.name.clone(),
+            decision: violations
+                .iter()
+                .map(|violation| violation.action)
+                .max()
+                .unwrap_or(Decision::Allow),
+            violations,
+            unevaluated,
+            clauses_applied: self.clauses.len(),
+        }
+    }
+}
+
+/// Read a policy from a `.toml` or `.json` file
+pub fn read_policy_file(path: &Path) -> Result<Policy, String> {
+    let data = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut policy: Policy = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => toml::from_str(&data).map_err(|e| format!("{}: {}", path.display(), e))?,
+        Some("json") => {
+            serde_json::from_str(&data).map_err(|e| format!("{}: {}", path.display(), e))?
+        }
+        _ => return Err(format!("{}: expected a .toml or .json file", path.display())),
+    };
+
+    if policy.id.is_empty() {
+        policy.id = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default()
+            .to_string();
+    }
+    if policy.name.is_empty() {
+        policy.name = policy.id.clone();
+    }
+    policy.validate().map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(policy)
+}
+
+/// A policy name or a full inline policy
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum PolicySelection {
+    Name(String),
+    Policy(Policy),
+}
+
+/// Policies available for selection by id
+#[derive(Debug, Clone)]
+pub struct PolicyRegistry {
+    policies: BTreeMap<String, Policy>,
+}
+
+impl Default for PolicyRegistry {
+    fn default() -> Self {
+        Self {
+            policies: builtin::profiles()
+                .into_iter()
+                .map(|policy| (policy.id.clone(), policy))
+                .collect(),
+        }
+    }
+}
+
+impl PolicyRegistry {
+    /// Add or replace a policy
+    pub fn insert(&mut self, policy: Policy) {
+        self.policies.insert(policy.id.clone(), policy);
+    }
+
+    /// Load every `.toml` and `.json` policy in a directory
+    ///
+    /// Returns the number loaded; files that fail to parse are skipped and
+    /// reported in the error list.
+    pub fn load_dir(&mut self, dir: &Path) -> std::io::Result<(usize, Vec<String>)> {
+        let mut paths: Vec<_> = fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| matches!(path.extension().and_then(|e| e.to_str()), Some("toml" | "json")))
+            .collect();
+        paths.sort();
+
+        let mut loaded = 0;
+        let mut errors = Vec::new();
+        for path in paths {
+            match read_policy_file(&path) {
+                Ok(policy) => {
+                    self.insert(policy);
+                    loaded += 1;
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+        Ok((loaded, errors))
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Policy> {
+        self.policies.get(id)
+    }
+
+    /// All policies, by id
+    pub fn iter(&self) -> impl Iterator<Item = &Policy> {
+        self.policies.values()
+    }
+
+    /// Resolve a selection to a validated policy
+    pub fn resolve(&self, selection: &PolicySelection) -> Result<Policy, String> {
+        match selection {
+            PolicySelection::Name(id) => self.get(id).cloned().ok_or_else(|| {
+                format!(
+                    "Unknown policy {}, expected one of {}",
+                    id,
+                    self.policies.keys().cloned().collect::<Vec<_>>().join(", ")
+                )
+            }),
+            PolicySelection::Policy(policy) => {
+                let mut policy = policy.clone();
+                if policy.id.is_empty() {
+                    policy.id = "inline".to_string();
+                }
+                policy.validate()?;
+                Ok(policy)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{RiskFinding, RiskLevel};
+
+    fn response(findings: Vec<RiskFinding>) -> RiskAssessmentResponse {
+        RiskAssessmentResponse {
+            contract_address: "0xabc".to_string(),
+            risk_score: 40.0,
+            risk_level: RiskLevel::Medium,
+            findings,
+            processing_time_ms: 0.0,
+            timestamp: String::new(),
+            ruleset_version: String::new(),
+            scoring_model: Default::default(),
+            explanation: Default::default(),
+            rules: Default::default(),
+            partial: false,
+            timed_out_rules: Vec::new(),
+            rule_timings_ms: Default::default(),
+            policy: None,
+            compiler: None,
+        }
+    }
+
+    fn finding(severity: Severity, description: &str) -> RiskFinding {
+        RiskFinding {
+            rule_id: "DELEG-001".to_string(),
+            category: "OWASP-SC03".to_string(),
+            severity,
+            description: description.to_string(),
+            location: None,
+            origin: Default::default(),
+            confidence: 0.9,
+        }
+    }
+
+    #[test]
+    fn test_builtin_profiles_match_policy_pack() {
+        let registry = PolicyRegistry::default();
+        let facts = TokenFacts {
+            upgradeable: Some(true),
+            mintable: Some(false),
+            blacklist: Some(false),
+            pausable: Some(true),
+            verified: Some(true),
+            sell_tax_percent: Some(4.0),
+            liquidity_usd: Some(60_000.0),
+            compiler_version: Some("0.8.19".to_string()),
+        };
+        let assessment = response(vec![]);
+
+        let conservative = registry.get("conservative").unwrap().evaluate(&assessment, &facts);
+        assert_eq!(conservative.decision, Decision::Warn);
+        let clauses: Vec<_> = conservative.violations.iter().map(|v| v.clause.as_str()).collect();
+        assert_eq!(
+            clauses,
+            ["block_upgradeable", "block_pausable", "max_tax_percent", "min_liquidity_usd"]
+        );
+
+        let moderate = registry.get("moderate").unwrap().evaluate(&assessment, &facts);
+        assert_eq!(moderate.decision, Decision::Allow);
+
+        let minted = TokenFacts {
+            mintable: Some(true),
+            ..facts.clone()
+        };
+        let moderate = registry.get("moderate").unwrap().evaluate(&assessment, &minted);
+        assert_eq!(moderate.decision, Decision::Block);
+        assert_eq!(moderate.violations[0].message, "Token has mint function");
+
+        let aggressive = registry.get("aggressive").unwrap().evaluate(&assessment, &minted);
+        assert_eq!(aggressive.decision, Decision::Allow);
+    }
+
+    #[test]
+    fn test_findings_and_missing_facts() {
+        let registry = PolicyRegistry::default();
+        let assessment = response(vec![
+            finding(Severity::Critical, "Reentrancy"),
+            finding(Severity::Medium, "DELEGATECALL usage detected"),
+        ]);
+        let facts = TokenFacts {
+            compiler_version: Some("v0.7.6+commit.7338295f".to_string()),
+            ..TokenFacts::default()
+        };
+
+        let decision = registry.get("conservative").unwrap().evaluate(&assessment, &facts);
+        assert_eq!(decision.decision, Decision::Block);
+        let clauses: Vec<_> = decision.violations.iter().map(|v| v.clause.as_str()).collect();
+        assert_eq!(clauses, ["critical_finding", "min_compiler_version", "block_upgradeable"]);
+        assert!(decision.unevaluated.contains(&"max_tax_percent".to_string()));
+        assert!(!decision.unevaluated.contains(&"block_upgradeable".to_string()));
+    }
+
+    #[test]
+    fn test_upgradeable_is_inferred_from_delegatecall_rule_only() {
+        let registry = PolicyRegistry::default();
+        let conservative = registry.get("conservative").unwrap();
+        let violated = |findings: Vec<RiskFinding>| -> bool {
+            conservative
+                .evaluate(&response(findings), &TokenFacts::default())
+                .violations
+                .iter()
+                .any(|v| v.clause == "block_upgradeable")
+        };
+
+        assert!(violated(vec![finding(Severity::Low, "Low-level call to an implementation")]));
+        let custom = RiskFinding {
+            rule_id: "PROXY-001".to_string(),
+            ..finding(Severity::Low, "Mentions DELEGATECALL in its description")
+        };
+        assert!(!violated(vec![custom]));
+    }
+
+    #[test]
+    fn test_partial_scan_is_never_allowed_outright() {
+        let registry = PolicyRegistry::default();
+        let partial = RiskAssessmentResponse {
+            partial: true,
+            timed_out_rules: vec!["AC-001".to_string(), "REENT-001".to_string()],
+            ..response(vec![])
+        };
+
+        let aggressive = registry.get("aggressive").unwrap();
+        assert_eq!(aggressive.evaluate(&response(vec![]), &TokenFacts::default()).decision, Decision::Allow);
+        let decision = aggressive.evaluate(&partial, &TokenFacts::default());
+        assert_eq!(decision.decision, Decision::Warn);
+        assert_eq!(decision.violations[0].clause, "partial_scan");
+        assert!(decision.violations[0].message.contains("AC-001, REENT-001"));
+        assert!(decision.unevaluated.ends_with(&["AC-001".to_string(), "REENT-001".to_string()]));
+
+        // Findings that did come back still block
+        let critical = RiskAssessmentResponse { findings: vec![finding(Severity::Critical, "Reentrancy")], ..partial };
+        assert_eq!(aggressive.evaluate(&critical, &TokenFacts::default()).decision, Decision::Block);
+    }
+
+    #[test]
+    fn test_load_toml_and_json_policies() {
+        let dir = std::env::temp_dir().join(format!("policies-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("treasury.toml"),
+            r#"
+name = "Treasury"
+
+[[clauses]]
+id = "no_high"
+when = "finding_severity"
+min_severity = "high"
+action = "BLOCK"
+
+[[clauses]]
+id = "tax"
+when = "sell_tax_above"
+percent = 1.0
+action = "WARN"
+"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("broken.json"),
+            r#"{"clauses":[{"id":"x","when":"upgradeable","action":"ALLOW"}]}"#,
+        )
+        .unwrap();
+
+        let mut registry = PolicyRegistry::default();
+        let (loaded, errors) = registry.load_dir(&dir).unwrap();
+        assert_eq!(loaded, 1);
+        assert_eq!(errors.len(), 1);
+
+        let treasury = registry.get("treasury").unwrap();
+        assert_eq!(treasury.name, "Treasury");
+        let decision = treasury.evaluate(
+            &response(vec![finding(Severity::High, "Unchecked call")]),
+            &TokenFacts {
+                sell_tax_percent: Some(2.0),
+                ..TokenFacts::default()
+            },
+        );
+        assert_eq!(decision.decision, Decision::Block);
+        assert_eq!(decision.violations.len(), 2);
+
+        let inline: PolicySelection = serde_json::from_str(
+            r#"{"clauses":[{"id":"score","when":"score_at_least","score":30,"action":"WARN"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(registry.resolve(&inline).unwrap().id, "inline");
+        assert!(registry.resolve(&PolicySelection::Name("unknown".to_string())).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -101,14 +101,6 @@ impl<T> ObjectPool<T> {
         }
     }
 
-    /// Get mutable reference to storage at index
-    /// 
-    /// # Safety
-    /// The index must be valid and the caller must have exclusive access
-    unsafe fn get_mut(&self, index: usize) -> &mut MaybeUninit<T> {
-        &mut *self.storage[index].get()
-    }
-
     /// Get the number of available objects in the pool
     pub fn available(&self) -> usize {
         let mut count = 0;
@@ -232,7 +224,7 @@ impl BufferPool {
     }
 
     /// Acquire a buffer of appropriate size
-    pub fn acquire_buffer(&self, size: usize) -> Option<PoolGuard<Vec<u8>>> {
+    pub fn acquire_buffer(&self, size: usize) -> Option<PoolGuard<'_, Vec<u8>>> {
         if size <= 1024 {
             self.small.acquire()
         } else if size <= 16 * 1024 {
@@ -285,7 +277,7 @@ mod tests {
         drop(guard);
         
         // Acquire again
-        let guard2 = pool.acquire().unwrap();
+        let _guard2 = pool.acquire().unwrap();
         assert!(pool.available() <= 9);
     }
 
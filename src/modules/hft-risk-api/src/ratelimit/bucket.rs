@@ -0,0 +1,107 @@
+//! Token Bucket - Local rate limiting when Redis is unavailable
+//!
+//! Each replica enforces the limit on its own, so the effective cluster-wide
+//! limit is multiplied by the replica count while in fallback.
+
+use std::time::{Duration, Instant};
+
+/// A token bucket that refills continuously to `capacity` over one window
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_sec: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    /// Create a full bucket allowing `capacity` requests per `window`
+    pub fn new(capacity: u64, window: Duration, now: Instant) -> Self {
+        let capacity = capacity.max(1) as f64;
+        Self {
+            capacity,
+            tokens: capacity,
+            refill_per_sec: capacity / window.as_secs_f64().max(0.001),
+            updated: now,
+        }
+    }
+
+    /// Requests allowed per window
+    pub fn capacity(&self) -> u64 {
+        self.capacity as u64
+    }
+
+    /// Rebuild the bucket for a new `capacity`, keeping the tokens already
+    /// used so a limit change does not grant a fresh burst
+    pub fn resize(&mut self, capacity: u64, window: Duration, now: Instant) {
+        self.refill(now);
+        let used = self.capacity - self.tokens;
+        *self = Self::new(capacity, window, now);
+        self.tokens = (self.capacity - used).max(0.0);
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.updated = now;
+    }
+
+    /// Take one token if available
+    pub fn try_acquire(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Whole tokens left
+    pub fn remaining(&self) -> u64 {
+        self.tokens.floor() as u64
+    }
+
+    /// Time until the next token is available
+    pub fn retry_after(&self) -> Duration {
+        let missing = (1.0 - self.tokens).max(0.0);
+        Duration::from_secs_f64(missing / self.refill_per_sec)
+    }
+
+    /// Time until the bucket is full again
+    pub fn reset_after(&self) -> Duration {
+        Duration::from_secs_f64((self.capacity - self.tokens) / self.refill_per_sec)
+    }
+
+    /// Whether the bucket has refilled completely by `now` and can be dropped
+    pub fn is_idle(&self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens + elapsed * self.refill_per_sec >= self.capacity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_exhausts_and_refills() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(3, Duration::from_secs(3), start);
+
+        assert!(bucket.try_acquire(start));
+        assert!(bucket.try_acquire(start));
+        assert!(bucket.try_acquire(start));
+        assert!(!bucket.try_acquire(start));
+        assert_eq!(bucket.remaining(), 0);
+        assert!(bucket.retry_after() <= Duration::from_secs(1));
+        assert!(!bucket.is_idle(start));
+
+        // One token per second
+        let later = start + Duration::from_millis(1500);
+        assert!(bucket.try_acquire(later));
+        assert!(!bucket.try_acquire(later));
+
+        assert!(bucket.is_idle(later + Duration::from_secs(3)));
+    }
+}
@@ -0,0 +1,296 @@
+//! Rate Limiting - Per-API-key and per-IP request limits
+//!
+//! Features:
+//! - Atomic sliding windows shared across replicas through Redis
+//! - Local token buckets when Redis is missing or unhealthy
+//! - `RateLimit-*` response headers and 429 rejections
+
+pub mod bucket;
+
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use dashmap::DashMap;
+use http_body_util::Full;
+use hyper::header::HeaderValue;
+use hyper::{Request, Response};
+use parking_lot::Mutex;
+
+use crate::cache::RedisCache;
+use crate::handlers::risk_handler::error_response;
+
+pub use bucket::TokenBucket;
+
+/// Local buckets kept before idle ones are pruned
+const MAX_LOCAL_BUCKETS: usize = 100_000;
+
+/// Rate limiter configuration
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    /// Whether limits are enforced at all
+    pub enabled: bool,
+    /// Length of the sliding window
+    pub window: Duration,
+    /// Requests per window for a request carrying an API key
+    pub per_key: u64,
+    /// Requests per window for an anonymous client IP
+    pub per_ip: u64,
+    /// Take the client IP from the first `X-Forwarded-For` entry
+    pub trust_forwarded_for: bool,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window: Duration::from_secs(60),
+            per_key: 1200,
+            per_ip: 300,
+            trust_forwarded_for: false,
+        }
+    }
+}
+
+/// Who a request is counted against
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RateLimitSubject {
+    /// Requests authenticated with an API key, by key id
+    ApiKey(String),
+    /// Anonymous requests from a client IP
+    Ip(IpAddr),
+}
+
+impl RateLimitSubject {
+    /// Window key
+    fn key(&self) -> String {
+        match self {
+            RateLimitSubject::ApiKey(id) => format!("key:{}", id),
+            RateLimitSubject::Ip(ip) => format!("ip:{}", ip),
+        }
+    }
+}
+
+/// Result of a rate limit check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitDecision {
+    pub allowed: bool,
+    /// Requests allowed per window
+    pub limit: u64,
+    /// Requests left in the current window
+    pub remaining: u64,
+    /// Seconds until the quota is restored (when rejected: until a retry can succeed)
+    pub reset_secs: u64,
+    /// Window length in seconds
+    pub window_secs: u64,
+}
+
+impl RateLimitDecision {
+    /// Add `RateLimit-*` headers to a response
+    pub fn apply_headers<B>(&self, response: &mut Response<B>) {
+        let headers = response.headers_mut();
+        headers.insert("RateLimit-Limit", HeaderValue::from(self.limit));
+        headers.insert("RateLimit-Remaining", HeaderValue::from(self.remaining));
+        headers.insert("RateLimit-Reset", HeaderValue::from(self.reset_secs));
+        if let Ok(policy) = HeaderValue::from_str(&format!("{};w={}", self.limit, self.window_secs)) {
+            headers.insert("RateLimit-Policy", policy);
+        }
+    }
+
+    /// 429 response for a rejected request
+    pub fn rejection(&self) -> Response<Full<Bytes>> {
+        let mut response = error_response(
+            429,
+            "rate_limited",
+            "Rate limit exceeded",
+            Some(format!("Limit of {} requests per {}s reached", self.limit, self.window_secs)),
+        );
+        response
+            .headers_mut()
+            .insert("Retry-After", HeaderValue::from(self.reset_secs));
+        self.apply_headers(&mut response);
+        response
+    }
+}
+
+/// Rate limiter backed by Redis with a local fallback
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    redis: Option<Arc<RedisCache>>,
+    local: DashMap<String, Mutex<TokenBucket>>,
+}
+
+impl RateLimiter {
+    /// Create a rate limiter; without Redis every replica limits on its own
+    pub fn new(config: RateLimitConfig, redis: Option<Arc<RedisCache>>) -> Self {
+        Self {
+            config,
+            redis,
+            local: DashMap::new(),
+        }
+    }
+
+    /// Limiter configuration
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.config
+    }
+
+    /// Default requests per window for a subject
+    pub fn limit_for(&self, subject: &RateLimitSubject) -> u64 {
+        match subject {
+            RateLimitSubject::ApiKey(_) => self.config.per_key,
+            RateLimitSubject::Ip(_) => self.config.per_ip,
+        }
+    }
+
+    /// Count a request against its subject
+    ///
+    /// `limit` overrides the configured default (e.g. a per-key limit). Uses
+    /// the shared Redis window while the pool is healthy and falls back to
+    /// the local bucket otherwise, so a Redis outage neither blocks nor slows
+    /// down request handling.
+    pub async fn check(&self, subject: &RateLimitSubject, limit: Option<u64>) -> RateLimitDecision {
+        let limit = limit.unwrap_or_else(|| self.limit_for(subject));
+        let key = subject.key();
+
+        if let Some(redis) = self.redis.as_ref().filter(|redis| redis.health().healthy) {
+            match redis.sliding_window_hit(&key, limit, self.config.window).await {
+                Ok(window) => {
+                    return RateLimitDecision {
+                        allowed: window.allowed,
+                        limit,
+                        remaining: limit.saturating_sub(window.count),
+                        reset_secs: window.reset_ms.div_ceil(1000),
+                        window_secs: self.config.window.as_secs(),
+                    };
+                }
+                Err(e) => tracing::debug!("Rate limit falling back to local bucket: {}", e),
+            }
+        }
+
+        self.check_local(&key, limit, Instant::now())
+    }
+
+    fn check_local(&self, key: &str, limit: u64, now: Instant) -> RateLimitDecision {
+        if self.local.len() >= MAX_LOCAL_BUCKETS {
+            self.local.retain(|_, bucket| !bucket.lock().is_idle(now));
+        }
+
+        let entry = self
+            .local
+            .entry(key.to_string())
+            .or_insert_with(|| Mutex::new(TokenBucket::new(limit, self.config.window, now)));
+        let mut bucket = entry.lock();
+        // The key's limit may have changed since the bucket was created
+        if bucket.capacity() != limit.max(1) {
+            bucket.resize(limit, self.config.window, now);
+        }
+        let allowed = bucket.try_acquire(now);
+        let wait = if allowed { bucket.reset_after() } else { bucket.retry_after() };
+
+        RateLimitDecision {
+            allowed,
+            limit,
+            remaining: bucket.remaining(),
+            reset_secs: wait.as_secs_f64().ceil() as u64,
+            window_secs: self.config.window.as_secs(),
+        }
+    }
+}
+
+/// Client IP, optionally taken from `X-Forwarded-For` behind a trusted proxy
+pub fn client_ip<B>(req: &Request<B>, peer: IpAddr, trust_forwarded_for: bool) -> IpAddr {
+    if !trust_forwarded_for {
+        return peer;
+    }
+
+    req.headers()
+        .get("x-forwarded-for")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(',').next())
+        .and_then(|ip| ip.trim().parse().ok())
+        .unwrap_or(peer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(headers: &[(&str, &str)]) -> Request<()> {
+        let mut builder = Request::builder().uri("/api/v1/risk/assess");
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder.body(()).unwrap()
+    }
+
+    #[test]
+    fn test_client_ip_honours_trusted_proxy() {
+        let peer: IpAddr = "10.0.0.1".parse().unwrap();
+
+        let forwarded = request(&[("X-Forwarded-For", "203.0.113.7, 10.0.0.1")]);
+        assert_eq!(client_ip(&forwarded, peer, false), peer);
+        assert_eq!(client_ip(&forwarded, peer, true), "203.0.113.7".parse::<IpAddr>().unwrap());
+
+        let spoofed = request(&[("X-Forwarded-For", "not-an-ip")]);
+        assert_eq!(client_ip(&spoofed, peer, true), peer);
+    }
+
+    #[tokio::test]
+    async fn test_local_fallback_rejects_with_headers() {
+        let limiter = RateLimiter::new(
+            RateLimitConfig {
+                per_ip: 2,
+                ..RateLimitConfig::default()
+            },
+            None,
+        );
+        let subject = RateLimitSubject::Ip("10.0.0.1".parse().unwrap());
+
+        assert!(limiter.check(&subject, None).await.allowed);
+        let last = limiter.check(&subject, None).await;
+        assert!(last.allowed);
+        assert_eq!(last.remaining, 0);
+
+        let rejected = limiter.check(&subject, None).await;
+        assert!(!rejected.allowed);
+        assert!(rejected.reset_secs >= 1);
+
+        let response = rejected.rejection();
+        assert_eq!(response.status(), 429);
+        assert_eq!(response.headers()["RateLimit-Limit"], "2");
+        assert_eq!(response.headers()["RateLimit-Remaining"], "0");
+        assert_eq!(response.headers()["RateLimit-Policy"], "2;w=60");
+        assert!(response.headers().contains_key("Retry-After"));
+    }
+
+    #[tokio::test]
+    async fn test_per_key_limit_override() {
+        let limiter = RateLimiter::new(RateLimitConfig::default(), None);
+        let subject = RateLimitSubject::ApiKey("acme".to_string());
+
+        let decision = limiter.check(&subject, Some(5000)).await;
+        assert_eq!(decision.limit, 5000);
+        assert_eq!(decision.remaining, 4999);
+    }
+
+    #[tokio::test]
+    async fn test_local_bucket_follows_limit_changes() {
+        let limiter = RateLimiter::new(RateLimitConfig::default(), None);
+        let subject = RateLimitSubject::ApiKey("acme".to_string());
+
+        assert!(limiter.check(&subject, Some(2)).await.allowed);
+        assert!(limiter.check(&subject, Some(2)).await.allowed);
+        assert!(!limiter.check(&subject, Some(2)).await.allowed);
+
+        // Raised limit: the two requests already made still count
+        let raised = limiter.check(&subject, Some(10)).await;
+        assert!(raised.allowed);
+        assert_eq!(raised.limit, 10);
+        assert_eq!(raised.remaining, 7);
+
+        // Lowered limit below what was used: rejected until refilled
+        assert!(!limiter.check(&subject, Some(1)).await.allowed);
+    }
+}
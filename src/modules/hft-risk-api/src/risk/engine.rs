@@ -3,26 +3,58 @@
 //! This module implements the high-performance risk assessment engine
 //! with support for SIMD operations (AVX2/SSE4.2) for vectorized calculations.
 
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
-use crossbeam::queue::SegQueue;
 use dashmap::DashMap;
+use parking_lot::RwLock;
 
+use crate::cache::snapshot::{CacheSnapshot, SnapshotEntry};
+use crate::cache::{CacheInvalidation, RedisCache};
 use crate::models::{
     RiskAssessmentRequest, RiskAssessmentResponse, RiskFinding, RiskLevel,
 };
-use crate::scanner::OwaspScanner;
+use crate::risk::scoring::{Aggregate, ScoringModel, ScoringSelection};
+use crate::risk::simd::{with_batch_buffer, with_findings_buffer, Kernel};
+use crate::scanner::input;
+use crate::scanner::selection::{EffectiveRules, RuleSelection};
+use crate::scanner::{OwaspScanner, RuleMetadata, ScannerConfig};
 
 /// Risk engine with optimized memory pooling
 pub struct RiskEngine {
     /// Scanner for OWASP vulnerabilities
     scanner: OwaspScanner,
-    /// Request pool for zero-allocation reuse
-    request_pool: Arc<SegQueue<RiskAssessmentRequest>>,
-    /// Response cache (contract_address -> response)
+    /// Response cache (chain:contract_address -> response)
     cache: Arc<DashMap<String, CachedResponse>>,
+    /// Shared Redis cache behind the in-process cache
+    l2: Option<Arc<RedisCache>>,
+    /// Bumped by every invalidation; results of scans started before the
+    /// latest one are not cached
+    generation: Arc<AtomicU64>,
     /// Engine configuration
     config: EngineConfig,
+    /// Severity sum kernel, chosen once from `enable_simd` and the CPU
+    kernel: Kernel,
+    /// Rules checked and overrides applied when the caller narrows nothing;
+    /// recomputed when custom rules reload
+    rules: RwLock<EffectiveRules>,
+}
+
+/// Every active rule, with the overrides of the configured selection
+///
+/// Overrides naming unknown rules are ignored.
+fn configured_rules(scanner: &OwaspScanner, selection: &RuleSelection) -> EffectiveRules {
+    let all = EffectiveRules::all(&scanner.catalogue());
+    let overrides = RuleSelection {
+        overrides: selection.overrides.clone(),
+        ..RuleSelection::default()
+    };
+    all.narrow(&overrides, &scanner.known_rules()).unwrap_or_else(|e| {
+        tracing::warn!("Ignoring configured rule overrides: {}", e);
+        all.clone()
+    })
 }
 
 /// Cached response with TTL
@@ -32,6 +64,25 @@ pub struct CachedResponse {
     pub timestamp: std::time::Instant,
 }
 
+/// Where an assessment was served from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Provenance {
+    /// In-process cache
+    LocalCache,
+    /// Shared Redis cache
+    SharedCache,
+    /// Freshly scanned
+    Scan,
+}
+
+impl Provenance {
+    /// Whether the assessment came from a cache rather than a scan
+    pub fn is_cache_hit(self) -> bool {
+        self != Provenance::Scan
+    }
+}
+
 /// Engine configuration
 #[derive(Debug, Clone)]
 pub struct EngineConfig {
@@ -39,10 +90,22 @@ pub struct EngineConfig {
     pub cache_ttl_secs: u64,
     /// Maximum cache entries
     pub max_cache_entries: usize,
-    /// Enable SIMD optimizations
+    /// Enable SIMD optimizations; `false` forces the scalar kernel
     pub enable_simd: bool,
     /// Worker threads for parallel processing
     pub worker_threads: usize,
+    /// Scoring model used when neither the request nor the API key picks one
+    pub scoring_model: ScoringModel,
+    /// Rules to scan with and overrides from the config file
+    pub rules: RuleSelection,
+    /// Deadline for scanning one contract (milliseconds)
+    pub scan_timeout_ms: u64,
+    /// Threads running scanner rules in parallel; 0 uses one per CPU
+    pub scan_threads: usize,
+    /// Smallest input, in bytes, whose rules run in parallel
+    pub scan_parallel_min_bytes: usize,
+    /// Directory of declarative rule files
+    pub rules_dir: Option<PathBuf>,
 }
 
 impl Default for EngineConfig {
@@ -52,6 +115,12 @@ impl Default for EngineConfig {
             max_cache_entries: 100_000,
             enable_simd: true,
             worker_threads: num_cpus::get(),
+            scoring_model: ScoringModel::default(),
+            rules: RuleSelection::default(),
+            scan_timeout_ms: ScannerConfig::default().timeout_ms,
+            scan_threads: ScannerConfig::default().threads,
+            scan_parallel_min_bytes: ScannerConfig::default().parallel_min_bytes,
+            rules_dir: None,
         }
     }
 }
@@ -63,211 +132,855 @@ impl RiskEngine {
     }
 
     /// Create a new risk engine with custom configuration
+    ///
+    /// Rules excluded by `config.rules` are not scanned at all; its overrides
+    /// apply to every assessment. Overrides naming unknown rules are ignored.
     pub fn with_config(config: EngineConfig) -> Self {
-        let scanner = OwaspScanner::new();
-        let request_pool = Arc::new(SegQueue::new());
+        let scanner = OwaspScanner::with_config(ScannerConfig {
+            enable_all: config.rules.include.is_empty(),
+            enabled_rules: config.rules.include.clone(),
+            disabled_rules: config.rules.exclude.clone(),
+            timeout_ms: config.scan_timeout_ms,
+            threads: config.scan_threads,
+            parallel_min_bytes: config.scan_parallel_min_bytes,
+            rules_dir: config.rules_dir.clone(),
+        });
         let cache = Arc::new(DashMap::with_capacity(config.max_cache_entries));
+        let rules = configured_rules(&scanner, &config.rules);
 
         Self {
             scanner,
-            request_pool,
             cache,
+            l2: None,
+            generation: Arc::new(AtomicU64::new(0)),
+            kernel: Kernel::select(config.enable_simd),
+            rules: RwLock::new(rules),
             config,
         }
     }
 
+    /// Reload custom rules from `rules_dir`, returning how many were loaded
+    ///
+    /// On failure the current rules stay active. Cached results of the old
+    /// ruleset become misses, as its version changes.
+    pub fn reload_rules(&self) -> Result<usize, String> {
+        let count = self.scanner.reload_custom_rules()?;
+        *self.rules.write() = configured_rules(&self.scanner, &self.config.rules);
+        Ok(count)
+    }
+
+    /// Attach a shared Redis cache used as L2 behind the in-process cache
+    pub fn with_redis(mut self, redis: Arc<RedisCache>) -> Self {
+        self.l2 = Some(redis);
+        self
+    }
+
+    /// Shared Redis cache, if attached
+    pub fn redis(&self) -> Option<&Arc<RedisCache>> {
+        self.l2.as_ref()
+    }
+
+    /// Scoring model for a selection, falling back to the engine default
+    pub fn resolve_scoring(&self, selection: Option<&ScoringSelection>) -> Result<ScoringModel, String> {
+        match selection {
+            Some(selection) => selection.resolve(),
+            None => Ok(self.config.scoring_model.clone()),
+        }
+    }
+
+    /// Rules checked by default, narrowed by each selection in turn
+    pub fn resolve_rules(&self, selections: &[&RuleSelection]) -> Result<EffectiveRules, String> {
+        let known = self.scanner.known_rules();
+        selections.iter().try_fold(self.default_rules(), |rules, selection| {
+            rules.narrow(selection, &known)
+        })
+    }
+
+    /// Rules checked when the caller narrows nothing
+    pub fn default_rules(&self) -> EffectiveRules {
+        self.rules.read().clone()
+    }
+
     /// Assess risk for a contract request
+    ///
+    /// An invalid scoring or rule selection falls back to the default; handlers
+    /// validate them up front with [`RiskEngine::resolve_scoring`] and
+    /// [`RiskEngine::resolve_rules`].
     pub async fn assess(&self, request: RiskAssessmentRequest) -> RiskAssessmentResponse {
+        let model = self
+            .resolve_scoring(request.scoring_model.as_ref())
+            .unwrap_or_else(|e| {
+                tracing::warn!("Invalid scoring model, using default: {}", e);
+                self.config.scoring_model.clone()
+            });
+        let rules = self
+            .resolve_rules(request.rules.as_ref().as_slice())
+            .unwrap_or_else(|e| {
+                tracing::warn!("Invalid rule selection, using default: {}", e);
+                self.default_rules()
+            });
+        self.assess_with_provenance(request, &model, &rules).await.0
+    }
+
+    /// Assess risk with `model` and report whether the result was cached or scanned
+    ///
+    /// Cached entries keep the findings of every scanned rule; `rules` and
+    /// the score for the requested model are applied on every read.
+    pub async fn assess_with_provenance(
+        &self,
+        request: RiskAssessmentRequest,
+        model: &ScoringModel,
+        rules: &EffectiveRules,
+    ) -> (RiskAssessmentResponse, Provenance) {
         let start = std::time::Instant::now();
-        let cache_key = format!("{}:{}", request.chain, request.contract_address);
 
-        // Check cache first
+        let (mut response, provenance) = match self.lookup(&request).await {
+            Some(found) => found,
+            None => {
+                let generation = self.generation.load(Ordering::Acquire);
+                let response = self.scan(&request);
+                self.store(&request, &response, generation);
+                (response, Provenance::Scan)
+            }
+        };
+
+        self.apply_rules(&mut response, rules);
+        self.apply_scoring(&mut response, model);
+        response.processing_time_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+        (response, provenance)
+    }
+
+    /// Assess many contracts, scoring them together
+    ///
+    /// Findings come from the caches or fresh scans as for single requests;
+    /// the scores of the whole batch are then computed with the batch SIMD
+    /// kernels, one lane per contract. Results are in request order.
+    pub async fn assess_batch(
+        &self,
+        requests: Vec<RiskAssessmentRequest>,
+        model: &ScoringModel,
+        rules: &EffectiveRules,
+    ) -> Vec<(RiskAssessmentResponse, Provenance)> {
+        let start = std::time::Instant::now();
+
+        let mut assessed = Vec::with_capacity(requests.len());
+        for request in &requests {
+            let (mut response, provenance) = match self.lookup(request).await {
+                Some(found) => found,
+                None => {
+                    let generation = self.generation.load(Ordering::Acquire);
+                    let response = self.scan(request);
+                    self.store(request, &response, generation);
+                    (response, Provenance::Scan)
+                }
+            };
+            self.apply_rules(&mut response, rules);
+            assessed.push((response, provenance));
+        }
+
+        let findings: Vec<&[RiskFinding]> = assessed
+            .iter()
+            .map(|(response, _)| response.findings.as_slice())
+            .collect();
+        let scores = self.score_batch(&findings, model);
+
+        let elapsed_ms = start.elapsed().as_secs_f64() * 1000.0;
+        for ((response, _), score) in assessed.iter_mut().zip(scores) {
+            response.risk_score = score;
+            response.risk_level = RiskLevel::from_score(score);
+            response.explanation = model.explain(&response.findings, score);
+            response.scoring_model = model.clone();
+            response.processing_time_ms = elapsed_ms;
+        }
+        assessed
+    }
+
+    /// Cached assessment from the local cache, then the shared cache
+    ///
+    /// Entries from another ruleset are misses, and requests submitting
+    /// source always scan, since entries are keyed by address alone.
+    async fn lookup(&self, request: &RiskAssessmentRequest) -> Option<(RiskAssessmentResponse, Provenance)> {
+        if request.source.is_some() {
+            return None;
+        }
+
+        let cache_key = l1_cache_key(&request.chain, &request.contract_address);
+
         if let Some(cached) = self.cache.get(&cache_key) {
-            if cached.timestamp.elapsed().as_secs() < self.config.cache_ttl_secs {
-                let mut response = cached.response.clone();
-                response.processing_time_ms = start.elapsed().as_secs_f64() * 1000.0;
-                return response;
+            if cached.timestamp.elapsed().as_secs() < self.config.cache_ttl_secs
+                && cached.response.ruleset_version == self.ruleset_version()
+            {
+                return Some((cached.response.clone(), Provenance::LocalCache));
+            }
+        }
+
+        if let Some(l2) = &self.l2 {
+            match l2.get_risk_assessment(&request.chain, &request.contract_address).await {
+                Ok(Some((response, age))) if response.ruleset_version == self.ruleset_version() => {
+                    self.promote(cache_key, &response, age);
+                    return Some((response, Provenance::SharedCache));
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("L2 cache lookup failed: {}", e),
             }
         }
 
-        // Perform risk assessment
-        let findings = self.scanner.scan_bytecode(
+        None
+    }
+
+    /// Copy a shared cache hit into the local cache
+    ///
+    /// The entry keeps the age it had in Redis, so moving between the two
+    /// caches never extends its lifetime past one TTL.
+    fn promote(&self, cache_key: String, response: &RiskAssessmentResponse, age: Duration) {
+        if age.as_secs() >= self.config.cache_ttl_secs {
+            return;
+        }
+        let Some(timestamp) = std::time::Instant::now().checked_sub(age) else {
+            return;
+        };
+        self.cache.insert(
+            cache_key,
+            CachedResponse {
+                response: response.clone(),
+                timestamp,
+            },
+        );
+    }
+
+    /// Scan a contract and any submitted source; the response is not yet scored
+    ///
+    /// Each source file is scanned on its own and its findings are added to
+    /// the bytecode findings, told apart by their `origin`. The whole scan
+    /// shares one deadline.
+    fn scan(&self, request: &RiskAssessmentRequest) -> RiskAssessmentResponse {
+        let files = match request.source.as_ref().map(|source| source.files()) {
+            Some(Ok(files)) => files,
+            Some(Err(e)) => {
+                tracing::warn!("Ignoring invalid source: {}", e);
+                Vec::new()
+            }
+            None => Vec::new(),
+        };
+        let outcome = self.scanner.scan_contract(
             &request.contract_address,
             &request.contract_address, // Placeholder for actual bytecode
+            &files,
         );
+        let compiler = input::compiler_metadata(&files);
 
-        // Calculate risk score using SIMD-optimized vectorized calculation
-        let risk_score = self.calculate_risk_score_simd(&findings);
-        let risk_level = RiskLevel::from_score(risk_score);
-
-        let response = RiskAssessmentResponse {
+        RiskAssessmentResponse {
             contract_address: request.contract_address.clone(),
-            risk_score,
-            risk_level,
-            findings,
-            processing_time_ms: start.elapsed().as_secs_f64() * 1000.0,
+            risk_score: 0.0,
+            risk_level: RiskLevel::Low,
+            partial: outcome.is_partial(),
+            findings: outcome.findings,
+            processing_time_ms: 0.0,
             timestamp: chrono::Utc::now().to_rfc3339(),
-        };
+            ruleset_version: outcome.ruleset_version,
+            scoring_model: ScoringModel::default(),
+            explanation: Default::default(),
+            rules: Default::default(),
+            timed_out_rules: outcome.timed_out,
+            rule_timings_ms: outcome.rule_timings,
+            policy: None,
+            compiler,
+        }
+    }
+
+    /// Cache a scanned response locally and write it behind to the shared cache
+    ///
+    /// Partial scans and scans of submitted source are not cached, so the
+    /// next request scans again. Rule timings describe this scan only and
+    /// are not cached. `generation` is the invalidation generation when the
+    /// scan started; if an invalidation has run since, the result may
+    /// predate it and is dropped, here and again before the write-behind.
+    fn store(&self, request: &RiskAssessmentRequest, response: &RiskAssessmentResponse, generation: u64) {
+        if response.partial || request.source.is_some() {
+            return;
+        }
+        if self.generation.load(Ordering::Acquire) != generation {
+            return;
+        }
+        let chain = &request.chain;
 
-        // Cache the response
         self.cache.insert(
-            cache_key,
+            l1_cache_key(chain, &response.contract_address),
             CachedResponse {
-                response: response.clone(),
+                response: RiskAssessmentResponse {
+                    rule_timings_ms: Default::default(),
+                    ..response.clone()
+                },
                 timestamp: std::time::Instant::now(),
             },
         );
 
+        // Write behind to the shared cache without holding up the response
+        if let Some(l2) = &self.l2 {
+            let l2 = Arc::clone(l2);
+            let chain = chain.to_string();
+            let response = response.clone();
+            let ttl = self.config.cache_ttl_secs;
+            let current = Arc::clone(&self.generation);
+            tokio::spawn(async move {
+                if current.load(Ordering::Acquire) != generation {
+                    return;
+                }
+                if let Err(e) = l2
+                    .set_risk_assessment(&chain, &response.contract_address, &response, Some(ttl))
+                    .await
+                {
+                    tracing::warn!("L2 cache write failed: {}", e);
+                }
+            });
+        }
+
         // Clean up old cache entries if needed
         if self.cache.len() > self.config.max_cache_entries {
             self.cleanup_cache();
         }
+    }
 
-        response
+    /// Score findings with a scoring model
+    pub fn score(&self, findings: &[RiskFinding], model: &ScoringModel) -> f32 {
+        model.score(findings, |aggregate| match aggregate {
+            Aggregate::SeveritySum => self.severity_sum_simd(findings),
+            Aggregate::ClearProduct => aggregate.compute(findings),
+        })
+    }
+
+    /// Score many findings lists at once, one SIMD lane per list
+    ///
+    /// Equal to calling [`RiskEngine::score`] on each list.
+    pub fn score_batch(&self, batch: &[&[RiskFinding]], model: &ScoringModel) -> Vec<f32> {
+        let Some(aggregate) = model.aggregate() else {
+            return batch.iter().map(|findings| self.score(findings, model)).collect();
+        };
+
+        let mut values = Vec::with_capacity(batch.len());
+        with_batch_buffer(batch, |buffer| {
+            self.kernel.batch_aggregate(buffer, aggregate, &mut values)
+        });
+        batch
+            .iter()
+            .zip(values)
+            .map(|(findings, value)| model.score(findings, |_| value))
+            .collect()
     }
 
-    /// Calculate risk score using SIMD vectorized operations
-    /// 
-    /// Uses AVX2 when available for parallel confidence calculations
-    fn calculate_risk_score_simd(&self, findings: &[RiskFinding]) -> f32 {
+    /// Restrict a response's findings to `rules` and apply their overrides
+    fn apply_rules(&self, response: &mut RiskAssessmentResponse, rules: &EffectiveRules) {
+        rules.apply(&mut response.findings);
+        response.timed_out_rules.retain(|id| rules.checked.contains(id));
+        response.partial = !response.timed_out_rules.is_empty();
+        response.rule_timings_ms.retain(|id, _| rules.checked.contains(id));
+        response.rules = rules.clone();
+    }
+
+    /// Recompute a response's score and level for `model`
+    fn apply_scoring(&self, response: &mut RiskAssessmentResponse, model: &ScoringModel) {
+        response.risk_score = self.score(&response.findings, model);
+        response.risk_level = RiskLevel::from_score(response.risk_score);
+        response.explanation = model.explain(&response.findings, response.risk_score);
+        response.scoring_model = model.clone();
+    }
+
+    /// Sum of `confidence × severity` with the selected SIMD kernel
+    fn severity_sum_simd(&self, findings: &[RiskFinding]) -> f32 {
         if findings.is_empty() {
             return 0.0;
         }
+        with_findings_buffer(findings, |buffer| self.kernel.severity_sum(buffer))
+    }
 
-        #[cfg(target_arch = "x86_64")]
-        {
-            if self.config.enable_simd && is_x86_feature_detected!("avx2") {
-                return unsafe { self.calculate_risk_score_avx2(findings) };
-            }
-            if self.config.enable_simd && is_x86_feature_detected!("sse4.2") {
-                return unsafe { self.calculate_risk_score_sse42(findings) };
-            }
-        }
+    /// Kernel used for scoring
+    pub fn kernel(&self) -> Kernel {
+        self.kernel
+    }
+
+    /// Clean up expired cache entries
+    fn cleanup_cache(&self) {
+        let now = std::time::Instant::now();
+        self.cache.retain(|_, cached| {
+            now.duration_since(cached.timestamp).as_secs() < self.config.cache_ttl_secs
+        });
+    }
 
-        // Fallback to scalar calculation
-        self.calculate_risk_score_scalar(findings)
+    /// Version hash of the scanner ruleset used for new assessments
+    pub fn ruleset_version(&self) -> String {
+        self.scanner.ruleset_version()
     }
 
-    /// AVX2-optimized risk score calculation
-    #[cfg(target_arch = "x86_64")]
-    #[target_feature(enable = "avx2")]
-    unsafe fn calculate_risk_score_avx2(&self, findings: &[RiskFinding]) -> f32 {
-        use std::arch::x86_64::*;
+    /// Metadata of the scanner's active rules
+    pub fn rule_catalogue(&self) -> Vec<RuleMetadata> {
+        self.scanner.catalogue()
+    }
 
-        let mut total_score = 0.0_f32;
-        let chunk_size = 8; // AVX2 can process 8 f32 values at once
+    /// Get cache statistics
+    pub fn cache_stats(&self) -> (usize, usize) {
+        (self.cache.len(), self.config.max_cache_entries)
+    }
 
-        let weights: Vec<f32> = findings.iter().map(|f| f.confidence).collect();
-        let severities: Vec<f32> = findings.iter().map(|f| severity_to_f32(&f.severity)).collect();
+    /// Clear the cache
+    pub fn clear_cache(&self) {
+        self.cache.clear();
+    }
 
-        let len = weights.len();
-        let mut i = 0;
+    /// Dump the local cache to a MessagePack snapshot file
+    ///
+    /// Returns the number of entries written.
+    pub fn save_snapshot(&self, path: &Path) -> std::io::Result<usize> {
+        let ttl = Duration::from_secs(self.config.cache_ttl_secs);
+        let entries: Vec<SnapshotEntry> = self
+            .cache
+            .iter()
+            .filter(|entry| entry.timestamp.elapsed() < ttl)
+            .map(|entry| SnapshotEntry {
+                key: entry.key().clone(),
+                response: entry.response.clone(),
+                age: entry.timestamp.elapsed(),
+            })
+            .collect();
+
+        let snapshot = CacheSnapshot::from_entries(entries);
+        snapshot.write_to(path)?;
+        Ok(snapshot.len())
+    }
 
-        while i + chunk_size <= len {
-            let w = _mm256_loadu_ps(weights.as_ptr().add(i));
-            let s = _mm256_loadu_ps(severities.as_ptr().add(i));
-            let product = _mm256_mul_ps(w, s);
-            
-            // Horizontal sum of the 8 values
-            let sum = _mm256_hadd_ps(product, product);
-            let sum = _mm256_hadd_ps(sum, sum);
-            
-            let result: [f32; 8] = std::mem::transmute(sum);
-            total_score += result[0] + result[4];
+    /// Reload a snapshot written by [`RiskEngine::save_snapshot`]
+    ///
+    /// Expired entries and entries from another ruleset are dropped. Returns
+    /// the number of entries restored.
+    pub fn load_snapshot(&self, path: &Path) -> std::io::Result<usize> {
+        let ttl = Duration::from_secs(self.config.cache_ttl_secs);
+        let entries = CacheSnapshot::read_from(path)?.into_entries(&self.ruleset_version(), ttl);
+        let now = std::time::Instant::now();
 
-            i += chunk_size;
+        let mut restored = 0;
+        for entry in entries {
+            if self.cache.len() >= self.config.max_cache_entries {
+                break;
+            }
+            let Some(timestamp) = now.checked_sub(entry.age) else {
+                continue;
+            };
+            self.cache.insert(
+                entry.key,
+                CachedResponse {
+                    response: entry.response,
+                    timestamp,
+                },
+            );
+            restored += 1;
         }
 
-        // Process remaining elements
-        for j in i..len {
-            total_score += weights[j] * severities[j];
-        }
+        Ok(restored)
+    }
 
-        (total_score / len as f32 * 100.0).min(100.0)
+    /// Pre-assess `(chain, address)` pairs so they are served from cache
+    ///
+    /// Runs up to `worker_threads` assessments concurrently. Returns the
+    /// number of contracts assessed.
+    pub async fn warm(&self, targets: Vec<(String, String)>) -> usize {
+        use futures::StreamExt;
+
+        let count = targets.len();
+        futures::stream::iter(targets)
+            .for_each_concurrent(self.config.worker_threads.max(1), |(chain, contract_address)| async move {
+                self.assess(RiskAssessmentRequest {
+                    contract_address,
+                    chain,
+                    transaction_data: None,
+                    amount: None,
+                    scoring_model: None,
+                    policy: None,
+                    token_data: None,
+                    rules: None,
+                    source: None,
+                })
+                .await;
+            })
+            .await;
+        count
     }
 
-    /// SSE4.2-optimized risk score calculation
-    #[cfg(target_arch = "x86_64")]
-    #[target_feature(enable = "sse4.2")]
-    unsafe fn calculate_risk_score_sse42(&self, findings: &[RiskFinding]) -> f32 {
-        use std::arch::x86_64::*;
+    /// Drop a single `(chain, address)` entry from the local cache
+    pub fn evict(&self, chain: &str, contract_address: &str) {
+        self.cache.remove(&l1_cache_key(chain, contract_address));
+    }
 
-        let mut total_score = 0.0_f32;
-        let chunk_size = 4; // SSE can process 4 f32 values at once
+    /// Drop every local cache entry for a chain
+    pub fn evict_chain(&self, chain: &str) {
+        let prefix = format!("{}:", chain);
+        self.cache.retain(|key, _| !key.starts_with(&prefix));
+    }
 
-        let weights: Vec<f32> = findings.iter().map(|f| f.confidence).collect();
-        let severities: Vec<f32> = findings.iter().map(|f| severity_to_f32(&f.severity)).collect();
+    /// Apply an invalidation to the local cache only
+    ///
+    /// Used for invalidations received from other replicas.
+    pub fn apply_invalidation(&self, invalidation: &CacheInvalidation) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        match invalidation {
+            CacheInvalidation::Entry { chain, address } => self.evict(chain, address),
+            CacheInvalidation::Chain { chain } => self.evict_chain(chain),
+            CacheInvalidation::All => self.clear_cache(),
+        }
+    }
 
-        let len = weights.len();
-        let mut i = 0;
+    /// Invalidate cached assessments on every replica
+    ///
+    /// Drops the local entries, purges them from Redis and broadcasts the
+    /// invalidation so other replicas drop their local entries too. Returns
+    /// the number of Redis keys removed.
+    pub async fn invalidate(
+        &self,
+        invalidation: &CacheInvalidation,
+    ) -> Result<u64, redis::RedisError> {
+        self.apply_invalidation(invalidation);
+
+        let Some(l2) = &self.l2 else {
+            return Ok(0);
+        };
 
-        while i + chunk_size <= len {
-            let w = _mm_loadu_ps(weights.as_ptr().add(i));
-            let s = _mm_loadu_ps(severities.as_ptr().add(i));
-            let product = _mm_mul_ps(w, s);
-            
-            // Horizontal sum
-            let shuf = _mm_movehdup_ps(product);
-            let sums = _mm_add_ps(product, shuf);
-            let shuf = _mm_movehl_ps(shuf, sums);
-            let sums = _mm_add_ss(sums, shuf);
-            
-            total_score += _mm_cvtss_f32(sums);
+        let removed = l2.invalidate(invalidation).await?;
+        l2.publish_invalidation(invalidation).await?;
+        Ok(removed)
+    }
+}
 
-            i += chunk_size;
-        }
+impl Default for RiskEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-        // Process remaining elements
-        for j in i..len {
-            total_score += weights[j] * severities[j];
+/// Local cache key, normalised the same way as the Redis key
+fn l1_cache_key(chain: &str, contract_address: &str) -> String {
+    format!("{}:{}", chain, contract_address.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Origin;
+
+    fn request(chain: &str, address: &str) -> RiskAssessmentRequest {
+        RiskAssessmentRequest {
+            contract_address: address.to_string(),
+            chain: chain.to_string(),
+            transaction_data: None,
+            amount: None,
+            scoring_model: None,
+            policy: None,
+            token_data: None,
+            rules: None,
+            source: None,
         }
+    }
 
-        (total_score / len as f32 * 100.0).min(100.0)
+    #[tokio::test]
+    async fn test_invalidation_scopes() {
+        let engine = RiskEngine::new();
+        engine.assess(request("ethereum", "0xAAA")).await;
+        engine.assess(request("ethereum", "0xBBB")).await;
+        engine.assess(request("bsc", "0xAAA")).await;
+        assert_eq!(engine.cache_stats().0, 3);
+
+        // Entry eviction is case-insensitive on the address
+        engine.apply_invalidation(&CacheInvalidation::Entry {
+            chain: "ethereum".to_string(),
+            address: "0xaaa".to_string(),
+        });
+        assert_eq!(engine.cache_stats().0, 2);
+
+        engine.apply_invalidation(&CacheInvalidation::Chain {
+            chain: "ethereum".to_string(),
+        });
+        assert_eq!(engine.cache_stats().0, 1);
+
+        let removed = engine.invalidate(&CacheInvalidation::All).await.unwrap();
+        assert_eq!(removed, 0);
+        assert_eq!(engine.cache_stats().0, 0);
     }
 
-    /// Scalar fallback for risk score calculation
-    fn calculate_risk_score_scalar(&self, findings: &[RiskFinding]) -> f32 {
-        let total_score: f32 = findings
-            .iter()
-            .map(|f| f.confidence * severity_to_f32(&f.severity))
-            .sum();
+    #[tokio::test]
+    async fn test_scan_started_before_invalidation_is_not_cached() {
+        let engine = RiskEngine::new();
+        let request = request("ethereum", "0xAAA");
 
-        (total_score / findings.len() as f32 * 100.0).min(100.0)
+        let generation = engine.generation.load(Ordering::Acquire);
+        let response = engine.scan(&request);
+        engine.invalidate(&CacheInvalidation::All).await.unwrap();
+        engine.store(&request, &response, generation);
+        assert_eq!(engine.cache_stats().0, 0);
+
+        engine.assess(request).await;
+        assert_eq!(engine.cache_stats().0, 1);
     }
 
-    /// Clean up expired cache entries
-    fn cleanup_cache(&self) {
-        let now = std::time::Instant::now();
-        self.cache.retain(|_, cached| {
-            now.duration_since(cached.timestamp).as_secs() < self.config.cache_ttl_secs
+    #[tokio::test]
+    async fn test_snapshot_warm_start() {
+        let engine = RiskEngine::new();
+        engine
+            .warm(vec![
+                ("ethereum".to_string(), "0xAAA".to_string()),
+                ("bsc".to_string(), "0xBBB".to_string()),
+            ])
+            .await;
+        assert_eq!(engine.cache_stats().0, 2);
+
+        let path = std::env::temp_dir().join(format!("engine-{}.msgpack", uuid::Uuid::new_v4()));
+        assert_eq!(engine.save_snapshot(&path).unwrap(), 2);
+
+        let restarted = RiskEngine::new();
+        assert_eq!(restarted.load_snapshot(&path).unwrap(), 2);
+        std::fs::remove_file(&path).unwrap();
+
+        let key = l1_cache_key("bsc", "0xBBB");
+        let cached = restarted.cache.get(&key).unwrap();
+        assert_eq!(cached.response.ruleset_version, restarted.ruleset_version());
+    }
+
+    #[tokio::test]
+    async fn test_ruleset_mismatch_is_cache_miss() {
+        let engine = RiskEngine::new();
+        let fresh = engine.assess(request("ethereum", "0xCCC")).await;
+        assert_eq!(fresh.ruleset_version, engine.ruleset_version());
+
+        // Simulate an entry written by a previous ruleset
+        let mut stale = fresh.clone();
+        stale.ruleset_version = "0000000000000000".to_string();
+        stale.timestamp = "stale".to_string();
+        engine.cache.insert(
+            l1_cache_key("ethereum", "0xCCC"),
+            CachedResponse {
+                response: stale,
+                timestamp: std::time::Instant::now(),
+            },
+        );
+
+        let response = engine.assess(request("ethereum", "0xCCC")).await;
+        assert_eq!(response.ruleset_version, engine.ruleset_version());
+        assert_ne!(response.timestamp, "stale");
+    }
+
+    #[tokio::test]
+    async fn test_shared_cache_hit_keeps_its_age() {
+        let engine = RiskEngine::new();
+        let response = engine.assess(request("ethereum", "0xEEE")).await;
+        engine.clear_cache();
+        let ttl = engine.config.cache_ttl_secs;
+
+        let key = l1_cache_key("ethereum", "0xEEE");
+        engine.promote(key.clone(), &response, Duration::from_secs(ttl - 10));
+        let age = engine.cache.get(&key).unwrap().timestamp.elapsed();
+        assert!(age >= Duration::from_secs(ttl - 10) && age < Duration::from_secs(ttl));
+
+        // Already expired locally, so it is not cached again
+        engine.clear_cache();
+        engine.promote(key.clone(), &response, Duration::from_secs(ttl));
+        assert_eq!(engine.cache_stats().0, 0);
+    }
+
+    #[tokio::test]
+    async fn test_provenance_reports_scan_then_hit() {
+        let engine = RiskEngine::new();
+        let model = ScoringModel::default();
+        let rules = engine.default_rules();
+        let (_, first) = engine.assess_with_provenance(request("ethereum", "0xDDD"), &model, &rules).await;
+        let (_, second) = engine.assess_with_provenance(request("ethereum", "0xddd"), &model, &rules).await;
+
+        assert_eq!(first, Provenance::Scan);
+        assert_eq!(second, Provenance::LocalCache);
+        assert!(second.is_cache_hit());
+    }
+
+    #[tokio::test]
+    async fn test_cached_findings_rescored_per_model() {
+        let engine = RiskEngine::new();
+        let address = "0xf4_owner";
+        let noisy_or = engine.assess(request("ethereum", address)).await;
+        assert!(!noisy_or.findings.is_empty());
+
+        let max_severity = ScoringModel::MaxSeverity;
+        let (rescored, provenance) = engine
+            .assess_with_provenance(request("ethereum", address), &max_severity, &engine.default_rules())
+            .await;
+
+        assert_eq!(provenance, Provenance::LocalCache);
+        assert_eq!(rescored.scoring_model, max_severity);
+        assert_eq!(rescored.risk_score, engine.score(&rescored.findings, &max_severity));
+        assert!(noisy_or.risk_score >= rescored.risk_score);
+    }
+
+    #[tokio::test]
+    async fn test_batch_matches_single_assessments() {
+        let engine = RiskEngine::new();
+        let addresses = ["0xf4_owner", "0xAAA", "0xdelegatecall", "0xBBB", "0xf4_owner"];
+        let model = ScoringModel::NoisyOr;
+
+        let batch = engine
+            .assess_batch(
+                addresses.iter().map(|address| request("ethereum", address)).collect(),
+                &model,
+                &engine.default_rules(),
+            )
+            .await;
+        assert_eq!(batch.len(), addresses.len());
+        assert_eq!(batch[0].1, Provenance::Scan);
+
+        for (address, (batched, _)) in addresses.iter().zip(&batch) {
+            let (single, provenance) = engine
+                .assess_with_provenance(request("ethereum", address), &model, &engine.default_rules())
+                .await;
+            assert_eq!(provenance, Provenance::LocalCache);
+            assert_eq!(batched.contract_address, *address);
+            assert_eq!(batched.risk_score.to_bits(), single.risk_score.to_bits());
+        }
+
+        let scalar = RiskEngine::with_config(EngineConfig {
+            enable_simd: false,
+            ..EngineConfig::default()
         });
+        let findings: Vec<&[RiskFinding]> = batch.iter().map(|(r, _)| r.findings.as_slice()).collect();
+        for model in [ScoringModel::Average, ScoringModel::MaxSeverity, ScoringModel::NoisyOr] {
+            let expected: Vec<f32> = findings.iter().map(|f| scalar.score(f, &model)).collect();
+            assert_eq!(engine.score_batch(&findings, &model), expected);
+        }
     }
 
-    /// Get cache statistics
-    pub fn cache_stats(&self) -> (usize, usize) {
-        (self.cache.len(), self.config.max_cache_entries)
+    #[tokio::test]
+    async fn test_rule_selection_filters_reads_not_cache() {
+        let engine = RiskEngine::with_config(EngineConfig {
+            rules: RuleSelection {
+                exclude: vec!["ACCESS_CONTROL".to_string()],
+                ..RuleSelection::default()
+            },
+            ..EngineConfig::default()
+        });
+        assert!(!engine.default_rules().checked.contains("AC-001"));
+
+        // DELEGATECALL at offset 0
+        let address = "0xf4";
+        let model = ScoringModel::default();
+        let narrowed = engine
+            .resolve_rules(&[&RuleSelection {
+                exclude: vec!["DELEG-001".to_string()],
+                ..RuleSelection::default()
+            }])
+            .unwrap();
+        let (filtered, _) = engine
+            .assess_with_provenance(request("ethereum", address), &model, &narrowed)
+            .await;
+        assert!(filtered.findings.is_empty());
+        assert_eq!(filtered.rules, narrowed);
+        assert_eq!(filtered.risk_score, 0.0);
+
+        let (full, provenance) = engine
+            .assess_with_provenance(request("ethereum", address), &model, &engine.default_rules())
+            .await;
+        assert_eq!(provenance, Provenance::LocalCache);
+        assert_eq!(full.findings.len(), 1);
+        assert_eq!(full.findings[0].rule_id, "DELEG-001");
+
+        // Excluded in the config: a valid reference, but never checked
+        let reenabled = engine
+            .resolve_rules(&[&RuleSelection {
+                include: vec!["AC-001".to_string()],
+                ..RuleSelection::default()
+            }])
+            .unwrap();
+        assert!(reenabled.checked.is_empty());
+        assert!(engine.resolve_rules(&[&RuleSelection {
+            include: vec!["NOPE".to_string()],
+            ..RuleSelection::default()
+        }])
+        .is_err());
     }
 
-    /// Clear the cache
-    pub fn clear_cache(&self) {
-        self.cache.clear();
+    #[tokio::test]
+    async fn test_reloaded_rules_are_checked_by_default() {
+        let dir = std::env::temp_dir().join(format!("rules-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let engine = RiskEngine::with_config(EngineConfig {
+            rules_dir: Some(dir.clone()),
+            ..EngineConfig::default()
+        });
+        let model = ScoringModel::default();
+        let (_, provenance) = engine
+            .assess_with_provenance(request("ethereum", "0xf4"), &model, &engine.default_rules())
+            .await;
+        assert_eq!(provenance, Provenance::Scan);
+
+        std::fs::write(
+            dir.join("proxy.toml"),
+            "[[rules]]\nid = \"PROXY-001\"\ncategory = \"OWASP-SC03\"\nseverity = \"high\"\n\
+             confidence = 0.9\n\n[rules.match]\nopcodes = { sequence = [\"DELEGATECALL\"] }\n",
+        )
+        .unwrap();
+        assert_eq!(engine.reload_rules(), Ok(1));
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(engine.default_rules().checked.contains("PROXY-001"));
+
+        // The ruleset changed, so the cached result is a miss
+        let (response, provenance) = engine
+            .assess_with_provenance(request("ethereum", "0xf4"), &model, &engine.default_rules())
+            .await;
+        assert_eq!(provenance, Provenance::Scan);
+        assert!(response.findings.iter().any(|f| f.rule_id == "PROXY-001"));
     }
-}
 
-impl Default for RiskEngine {
-    fn default() -> Self {
-        Self::new()
+    #[tokio::test]
+    async fn test_timed_out_scan_is_partial_and_not_cached() {
+        let engine = RiskEngine::with_config(EngineConfig {
+            scan_timeout_ms: 0,
+            ..EngineConfig::default()
+        });
+        let model = ScoringModel::default();
+        let narrowed = engine
+            .resolve_rules(&[&RuleSelection {
+                include: vec!["DELEG-001".to_string(), "AC-001".to_string()],
+                ..RuleSelection::default()
+            }])
+            .unwrap();
+
+        let (response, provenance) = engine
+            .assess_with_provenance(request("ethereum", "0xf4"), &model, &narrowed)
+            .await;
+        assert_eq!(provenance, Provenance::Scan);
+        assert!(response.partial);
+        assert!(response.findings.is_empty());
+        assert_eq!(response.timed_out_rules, ["AC-001", "DELEG-001"]);
+        assert_eq!(engine.cache_stats().0, 0);
     }
-}
 
-/// Convert severity to numeric value for calculations
-fn severity_to_f32(severity: &crate::models::Severity) -> f32 {
-    use crate::models::Severity;
-    match severity {
-        Severity::Info => 0.1,
-        Severity::Low => 0.25,
-        Severity::Medium => 0.5,
-        Severity::High => 0.75,
-        Severity::Critical => 1.0,
+    #[tokio::test]
+    async fn test_source_findings_join_bytecode_findings() {
+        let engine = RiskEngine::new();
+        let model = ScoringModel::default();
+        let rules = engine.default_rules();
+        let mut submitted = request("ethereum", "0xf4");
+        submitted.source = Some(
+            serde_json::from_value(serde_json::json!({
+                "sources": {
+                    "contracts/Vault.sol": {"content": "contract Vault {\n    function f() external { require(tx.origin == owner); }\n}\n"},
+                    "contracts/Clean.sol": {"content": "contract Clean {}\n"}
+                }
+            }))
+            .unwrap(),
+        );
+
+        for _ in 0..2 {
+            let (response, provenance) = engine.assess_with_provenance(submitted.clone(), &model, &rules).await;
+            assert_eq!(provenance, Provenance::Scan);
+            assert!(response
+                .findings
+                .iter()
+                .any(|f| f.rule_id == "DELEG-001" && f.origin == Origin::Bytecode));
+            assert!(response.findings.iter().any(|f| f.rule_id == "ORIGIN-001"
+                && f.origin == Origin::Source { file: Some("contracts/Vault.sol".to_string()) }));
+        }
+        assert_eq!(engine.cache_stats().0, 0);
     }
 }
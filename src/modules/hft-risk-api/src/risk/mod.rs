@@ -1,10 +1,10 @@
 //! Risk Assessment Engine
 
 pub mod engine;
+pub mod scoring;
+pub mod simd;
 
-use std::sync::Arc;
-
-use crate::models::{RiskAssessmentRequest, RiskAssessmentResponse, RiskFinding, RiskLevel, Severity};
+use crate::models::{RiskAssessmentRequest, RiskAssessmentResponse, RiskLevel};
 
 /// Risk assessment engine
 pub struct RiskEngine {
@@ -34,6 +34,15 @@ impl RiskEngine {
             findings: vec![],
             processing_time_ms: 0.0,
             timestamp: chrono::Utc::now().to_rfc3339(),
+            ruleset_version: String::new(),
+            scoring_model: Default::default(),
+            explanation: Default::default(),
+            rules: Default::default(),
+            partial: false,
+            timed_out_rules: Vec::new(),
+            rule_timings_ms: Default::default(),
+            policy: None,
+            compiler: None,
         }
     }
 }
@@ -0,0 +1,470 @@
+//! Risk Scoring - Named models turning findings into a 0-100 score
+//!
+//! Every model works on the per-finding weight `confidence × severity`
+//! (scaled by a category weight where the model has one):
+//! - `average`: mean weight, the original behaviour; extra low findings dilute the score
+//! - `max_severity`: the single worst finding
+//! - `weighted_sum`: summed weights, saturating towards 100
+//! - `category_weighted`: `weighted_sum` with per-category factors
+//! - `noisy_or`: probability that at least one finding is a real issue
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::{FindingContribution, LevelThreshold, RiskFinding, ScoreExplanation, Severity};
+
+/// Saturation used by the named `weighted_sum` and `category_weighted` models
+pub const DEFAULT_SATURATION: f32 = 0.5;
+
+/// A scoring model and its parameters
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "model", rename_all = "snake_case")]
+pub enum ScoringModel {
+    /// Mean of the finding weights
+    Average,
+    /// Largest finding weight
+    MaxSeverity,
+    /// `1 - exp(-sum / saturation)`; smaller saturation reaches 100 sooner
+    WeightedSum { saturation: f32 },
+    /// Weighted sum with a factor per finding category
+    CategoryWeighted {
+        weights: BTreeMap<String, f32>,
+        #[serde(default = "default_category_weight")]
+        default_weight: f32,
+        saturation: f32,
+    },
+    /// `1 - Π(1 - weight)`, treating findings as independent evidence
+    #[default]
+    NoisyOr,
+}
+
+fn default_category_weight() -> f32 {
+    1.0
+}
+
+/// Names accepted by [`ScoringModel::named`]
+pub const SCORING_MODEL_NAMES: [&str; 5] = [
+    "average",
+    "max_severity",
+    "weighted_sum",
+    "category_weighted",
+    "noisy_or",
+];
+
+impl ScoringModel {
+    /// Named model with its default parameters
+    pub fn named(name: &str) -> Option<Self> {
+        match name {
+            "average" => Some(ScoringModel::Average),
+            "max_severity" => Some(ScoringModel::MaxSeverity),
+            "weighted_sum" => Some(ScoringModel::WeightedSum {
+                saturation: DEFAULT_SATURATION,
+            }),
+            "category_weighted" => Some(ScoringModel::CategoryWeighted {
+                weights: default_category_weights(),
+                default_weight: 1.0,
+                saturation: DEFAULT_SATURATION,
+            }),
+            "noisy_or" => Some(ScoringModel::NoisyOr),
+            _ => None,
+        }
+    }
+
+    /// Name of the model
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScoringModel::Average => "average",
+            ScoringModel::MaxSeverity => "max_severity",
+            ScoringModel::WeightedSum { .. } => "weighted_sum",
+            ScoringModel::CategoryWeighted { .. } => "category_weighted",
+            ScoringModel::NoisyOr => "noisy_or",
+        }
+    }
+
+    /// Reject parameters that would produce scores outside 0-100
+    pub fn validate(&self) -> Result<(), String> {
+        let check_saturation = |saturation: f32| {
+            if saturation.is_finite() && saturation > 0.0 {
+                Ok(())
+            } else {
+                Err(format!("saturation must be positive, got {}", saturation))
+            }
+        };
+
+        match self {
+            ScoringModel::WeightedSum { saturation } => check_saturation(*saturation),
+            ScoringModel::CategoryWeighted {
+                weights,
+                default_weight,
+                saturation,
+            } => {
+                check_saturation(*saturation)?;
+                match weights
+                    .iter()
+                    .map(|(category, weight)| (category.as_str(), *weight))
+                    .chain(std::iter::once(("default_weight", *default_weight)))
+                    .find(|(_, weight)| !weight.is_finite() || *weight < 0.0)
+                {
+                    Some((category, weight)) => {
+                        Err(format!("weight for {} must be non-negative, got {}", category, weight))
+                    }
+                    None => Ok(()),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Weight of a single finding under this model (0-1 before category factors)
+    pub fn finding_weight(&self, finding: &RiskFinding) -> f32 {
+        let base = finding.confidence * severity_to_f32(&finding.severity);
+        match self {
+            ScoringModel::CategoryWeighted {
+                weights,
+                default_weight,
+                ..
+            } => base * weights.get(&finding.category).copied().unwrap_or(*default_weight),
+            _ => base,
+        }
+    }
+
+    /// Score findings on a 0-100 scale
+    ///
+    /// Reductions over all findings are requested through `aggregate`, so the
+    /// engine can compute them with SIMD; each is only evaluated by the
+    /// models that need it.
+    pub fn score(&self, findings: &[RiskFinding], aggregate: impl FnOnce(Aggregate) -> f32) -> f32 {
+        if findings.is_empty() {
+            return 0.0;
+        }
+
+        let score = match self {
+            ScoringModel::Average => aggregate(Aggregate::SeveritySum) / findings.len() as f32,
+            ScoringModel::MaxSeverity => findings
+                .iter()
+                .map(|f| self.finding_weight(f))
+                .fold(0.0, f32::max),
+            ScoringModel::WeightedSum { saturation } => {
+                saturate(aggregate(Aggregate::SeveritySum), *saturation)
+            }
+            ScoringModel::CategoryWeighted { saturation, .. } => {
+                let sum: f32 = findings.iter().map(|f| self.finding_weight(f)).sum();
+                saturate(sum, *saturation)
+            }
+            ScoringModel::NoisyOr => 1.0 - aggregate(Aggregate::ClearProduct),
+        };
+
+        (score * 100.0).clamp(0.0, 100.0)
+    }
+
+    /// Reduction this model requests from [`ScoringModel::score`], if any
+    pub fn aggregate(&self) -> Option<Aggregate> {
+        match self {
+            ScoringModel::Average | ScoringModel::WeightedSum { .. } => Some(Aggregate::SeveritySum),
+            ScoringModel::NoisyOr => Some(Aggregate::ClearProduct),
+            ScoringModel::MaxSeverity | ScoringModel::CategoryWeighted { .. } => None,
+        }
+    }
+
+    /// Attribute `score` to the findings that produced it
+    ///
+    /// The marginal contribution of a finding is the drop in score when it
+    /// alone is removed, so contributions need not add up to the score for
+    /// non-linear models.
+    pub fn explain(&self, findings: &[RiskFinding], score: f32) -> ScoreExplanation {
+        let weights: Vec<f32> = findings.iter().map(|f| self.finding_weight(f)).collect();
+
+        let contributions = findings
+            .iter()
+            .zip(&weights)
+            .zip(self.scores_without_each(&weights))
+            .enumerate()
+            .map(|(index, ((finding, &weight), without))| FindingContribution {
+                finding_index: index,
+                category: finding.category.clone(),
+                severity_value: severity_to_f32(&finding.severity),
+                confidence: finding.confidence,
+                weight,
+                marginal_contribution: score - without,
+            })
+            .collect();
+
+        ScoreExplanation {
+            contributions,
+            threshold: LevelThreshold::for_score(score),
+        }
+    }
+
+    /// Score with each finding left out in turn, from the finding weights
+    ///
+    /// The finding is taken out of the model's aggregate rather than the
+    /// others rescored: sums lose its term, the noisy-or product its factor
+    /// and the maximum falls back to the runner-up.
+    fn scores_without_each(&self, weights: &[f32]) -> Vec<f32> {
+        let others = weights.len().saturating_sub(1);
+        let scaled = |score: f32| if others == 0 { 0.0 } else { (score * 100.0).clamp(0.0, 100.0) };
+        let sum: f32 = weights.iter().sum();
+
+        match self {
+            ScoringModel::Average => weights.iter().map(|w| scaled((sum - w) / others as f32)).collect(),
+            ScoringModel::WeightedSum { saturation } | ScoringModel::CategoryWeighted { saturation, .. } => {
+                weights.iter().map(|w| scaled(saturate(sum - w, *saturation))).collect()
+            }
+            ScoringModel::MaxSeverity => {
+                // Index of the largest weight and the largest of the others
+                let (mut top, mut runner_up) = (0, 0.0_f32);
+                for (index, &weight) in weights.iter().enumerate().skip(1) {
+                    if weight > weights[top] {
+                        runner_up = runner_up.max(weights[top]);
+                        top = index;
+                    } else {
+                        runner_up = runner_up.max(weight);
+                    }
+                }
+                (0..weights.len())
+                    .map(|index| scaled(if index == top { runner_up } else { weights[top] }))
+                    .collect()
+            }
+            ScoringModel::NoisyOr => {
+                // Factors of certain findings are zero and cannot be divided out
+                let clear: Vec<f32> = weights.iter().map(|w| 1.0 - w.clamp(0.0, 1.0)).collect();
+                let certain = clear.iter().filter(|factor| **factor == 0.0).count();
+                let product: f32 = clear.iter().filter(|factor| **factor != 0.0).product();
+                clear
+                    .iter()
+                    .map(|&factor| {
+                        let without = match (factor == 0.0, certain) {
+                            (true, 1) => product,
+                            (false, 0) => product / factor,
+                            _ => 0.0,
+                        };
+                        scaled(1.0 - without)
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// A reduction over every finding of an assessment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    /// `Σ confidence × severity`
+    SeveritySum,
+    /// `Π (1 - clamp(confidence × severity, 0, 1))`, the chance no finding is real
+    ClearProduct,
+}
+
+impl Aggregate {
+    /// Reference computation, in finding order
+    pub fn compute(self, findings: &[RiskFinding]) -> f32 {
+        let weights = findings
+            .iter()
+            .map(|f| f.confidence * severity_to_f32(&f.severity));
+        match self {
+            Aggregate::SeveritySum => weights.sum(),
+            Aggregate::ClearProduct => weights.map(|w| 1.0 - w.clamp(0.0, 1.0)).product(),
+        }
+    }
+}
+
+fn saturate(sum: f32, saturation: f32) -> f32 {
+    1.0 - (-sum / saturation).exp()
+}
+
+/// Default factors for `category_weighted`, keyed by OWASP category
+pub fn default_category_weights() -> BTreeMap<String, f32> {
+    [
+        ("OWASP-SC01", 1.3), // Access control
+        ("OWASP-SC02", 1.0), // Arithmetic
+        ("OWASP-SC03", 1.3), // Delegatecall
+        ("OWASP-SC04", 1.2), // Oracle manipulation
+        ("OWASP-SC05", 1.5), // Reentrancy
+        ("OWASP-SC06", 0.9), // Unchecked call
+        ("OWASP-SC07", 0.6), // Timestamp dependence
+        ("OWASP-SC08", 0.8), // tx.origin
+        ("OWASP-SC09", 1.2), // Flash loan
+        ("OWASP-SC10", 0.7), // Input validation
+        ("COMPILER", 0.8),   // Known compiler bugs
+    ]
+    .into_iter()
+    .map(|(category, weight)| (category.to_string(), weight))
+    .collect()
+}
+
+/// A model chosen by name or given in full with parameters
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum ScoringSelection {
+    Name(String),
+    Model(ScoringModel),
+}
+
+impl ScoringSelection {
+    /// Resolve to a validated model
+    pub fn resolve(&self) -> Result<ScoringModel, String> {
+        let model = match self {
+            ScoringSelection::Name(name) => ScoringModel::named(name).ok_or_else(|| {
+                format!(
+                    "Unknown scoring model {}, expected one of {}",
+                    name,
+                    SCORING_MODEL_NAMES.join(", ")
+                )
+            })?,
+            ScoringSelection::Model(model) => model.clone(),
+        };
+        model.validate()?;
+        Ok(model)
+    }
+}
+
+/// Convert severity to its 0-1 weight
+pub fn severity_to_f32(severity: &Severity) -> f32 {
+    match severity {
+        Severity::Info => 0.1,
+        Severity::Low => 0.25,
+        Severity::Medium => 0.5,
+        Severity::High => 0.75,
+        Severity::Critical => 1.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finding(category: &str, severity: Severity, confidence: f32) -> RiskFinding {
+        RiskFinding {
+            rule_id: String::new(),
+            category: category.to_string(),
+            severity,
+            description: String::new(),
+            location: None,
+            origin: Default::default(),
+            confidence,
+        }
+    }
+
+    fn score(model: &ScoringModel, findings: &[RiskFinding]) -> f32 {
+        model.score(findings, |aggregate| aggregate.compute(findings))
+    }
+
+    #[test]
+    fn test_extra_low_finding_never_lowers_score() {
+        let critical = vec![finding("OWASP-SC05", Severity::Critical, 1.0)];
+        let mut with_low = critical.clone();
+        with_low.push(finding("OWASP-SC07", Severity::Low, 0.5));
+
+        for name in SCORING_MODEL_NAMES.iter().filter(|name| **name != "average") {
+            let model = ScoringModel::named(name).unwrap();
+            assert!(
+                score(&model, &with_low) >= score(&model, &critical),
+                "{} decreased when adding a low finding",
+                name
+            );
+        }
+
+        // The legacy average is the one model that dilutes
+        let average = ScoringModel::Average;
+        assert!(score(&average, &with_low) < score(&average, &critical));
+    }
+
+    #[test]
+    fn test_model_scores() {
+        let findings = vec![
+            finding("OWASP-SC05", Severity::High, 0.8),
+            finding("OWASP-SC07", Severity::Medium, 0.5),
+        ];
+
+        assert!((score(&ScoringModel::MaxSeverity, &findings) - 60.0).abs() < 1e-4);
+        // 1 - (1 - 0.6)(1 - 0.25)
+        assert!((score(&ScoringModel::NoisyOr, &findings) - 70.0).abs() < 1e-4);
+        // 1 - exp(-0.85 / 0.5)
+        let weighted = ScoringModel::named("weighted_sum").unwrap();
+        assert!((score(&weighted, &findings) - 81.73164).abs() < 1e-3);
+        assert_eq!(score(&ScoringModel::NoisyOr, &[]), 0.0);
+    }
+
+    #[test]
+    fn test_explanation_attributes_score() {
+        let findings = vec![
+            finding("OWASP-SC05", Severity::Critical, 0.9),
+            finding("OWASP-SC07", Severity::Low, 0.4),
+        ];
+        let model = ScoringModel::MaxSeverity;
+        let total = score(&model, &findings);
+        let explanation = model.explain(&findings, total);
+
+        assert_eq!(explanation.contributions.len(), 2);
+        let critical = &explanation.contributions[0];
+        assert_eq!(critical.severity_value, 1.0);
+        assert!((critical.weight - 0.9).abs() < 1e-6);
+        // Removing the critical finding leaves only the low one (10 points)
+        assert!((critical.marginal_contribution - 80.0).abs() < 1e-4);
+        assert_eq!(explanation.contributions[1].marginal_contribution, 0.0);
+
+        assert_eq!(explanation.threshold.level, crate::models::RiskLevel::Critical);
+        assert_eq!(explanation.threshold.crossed, 75.0);
+        assert_eq!(explanation.threshold.next, None);
+    }
+
+    #[test]
+    fn test_explanation_matches_rescoring_without_each_finding() {
+        let findings = [
+            finding("OWASP-SC05", Severity::Critical, 1.0),
+            finding("OWASP-SC01", Severity::High, 0.8),
+            finding("OWASP-SC07", Severity::Medium, 0.5),
+            finding("OWASP-SC07", Severity::Medium, 0.5),
+            finding("OWASP-SC10", Severity::Low, 0.3),
+        ];
+
+        for name in SCORING_MODEL_NAMES {
+            let model = ScoringModel::named(name).unwrap();
+            for count in 1..=findings.len() {
+                let findings = &findings[..count];
+                let total = score(&model, findings);
+                let explanation = model.explain(findings, total);
+                for (index, contribution) in explanation.contributions.iter().enumerate() {
+                    let rest: Vec<RiskFinding> = findings
+                        .iter()
+                        .enumerate()
+                        .filter(|(other, _)| *other != index)
+                        .map(|(_, f)| f.clone())
+                        .collect();
+                    let expected = total - score(&model, &rest);
+                    assert!(
+                        (contribution.marginal_contribution - expected).abs() < 1e-3,
+                        "{} with {} findings, finding {}: {} != {}",
+                        name,
+                        count,
+                        index,
+                        contribution.marginal_contribution,
+                        expected
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_selection_by_name_or_parameters() {
+        let by_name: ScoringSelection = serde_json::from_str(r#""max_severity""#).unwrap();
+        assert_eq!(by_name.resolve().unwrap(), ScoringModel::MaxSeverity);
+
+        let with_params: ScoringSelection =
+            serde_json::from_str(r#"{"model":"weighted_sum","saturation":2.0}"#).unwrap();
+        assert_eq!(
+            with_params.resolve().unwrap(),
+            ScoringModel::WeightedSum { saturation: 2.0 }
+        );
+
+        let invalid: ScoringSelection =
+            serde_json::from_str(r#"{"model":"weighted_sum","saturation":0.0}"#).unwrap();
+        assert!(invalid.resolve().is_err());
+        assert!(ScoringSelection::Name("median".to_string()).resolve().is_err());
+
+        let echoed = serde_json::to_value(ScoringModel::WeightedSum { saturation: 2.0 }).unwrap();
+        assert_eq!(echoed, serde_json::json!({"model": "weighted_sum", "saturation": 2.0}));
+    }
+}
@@ -0,0 +1,581 @@
+//! SIMD Kernels - Vectorized `Σ confidence × severity` over a findings buffer
+//!
+//! Findings are scored from a structure-of-arrays buffer so the kernels load
+//! contiguous `f32` lanes. Every kernel uses the same reduction order (eight
+//! strided partial sums, combined pairwise, then the tail in order), so the
+//! AVX2, SSE4.2 and scalar paths return bit-identical results.
+//!
+//! For batches, [`BatchFindings`] lays many assessments out column-wise with
+//! one lane per contract, so a vector covers eight contracts at once instead
+//! of one short findings list. The batch kernels reproduce the per-contract
+//! reduction order lane by lane and return the same bits as scoring each
+//! contract on its own.
+
+use std::cell::RefCell;
+
+use crate::models::RiskFinding;
+use crate::risk::scoring::{severity_to_f32, Aggregate};
+
+/// Partial sums kept by every kernel; one AVX2 register or two SSE registers
+const LANES: usize = 8;
+
+/// Findings as parallel columns
+#[derive(Debug, Clone, Default)]
+pub struct FindingsSoA {
+    pub confidence: Vec<f32>,
+    /// Severity as used in scoring (0-1)
+    pub severity: Vec<f32>,
+}
+
+impl FindingsSoA {
+    /// Replace the contents with `findings`, reusing the allocation
+    pub fn fill(&mut self, findings: &[RiskFinding]) {
+        self.confidence.clear();
+        self.severity.clear();
+        for finding in findings {
+            self.confidence.push(finding.confidence);
+            self.severity.push(severity_to_f32(&finding.severity));
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.confidence.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.confidence.is_empty()
+    }
+}
+
+thread_local! {
+    /// Per-thread buffer so scoring does not allocate per call
+    static FINDINGS_BUFFER: RefCell<FindingsSoA> = RefCell::new(FindingsSoA::default());
+}
+
+/// Run `f` over `findings` laid out in this thread's reusable buffer
+pub fn with_findings_buffer<R>(findings: &[RiskFinding], f: impl FnOnce(&FindingsSoA) -> R) -> R {
+    FINDINGS_BUFFER.with(|buffer| {
+        let mut buffer = buffer.borrow_mut();
+        buffer.fill(findings);
+        f(&buffer)
+    })
+}
+
+/// Kernel used for the severity sum
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kernel {
+    Scalar,
+    Sse42,
+    Avx2,
+}
+
+impl Kernel {
+    /// Best kernel supported by this CPU, or scalar when SIMD is disabled
+    pub fn select(enable_simd: bool) -> Self {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if enable_simd && is_x86_feature_detected!("avx2") {
+                return Kernel::Avx2;
+            }
+            if enable_simd && is_x86_feature_detected!("sse4.2") {
+                return Kernel::Sse42;
+            }
+        }
+        let _ = enable_simd;
+        Kernel::Scalar
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Kernel::Scalar => "scalar",
+            Kernel::Sse42 => "sse4.2",
+            Kernel::Avx2 => "avx2",
+        }
+    }
+
+    /// `Σ confidence[i] × severity[i]`
+    ///
+    /// Falls back to scalar if the CPU lacks the kernel's instructions.
+    pub fn severity_sum(self, findings: &FindingsSoA) -> f32 {
+        let (confidence, severity) = (&findings.confidence[..], &findings.severity[..]);
+        debug_assert_eq!(confidence.len(), severity.len());
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            match self {
+                Kernel::Avx2 if is_x86_feature_detected!("avx2") => {
+                    return unsafe { severity_sum_avx2(confidence, severity) };
+                }
+                Kernel::Sse42 if is_x86_feature_detected!("sse4.2") => {
+                    return unsafe { severity_sum_sse42(confidence, severity) };
+                }
+                _ => {}
+            }
+        }
+        severity_sum_scalar(confidence, severity)
+    }
+}
+
+/// Combine the partial sums pairwise, as a horizontal add would
+#[inline]
+fn reduce(partial: [f32; LANES]) -> f32 {
+    let quad = [
+        partial[0] + partial[4],
+        partial[1] + partial[5],
+        partial[2] + partial[6],
+        partial[3] + partial[7],
+    ];
+    (quad[0] + quad[2]) + (quad[1] + quad[3])
+}
+
+/// Reference kernel; defines the reduction order the SIMD kernels reproduce
+pub fn severity_sum_scalar(confidence: &[f32], severity: &[f32]) -> f32 {
+    let len = confidence.len().min(severity.len());
+    let full = len - len % LANES;
+
+    let mut partial = [0.0_f32; LANES];
+    for (c, s) in confidence[..full]
+        .chunks_exact(LANES)
+        .zip(severity[..full].chunks_exact(LANES))
+    {
+        for lane in 0..LANES {
+            partial[lane] += c[lane] * s[lane];
+        }
+    }
+
+    let mut total = reduce(partial);
+    for i in full..len {
+        total += confidence[i] * severity[i];
+    }
+    total
+}
+
+/// AVX2 kernel: one 8-lane accumulator
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn severity_sum_avx2(confidence: &[f32], severity: &[f32]) -> f32 {
+    use std::arch::x86_64::*;
+
+    let len = confidence.len().min(severity.len());
+    let full = len - len % LANES;
+
+    let mut acc = _mm256_setzero_ps();
+    let mut i = 0;
+    while i < full {
+        let c = _mm256_loadu_ps(confidence.as_ptr().add(i));
+        let s = _mm256_loadu_ps(severity.as_ptr().add(i));
+        // Multiply then add (no FMA) to round exactly like the scalar kernel
+        acc = _mm256_add_ps(acc, _mm256_mul_ps(c, s));
+        i += LANES;
+    }
+
+    let mut partial = [0.0_f32; LANES];
+    _mm256_storeu_ps(partial.as_mut_ptr(), acc);
+
+    let mut total = reduce(partial);
+    for j in full..len {
+        total += confidence[j] * severity[j];
+    }
+    total
+}
+
+/// SSE4.2 kernel: two 4-lane accumulators covering the same eight lanes
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse4.2")]
+unsafe fn severity_sum_sse42(confidence: &[f32], severity: &[f32]) -> f32 {
+    use std::arch::x86_64::*;
+
+    let len = confidence.len().min(severity.len());
+    let full = len - len % LANES;
+
+    let mut low = _mm_setzero_ps();
+    let mut high = _mm_setzero_ps();
+    let mut i = 0;
+    while i < full {
+        let c = _mm_loadu_ps(confidence.as_ptr().add(i));
+        let s = _mm_loadu_ps(severity.as_ptr().add(i));
+        low = _mm_add_ps(low, _mm_mul_ps(c, s));
+        let c = _mm_loadu_ps(confidence.as_ptr().add(i + 4));
+        let s = _mm_loadu_ps(severity.as_ptr().add(i + 4));
+        high = _mm_add_ps(high, _mm_mul_ps(c, s));
+        i += LANES;
+    }
+
+    let mut partial = [0.0_f32; LANES];
+    _mm_storeu_ps(partial.as_mut_ptr(), low);
+    _mm_storeu_ps(partial.as_mut_ptr().add(4), high);
+
+    let mut total = reduce(partial);
+    for j in full..len {
+        total += confidence[j] * severity[j];
+    }
+    total
+}
+
+/// Findings of many assessments, one lane per contract
+///
+/// Row `r` holds finding `r` of every contract; contracts with fewer findings
+/// are padded with zero weights, which leave sums and products unchanged.
+/// Lanes are padded to a multiple of eight.
+#[derive(Debug, Clone, Default)]
+pub struct BatchFindings {
+    contracts: usize,
+    lanes: usize,
+    rows: usize,
+    /// Findings per lane
+    lens: Vec<f32>,
+    /// Findings per lane covered by full strides of [`LANES`]
+    full: Vec<f32>,
+    /// Row-major `rows × lanes`
+    confidence: Vec<f32>,
+    severity: Vec<f32>,
+}
+
+impl BatchFindings {
+    /// Replace the contents with `batch`, reusing the allocations
+    pub fn fill(&mut self, batch: &[&[RiskFinding]]) {
+        self.contracts = batch.len();
+        self.lanes = batch.len().div_ceil(LANES) * LANES;
+        self.rows = batch.iter().map(|findings| findings.len()).max().unwrap_or(0);
+
+        self.lens.clear();
+        self.full.clear();
+        for lane in 0..self.lanes {
+            let len = batch.get(lane).map_or(0, |findings| findings.len());
+            self.lens.push(len as f32);
+            self.full.push((len - len % LANES) as f32);
+        }
+
+        let cells = self.rows * self.lanes;
+        self.confidence.clear();
+        self.confidence.resize(cells, 0.0);
+        self.severity.clear();
+        self.severity.resize(cells, 0.0);
+        for (lane, findings) in batch.iter().enumerate() {
+            for (row, finding) in findings.iter().enumerate() {
+                self.confidence[row * self.lanes + lane] = finding.confidence;
+                self.severity[row * self.lanes + lane] = severity_to_f32(&finding.severity);
+            }
+        }
+    }
+
+    /// Number of contracts
+    pub fn len(&self) -> usize {
+        self.contracts
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.contracts == 0
+    }
+
+    fn weight(&self, row: usize, lane: usize) -> f32 {
+        let cell = row * self.lanes + lane;
+        self.confidence[cell] * self.severity[cell]
+    }
+}
+
+thread_local! {
+    /// Per-thread batch buffer
+    static BATCH_BUFFER: RefCell<BatchFindings> = RefCell::new(BatchFindings::default());
+}
+
+/// Run `f` over `batch` laid out in this thread's reusable batch buffer
+pub fn with_batch_buffer<R>(batch: &[&[RiskFinding]], f: impl FnOnce(&BatchFindings) -> R) -> R {
+    BATCH_BUFFER.with(|buffer| {
+        let mut buffer = buffer.borrow_mut();
+        buffer.fill(batch);
+        f(&buffer)
+    })
+}
+
+impl Kernel {
+    /// `aggregate` of every contract in `batch`, in contract order
+    ///
+    /// Each value equals what the per-contract path computes for that contract.
+    pub fn batch_aggregate(self, batch: &BatchFindings, aggregate: Aggregate, out: &mut Vec<f32>) {
+        out.clear();
+        out.resize(batch.lanes, 0.0);
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            match self {
+                Kernel::Avx2 if is_x86_feature_detected!("avx2") => {
+                    unsafe { batch_aggregate_avx2(batch, aggregate, out) };
+                    out.truncate(batch.contracts);
+                    return;
+                }
+                Kernel::Sse42 if is_x86_feature_detected!("sse4.2") => {
+                    unsafe { batch_aggregate_sse42(batch, aggregate, out) };
+                    out.truncate(batch.contracts);
+                    return;
+                }
+                _ => {}
+            }
+        }
+        batch_aggregate_scalar(batch, aggregate, out);
+        out.truncate(batch.contracts);
+    }
+}
+
+/// Reference batch kernel: each lane on its own, as [`severity_sum_scalar`] and
+/// [`Aggregate::compute`] would
+fn batch_aggregate_scalar(batch: &BatchFindings, aggregate: Aggregate, out: &mut [f32]) {
+    for (lane, value) in out.iter_mut().enumerate().take(batch.contracts) {
+        let len = batch.lens[lane] as usize;
+        *value = match aggregate {
+            Aggregate::SeveritySum => {
+                let full = batch.full[lane] as usize;
+                let mut partial = [0.0_f32; LANES];
+                for row in 0..full {
+                    partial[row % LANES] += batch.weight(row, lane);
+                }
+                let mut total = reduce(partial);
+                for row in full..len {
+                    total += batch.weight(row, lane);
+                }
+                total
+            }
+            Aggregate::ClearProduct => (0..len)
+                .map(|row| 1.0 - batch.weight(row, lane).clamp(0.0, 1.0))
+                .product(),
+        };
+    }
+}
+
+/// AVX2 batch kernel: eight contracts per register
+///
+/// For sums, lane `k` adds rows below its full-stride count into eight
+/// partial sums, switches to a running total (the pairwise reduction) at
+/// that row, then adds the remaining rows in order.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn batch_aggregate_avx2(batch: &BatchFindings, aggregate: Aggregate, out: &mut [f32]) {
+    use std::arch::x86_64::*;
+
+    unsafe fn reduce_avx2(partial: &[__m256; LANES]) -> __m256 {
+        let q0 = _mm256_add_ps(partial[0], partial[4]);
+        let q1 = _mm256_add_ps(partial[1], partial[5]);
+        let q2 = _mm256_add_ps(partial[2], partial[6]);
+        let q3 = _mm256_add_ps(partial[3], partial[7]);
+        _mm256_add_ps(_mm256_add_ps(q0, q2), _mm256_add_ps(q1, q3))
+    }
+
+    let zero = _mm256_setzero_ps();
+    let one = _mm256_set1_ps(1.0);
+
+    for block in (0..batch.lanes).step_by(LANES) {
+        let weight = |row: usize| {
+            let cell = row * batch.lanes + block;
+            _mm256_mul_ps(
+                _mm256_loadu_ps(batch.confidence.as_ptr().add(cell)),
+                _mm256_loadu_ps(batch.severity.as_ptr().add(cell)),
+            )
+        };
+
+        let result = match aggregate {
+            Aggregate::SeveritySum => {
+                let full = _mm256_loadu_ps(batch.full.as_ptr().add(block));
+                let mut partial = [zero; LANES];
+                let mut total = zero;
+                for row in 0..batch.rows {
+                    let at = _mm256_set1_ps(row as f32);
+                    if row % LANES == 0 {
+                        let switching = _mm256_cmp_ps::<_CMP_EQ_OQ>(full, at);
+                        total = _mm256_blendv_ps(total, reduce_avx2(&partial), switching);
+                    }
+                    let w = weight(row);
+                    let strided = _mm256_cmp_ps::<_CMP_LT_OQ>(at, full);
+                    partial[row % LANES] = _mm256_add_ps(partial[row % LANES], _mm256_and_ps(strided, w));
+                    total = _mm256_add_ps(total, _mm256_andnot_ps(strided, w));
+                }
+                let switching = _mm256_cmp_ps::<_CMP_EQ_OQ>(full, _mm256_set1_ps(batch.rows as f32));
+                _mm256_blendv_ps(total, reduce_avx2(&partial), switching)
+            }
+            Aggregate::ClearProduct => {
+                let mut clear = one;
+                for row in 0..batch.rows {
+                    let w = _mm256_min_ps(_mm256_max_ps(weight(row), zero), one);
+                    clear = _mm256_mul_ps(clear, _mm256_sub_ps(one, w));
+                }
+                clear
+            }
+        };
+        _mm256_storeu_ps(out.as_mut_ptr().add(block), result);
+    }
+}
+
+/// SSE4.2 batch kernel: four contracts per register
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse4.2")]
+unsafe fn batch_aggregate_sse42(batch: &BatchFindings, aggregate: Aggregate, out: &mut [f32]) {
+    use std::arch::x86_64::*;
+
+    unsafe fn reduce_sse(partial: &[__m128; LANES]) -> __m128 {
+        let q0 = _mm_add_ps(partial[0], partial[4]);
+        let q1 = _mm_add_ps(partial[1], partial[5]);
+        let q2 = _mm_add_ps(partial[2], partial[6]);
+        let q3 = _mm_add_ps(partial[3], partial[7]);
+        _mm_add_ps(_mm_add_ps(q0, q2), _mm_add_ps(q1, q3))
+    }
+
+    let zero = _mm_setzero_ps();
+    let one = _mm_set1_ps(1.0);
+
+    for block in (0..batch.lanes).step_by(4) {
+        let weight = |row: usize| {
+            let cell = row * batch.lanes + block;
+            _mm_mul_ps(
+                _mm_loadu_ps(batch.confidence.as_ptr().add(cell)),
+                _mm_loadu_ps(batch.severity.as_ptr().add(cell)),
+            )
+        };
+
+        let result = match aggregate {
+            Aggregate::SeveritySum => {
+                let full = _mm_loadu_ps(batch.full.as_ptr().add(block));
+                let mut partial = [zero; LANES];
+                let mut total = zero;
+                for row in 0..batch.rows {
+                    let at = _mm_set1_ps(row as f32);
+                    if row % LANES == 0 {
+                        total = _mm_blendv_ps(total, reduce_sse(&partial), _mm_cmpeq_ps(full, at));
+                    }
+                    let w = weight(row);
+                    let strided = _mm_cmplt_ps(at, full);
+                    partial[row % LANES] = _mm_add_ps(partial[row % LANES], _mm_and_ps(strided, w));
+                    total = _mm_add_ps(total, _mm_andnot_ps(strided, w));
+                }
+                let switching = _mm_cmpeq_ps(full, _mm_set1_ps(batch.rows as f32));
+                _mm_blendv_ps(total, reduce_sse(&partial), switching)
+            }
+            Aggregate::ClearProduct => {
+                let mut clear = one;
+                for row in 0..batch.rows {
+                    let w = _mm_min_ps(_mm_max_ps(weight(row), zero), one);
+                    clear = _mm_mul_ps(clear, _mm_sub_ps(one, w));
+                }
+                clear
+            }
+        };
+        _mm_storeu_ps(out.as_mut_ptr().add(block), result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn random_buffer(rng: &mut StdRng, len: usize) -> FindingsSoA {
+        const SEVERITIES: [f32; 5] = [0.1, 0.25, 0.5, 0.75, 1.0];
+        FindingsSoA {
+            confidence: (0..len).map(|_| rng.gen_range(0.0..=1.0)).collect(),
+            severity: (0..len)
+                .map(|_| SEVERITIES[rng.gen_range(0..SEVERITIES.len())])
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_simd_kernels_equal_scalar() {
+        let mut rng = StdRng::seed_from_u64(0x5eed);
+        let kernels = [Kernel::Sse42, Kernel::Avx2, Kernel::select(true)];
+
+        // Every length up to a few vectors, so each tail length mod 4 and 8 is covered
+        for len in (0..=67).chain([255, 1000, 1003]) {
+            for _ in 0..25 {
+                let buffer = random_buffer(&mut rng, len);
+                let expected = Kernel::Scalar.severity_sum(&buffer);
+                for kernel in kernels {
+                    let actual = kernel.severity_sum(&buffer);
+                    assert_eq!(
+                        actual.to_bits(),
+                        expected.to_bits(),
+                        "{} differs from scalar for len {}: {} vs {}",
+                        kernel.name(),
+                        len,
+                        actual,
+                        expected
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_scalar_kernel_matches_naive_sum() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for len in [0, 1, 7, 8, 9, 33] {
+            let buffer = random_buffer(&mut rng, len);
+            let naive: f64 = buffer
+                .confidence
+                .iter()
+                .zip(&buffer.severity)
+                .map(|(c, s)| (*c as f64) * (*s as f64))
+                .sum();
+            let sum = severity_sum_scalar(&buffer.confidence, &buffer.severity);
+            assert!((sum as f64 - naive).abs() < 1e-4, "len {}: {} vs {}", len, sum, naive);
+        }
+    }
+
+    #[test]
+    fn test_forced_scalar_kernel() {
+        assert_eq!(Kernel::select(false), Kernel::Scalar);
+    }
+
+    fn random_findings(rng: &mut StdRng, len: usize) -> Vec<RiskFinding> {
+        use crate::models::Severity;
+        const SEVERITIES: [Severity; 5] =
+            [Severity::Info, Severity::Low, Severity::Medium, Severity::High, Severity::Critical];
+        (0..len)
+            .map(|_| RiskFinding {
+                rule_id: "REENT-001".to_string(),
+                category: "OWASP-SC05".to_string(),
+                severity: SEVERITIES[rng.gen_range(0..SEVERITIES.len())].clone(),
+                description: String::new(),
+                location: None,
+                origin: Default::default(),
+                confidence: rng.gen_range(0.0..=1.0),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_batch_kernels_equal_per_contract() {
+        let mut rng = StdRng::seed_from_u64(0xba7c4);
+        let kernels = [Kernel::Scalar, Kernel::Sse42, Kernel::Avx2];
+        let mut out = Vec::new();
+        let mut buffer = BatchFindings::default();
+
+        for contracts in [0, 1, 3, 8, 13, 64] {
+            for _ in 0..10 {
+                let batch: Vec<Vec<RiskFinding>> = (0..contracts)
+                    .map(|_| {
+                        let len = rng.gen_range(0..=20);
+                        random_findings(&mut rng, len)
+                    })
+                    .collect();
+                let slices: Vec<&[RiskFinding]> = batch.iter().map(Vec::as_slice).collect();
+                buffer.fill(&slices);
+
+                for kernel in kernels {
+                    kernel.batch_aggregate(&buffer, Aggregate::SeveritySum, &mut out);
+                    assert_eq!(out.len(), contracts);
+                    for (findings, sum) in batch.iter().zip(&out) {
+                        let mut single = FindingsSoA::default();
+                        single.fill(findings);
+                        let expected = Kernel::Scalar.severity_sum(&single);
+                        assert_eq!(sum.to_bits(), expected.to_bits(), "{} sum", kernel.name());
+                    }
+
+                    kernel.batch_aggregate(&buffer, Aggregate::ClearProduct, &mut out);
+                    for (findings, clear) in batch.iter().zip(&out) {
+                        let expected = Aggregate::ClearProduct.compute(findings);
+                        assert_eq!(clear.to_bits(), expected.to_bits(), "{} product", kernel.name());
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,357 @@
+//! EVM Bytecode - Instruction decoding and function attribution
+//!
+//! Bytecode is decoded once per scan into instructions with their offsets,
+//! skipping PUSH immediates so data bytes are never mistaken for opcodes.
+//! Function entry points are recovered from the selector dispatcher
+//! (`PUSH4 <selector> EQ PUSH <dest> JUMPI`) so rule hits can be attributed
+//! to the function they fall in. The CBOR metadata solc appends to the
+//! runtime code is decoded for the compiler version it records.
+
+use crate::scanner::solidity::version::Version;
+
+/// `CALLER` (msg.sender)
+pub const CALLER: u8 = 0x33;
+/// `EQ`
+pub const EQ: u8 = 0x14;
+/// `SLOAD`
+pub const SLOAD: u8 = 0x54;
+/// `JUMPI`
+pub const JUMPI: u8 = 0x57;
+/// `PUSH4`
+pub const PUSH4: u8 = 0x63;
+/// `DELEGATECALL`
+pub const DELEGATECALL: u8 = 0xf4;
+
+const PUSH1: u8 = 0x60;
+const PUSH32: u8 = 0x7f;
+const DUP1: u8 = 0x80;
+const SWAP1: u8 = 0x90;
+const LOG0: u8 = 0xa0;
+
+/// Mnemonics of the fixed-width opcodes; PUSHn, DUPn, SWAPn and LOGn are
+/// derived from their first opcode
+const MNEMONICS: &[(&str, u8)] = &[
+    ("STOP", 0x00), ("ADD", 0x01), ("MUL", 0x02), ("SUB", 0x03), ("DIV", 0x04),
+    ("SDIV", 0x05), ("MOD", 0x06), ("SMOD", 0x07), ("ADDMOD", 0x08), ("MULMOD", 0x09),
+    ("EXP", 0x0a), ("SIGNEXTEND", 0x0b), ("LT", 0x10), ("GT", 0x11), ("SLT", 0x12),
+    ("SGT", 0x13), ("EQ", 0x14), ("ISZERO", 0x15), ("AND", 0x16), ("OR", 0x17),
+    ("XOR", 0x18), ("NOT", 0x19), ("BYTE", 0x1a), ("SHL", 0x1b), ("SHR", 0x1c),
+    ("SAR", 0x1d), ("KECCAK256", 0x20), ("SHA3", 0x20), ("ADDRESS", 0x30),
+    ("BALANCE", 0x31), ("ORIGIN", 0x32), ("CALLER", 0x33), ("CALLVALUE", 0x34),
+    ("CALLDATALOAD", 0x35), ("CALLDATASIZE", 0x36), ("CALLDATACOPY", 0x37),
+    ("CODESIZE", 0x38), ("CODECOPY", 0x39), ("GASPRICE", 0x3a), ("EXTCODESIZE", 0x3b),
+    ("EXTCODECOPY", 0x3c), ("RETURNDATASIZE", 0x3d), ("RETURNDATACOPY", 0x3e),
+    ("EXTCODEHASH", 0x3f), ("BLOCKHASH", 0x40), ("COINBASE", 0x41), ("TIMESTAMP", 0x42),
+    ("NUMBER", 0x43), ("PREVRANDAO", 0x44), ("DIFFICULTY", 0x44), ("GASLIMIT", 0x45),
+    ("CHAINID", 0x46), ("SELFBALANCE", 0x47), ("BASEFEE", 0x48), ("BLOBHASH", 0x49),
+    ("BLOBBASEFEE", 0x4a), ("POP", 0x50), ("MLOAD", 0x51), ("MSTORE", 0x52),
+    ("MSTORE8", 0x53), ("SLOAD", 0x54), ("SSTORE", 0x55), ("JUMP", 0x56), ("JUMPI", 0x57),
+    ("PC", 0x58), ("MSIZE", 0x59), ("GAS", 0x5a), ("JUMPDEST", 0x5b), ("TLOAD", 0x5c),
+    ("TSTORE", 0x5d), ("MCOPY", 0x5e), ("PUSH0", 0x5f), ("CREATE", 0xf0), ("CALL", 0xf1),
+    ("CALLCODE", 0xf2), ("RETURN", 0xf3), ("DELEGATECALL", 0xf4), ("CREATE2", 0xf5),
+    ("STATICCALL", 0xfa), ("REVERT", 0xfd), ("INVALID", 0xfe), ("SELFDESTRUCT", 0xff),
+];
+
+/// Opcode of a mnemonic (`SLOAD`, `PUSH4`, `DUP2`, ...), case-insensitive
+pub fn opcode_by_name(name: &str) -> Option<u8> {
+    let name = name.to_ascii_uppercase();
+    // `first` is the opcode of `<prefix><lowest>`
+    let numbered = |prefix: &str, first: u8, lowest: u8, highest: u8| {
+        let n: u8 = name.strip_prefix(prefix)?.parse().ok()?;
+        (lowest..=highest).contains(&n).then(|| first + n - lowest)
+    };
+    MNEMONICS
+        .iter()
+        .find(|(mnemonic, _)| *mnemonic == name)
+        .map(|(_, opcode)| *opcode)
+        .or_else(|| numbered("PUSH", PUSH1, 1, 32))
+        .or_else(|| numbered("DUP", DUP1, 1, 16))
+        .or_else(|| numbered("SWAP", SWAP1, 1, 16))
+        .or_else(|| numbered("LOG", LOG0, 0, 4))
+}
+
+/// One decoded instruction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    /// Byte offset in the code
+    pub offset: u32,
+    pub opcode: u8,
+    /// PUSH immediate, big-endian; empty for other opcodes
+    pub immediate: Vec<u8>,
+}
+
+/// Compiler metadata from the CBOR blob at the end of the code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeMetadata {
+    /// `solc` entry; absent for code built before 0.5.9
+    pub solc: Option<Version>,
+    /// `experimental` entry, set by experimental pragmas such as ABIEncoderV2
+    pub experimental: bool,
+    /// Byte offset where the blob starts
+    pub offset: u32,
+}
+
+impl CodeMetadata {
+    /// Decode the trailing `<CBOR map><2-byte big-endian length>`
+    ///
+    /// Only the value shapes solc emits are understood (text keys, byte and
+    /// text strings, booleans); anything else, or a blob that does not end
+    /// exactly at the length suffix, means the code carries no metadata.
+    pub fn parse(code: &[u8]) -> Option<Self> {
+        let suffix = code.len().checked_sub(2)?;
+        let length = u16::from_be_bytes([code[suffix], code[suffix + 1]]) as usize;
+        let start = suffix.checked_sub(length)?;
+        let mut cbor = Cbor { bytes: &code[start..suffix], pos: 0 };
+
+        let entries = cbor.header(0xa0)?;
+        let mut metadata = Self { solc: None, experimental: false, offset: start as u32 };
+        for _ in 0..entries {
+            let key = cbor.text()?;
+            match cbor.peek()? {
+                0xf4 | 0xf5 => {
+                    let value = cbor.take(1)?[0] == 0xf5;
+                    if key == "experimental" {
+                        metadata.experimental = value;
+                    }
+                }
+                byte if byte >> 5 == 2 => {
+                    let value = cbor.bytes_value()?;
+                    if key == "solc" {
+                        let [major, minor, patch] = value.try_into().ok()?;
+                        metadata.solc = Some(Version::new(major as u32, minor as u32, patch as u32));
+                    }
+                }
+                byte if byte >> 5 == 3 => {
+                    // Prerelease and nightly builds record the full version string
+                    let value = cbor.text()?;
+                    if key == "solc" {
+                        metadata.solc = Version::parse(value);
+                    }
+                }
+                _ => return None,
+            }
+        }
+        (cbor.pos == cbor.bytes.len()).then_some(metadata)
+    }
+}
+
+/// Cursor over the subset of CBOR solc writes
+struct Cbor<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cbor<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn take(&mut self, count: usize) -> Option<&'a [u8]> {
+        let slice = self.bytes.get(self.pos..self.pos.checked_add(count)?)?;
+        self.pos += count;
+        Some(slice)
+    }
+
+    /// Argument of an item with major type `major` (given as its first byte)
+    fn header(&mut self, major: u8) -> Option<usize> {
+        let byte = self.take(1)?[0];
+        if byte & 0xe0 != major {
+            return None;
+        }
+        match byte & 0x1f {
+            short @ 0..=23 => Some(short as usize),
+            24 => Some(self.take(1)?[0] as usize),
+            25 => {
+                let wide = self.take(2)?;
+                Some(u16::from_be_bytes([wide[0], wide[1]]) as usize)
+            }
+            _ => None,
+        }
+    }
+
+    fn bytes_value(&mut self) -> Option<&'a [u8]> {
+        let length = self.header(0x40)?;
+        self.take(length)
+    }
+
+    fn text(&mut self) -> Option<&'a str> {
+        let length = self.header(0x60)?;
+        std::str::from_utf8(self.take(length)?).ok()
+    }
+}
+
+/// Decoded contract code
+#[derive(Debug, Clone, Default)]
+pub struct Program {
+    pub instructions: Vec<Instruction>,
+    /// Trailing compiler metadata, when the code ends with a well-formed blob
+    pub metadata: Option<CodeMetadata>,
+    /// `(entry offset, selector)` sorted by entry offset
+    functions: Vec<(u32, [u8; 4])>,
+}
+
+impl Program {
+    /// Decode hex bytecode, with or without a `0x` prefix
+    ///
+    /// Decoding stops at the first character that is not part of a hex byte;
+    /// a truncated final PUSH keeps the immediate bytes that are present.
+    pub fn decode(hex: &str) -> Self {
+        let hex = hex.trim();
+        let hex = hex.strip_prefix("0x").unwrap_or(hex).as_bytes();
+        let code: Vec<u8> = hex
+            .chunks_exact(2)
+            .map_while(|pair| std::str::from_utf8(pair).ok().and_then(|s| u8::from_str_radix(s, 16).ok()))
+            .collect();
+        Self::from_code(&code)
+    }
+
+    /// Decode raw code bytes
+    pub fn from_code(code: &[u8]) -> Self {
+        let mut instructions = Vec::new();
+        let mut pc = 0;
+        while pc < code.len() {
+            let opcode = code[pc];
+            let width = if (PUSH1..=PUSH32).contains(&opcode) {
+                (opcode - PUSH1 + 1) as usize
+            } else {
+                0
+            };
+            let end = (pc + 1 + width).min(code.len());
+            instructions.push(Instruction {
+                offset: pc as u32,
+                opcode,
+                immediate: code[pc + 1..end].to_vec(),
+            });
+            pc += 1 + width;
+        }
+
+        let functions = dispatcher_entries(&instructions);
+        Self {
+            instructions,
+            metadata: CodeMetadata::parse(code),
+            functions,
+        }
+    }
+
+    /// Length of the decoded code in bytes
+    pub fn len(&self) -> usize {
+        self.instructions
+            .last()
+            .map_or(0, |last| last.offset as usize + 1 + last.immediate.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instructions.is_empty()
+    }
+
+    /// Entry offset of the function with `selector`, if the dispatcher has it
+    pub fn entry_of(&self, selector: &[u8; 4]) -> Option<u32> {
+        self.functions
+            .iter()
+            .find(|(_, candidate)| candidate == selector)
+            .map(|(entry, _)| *entry)
+    }
+
+    /// Selector (`0x` + 8 hex digits) of the function containing `offset`
+    ///
+    /// The function is the one with the closest entry point at or before
+    /// `offset`; code before the first entry (the dispatcher) has none.
+    pub fn selector_at(&self, offset: u32) -> Option<String> {
+        let index = self.functions.partition_point(|(entry, _)| *entry <= offset);
+        let (_, selector) = self.functions.get(index.checked_sub(1)?)?;
+        Some(format!("0x{}", hex::encode(selector)))
+    }
+}
+
+/// `PUSH4 <selector> EQ PUSH<n> <dest> JUMPI` in the dispatcher
+fn dispatcher_entries(instructions: &[Instruction]) -> Vec<(u32, [u8; 4])> {
+    let mut entries: Vec<(u32, [u8; 4])> = instructions
+        .windows(4)
+        .filter_map(|window| {
+            let [push4, eq, push_dest, jumpi] = window else {
+                return None;
+            };
+            if push4.opcode != PUSH4 || eq.opcode != EQ || jumpi.opcode != JUMPI {
+                return None;
+            }
+            if !(PUSH1..=PUSH1 + 3).contains(&push_dest.opcode) {
+                return None;
+            }
+            let selector: [u8; 4] = push4.immediate.as_slice().try_into().ok()?;
+            let dest = push_dest
+                .immediate
+                .iter()
+                .fold(0_u32, |acc, byte| (acc << 8) | *byte as u32);
+            Some((dest, selector))
+        })
+        .collect();
+    entries.sort_unstable();
+    entries.dedup_by_key(|(entry, _)| *entry);
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_skips_push_data_and_maps_selectors() {
+        // Dispatcher for 0xa9059cbb -> 0x000e, then DELEGATECALL at 0x0010;
+        // the f4 inside PUSH2 0x00f4 is data, not an opcode
+        let code = "0x63a9059cbb1461000e576100f4005b5bf4";
+        let program = Program::decode(code);
+
+        let delegatecalls: Vec<u32> = program
+            .instructions
+            .iter()
+            .filter(|i| i.opcode == DELEGATECALL)
+            .map(|i| i.offset)
+            .collect();
+        assert_eq!(delegatecalls, [0x10]);
+        assert_eq!(program.selector_at(0x10).as_deref(), Some("0xa9059cbb"));
+        assert_eq!(program.selector_at(0x02), None);
+        assert_eq!(program.len(), 0x11);
+    }
+
+    #[test]
+    fn test_opcode_mnemonics() {
+        assert_eq!(opcode_by_name("sload"), Some(SLOAD));
+        assert_eq!(opcode_by_name("PUSH4"), Some(PUSH4));
+        assert_eq!(opcode_by_name("DUP16"), Some(0x8f));
+        assert_eq!(opcode_by_name("LOG0"), Some(0xa0));
+        assert_eq!(opcode_by_name("LOG4"), Some(0xa4));
+        assert_eq!(opcode_by_name("PUSH33"), None);
+        assert_eq!(opcode_by_name("JUMPX"), None);
+    }
+
+    #[test]
+    fn test_metadata_reads_solc_version() {
+        // STOP, then {"ipfs": <34 bytes>, "solc": 0x000814} with its length
+        let ipfs = format!("64697066735822{}", "11".repeat(34));
+        let cbor = format!("a2{ipfs}64736f6c6343000814");
+        let code = format!("0x00{cbor}{:04x}", cbor.len() / 2);
+        let metadata = Program::decode(&code).metadata.unwrap();
+        assert_eq!(metadata.solc, Some(Version::new(0, 8, 20)));
+        assert!(!metadata.experimental);
+        assert_eq!(metadata.offset, 1);
+
+        // {"bzzr0": <32 bytes>, "experimental": true}, no solc entry
+        let cbor = format!("a265627a7a72305820{}6c6578706572696d656e74616cf5", "22".repeat(32));
+        let code = format!("{cbor}{:04x}", cbor.len() / 2);
+        let metadata = Program::decode(&code).metadata.unwrap();
+        assert_eq!(metadata.solc, None);
+        assert!(metadata.experimental);
+
+        // A length suffix that does not frame a map is not metadata
+        assert_eq!(Program::decode("0x6000600055").metadata, None);
+        assert_eq!(Program::decode("0x00a10029").metadata, None);
+    }
+
+    #[test]
+    fn test_decode_stops_at_invalid_hex() {
+        let program = Program::decode("0xf4_owner");
+        assert_eq!(program.instructions.len(), 1);
+        assert_eq!(program.instructions[0].opcode, DELEGATECALL);
+        assert!(Program::decode("").is_empty());
+    }
+}
@@ -0,0 +1,644 @@
+//! Rule DSL - Declarative scanning rules loaded from YAML or TOML files
+//!
+//! A rule file holds a `rules` list. Each rule carries its catalogue entry
+//! and a `match` condition built from opcode sequences, selector presence,
+//! storage slot constants and source regexes, combined with `all`, `any`
+//! and `not`. Rules are compiled into [`ScanRule`]s when loaded; a rule
+//! checks bytecode or source, never both, depending on its conditions.
+//!
+//! ```yaml
+//! rules:
+//!   - id: SCAM-001
+//!     title: Owner-gated mint behind an EIP-1967 proxy
+//!     category: OWASP-SC01
+//!     severity: high
+//!     confidence: 0.8
+//!     description: Tokens can be minted by the owner through an upgradeable proxy
+//!     match:
+//!       all:
+//!         - selector: "0x40c10f19"
+//!         - not: { selector: "0x715018a6" }
+//!         - opcodes: { sequence: [CALLER, SLOAD, EQ], within: 8 }
+//!         - storage_slot: "0x360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc"
+//! ```
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::models::{Location, RiskFinding, Severity};
+use crate::scanner::bytecode::{self, Program};
+use crate::scanner::source::SourceText;
+use crate::scanner::{RuleMetadata, ScanRule};
+
+/// Contents of a rule file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleFile {
+    pub rules: Vec<RuleDefinition>,
+}
+
+/// One declarative rule as written
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleDefinition {
+    /// Stable rule id, e.g. `SCAM-001`
+    pub id: String,
+    /// Rule name; defaults to the id
+    #[serde(default)]
+    pub name: String,
+    /// Defaults to the name
+    #[serde(default)]
+    pub title: String,
+    /// OWASP Smart Contract Top 10 category, e.g. `OWASP-SC01`
+    pub category: String,
+    pub severity: Severity,
+    /// Confidence of each finding (0-1)
+    pub confidence: f32,
+    /// Finding description; defaults to the title
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub swc: Vec<String>,
+    #[serde(default)]
+    pub remediation: String,
+    #[serde(default)]
+    pub references: Vec<String>,
+    /// Condition under which the rule reports
+    #[serde(rename = "match")]
+    pub condition: Condition,
+}
+
+/// Condition of a declarative rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    /// Every condition holds
+    All(Vec<Condition>),
+    /// At least one condition holds
+    Any(Vec<Condition>),
+    /// The condition does not hold
+    Not(Box<Condition>),
+    /// Opcodes in order, each within `within` instructions of the previous
+    Opcodes(OpcodeSequence),
+    /// The dispatcher routes this selector (`0x` + 8 hex digits)
+    Selector(String),
+    /// A PUSH of this storage slot constant (hex, up to 32 bytes)
+    StorageSlot(String),
+    /// A regex matches the contract source
+    SourceRegex(String),
+}
+
+/// Opcode sequence pattern
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpcodeSequence {
+    /// Mnemonics (`SLOAD`, `PUSH4`) or hex opcodes (`0x54`)
+    pub sequence: Vec<String>,
+    /// Greatest instruction distance between consecutive opcodes; 1 means
+    /// adjacent
+    #[serde(default = "default_within")]
+    pub within: usize,
+}
+
+fn default_within() -> usize {
+    1
+}
+
+/// Input a compiled rule checks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Bytecode,
+    Source,
+}
+
+/// Condition compiled against decoded bytecode or source
+#[derive(Debug, Clone)]
+enum Matcher {
+    All(Vec<Matcher>),
+    Any(Vec<Matcher>),
+    Not(Box<Matcher>),
+    Opcodes { sequence: Vec<u8>, within: usize },
+    Selector([u8; 4]),
+    /// Slot bytes without leading zeros
+    StorageSlot(Vec<u8>),
+    SourceRegex(Regex),
+}
+
+impl Matcher {
+    /// Compile a condition, recording the input its leaves test in `target`
+    fn compile(condition: &Condition, target: &mut Option<Target>) -> Result<Self, String> {
+        let matcher = match condition {
+            Condition::All(conditions) | Condition::Any(conditions) => {
+                if conditions.is_empty() {
+                    return Err("`all` and `any` need at least one condition".to_string());
+                }
+                let compiled = conditions
+                    .iter()
+                    .map(|condition| Self::compile(condition, target))
+                    .collect::<Result<Vec<_>, _>>()?;
+                match condition {
+                    Condition::All(_) => Matcher::All(compiled),
+                    _ => Matcher::Any(compiled),
+                }
+            }
+            Condition::Not(condition) => Matcher::Not(Box::new(Self::compile(condition, target)?)),
+            Condition::Opcodes(pattern) => {
+                claim(target, Target::Bytecode)?;
+                if pattern.sequence.is_empty() {
+                    return Err("opcode sequence is empty".to_string());
+                }
+                if pattern.within == 0 {
+                    return Err("opcode `within` must be at least 1".to_string());
+                }
+                let sequence = pattern
+                    .sequence
+                    .iter()
+                    .map(|name| parse_opcode(name).ok_or_else(|| format!("unknown opcode '{}'", name)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Matcher::Opcodes { sequence, within: pattern.within }
+            }
+            Condition::Selector(selector) => {
+                claim(target, Target::Bytecode)?;
+                let bytes = parse_hex(selector)?;
+                let selector = bytes
+                    .try_into()
+                    .map_err(|_| format!("selector '{}' must be 4 bytes", selector))?;
+                Matcher::Selector(selector)
+            }
+            Condition::StorageSlot(slot) => {
+                claim(target, Target::Bytecode)?;
+                let bytes = parse_hex(slot)?;
+                if bytes.len() > 32 {
+                    return Err(format!("storage slot '{}' is longer than 32 bytes", slot));
+                }
+                Matcher::StorageSlot(trim_leading_zeros(&bytes).to_vec())
+            }
+            Condition::SourceRegex(pattern) => {
+                claim(target, Target::Source)?;
+                Matcher::SourceRegex(Regex::new(pattern).map_err(|e| e.to_string())?)
+            }
+        };
+        Ok(matcher)
+    }
+
+    /// Locations supporting a match, or `None` when the condition fails
+    ///
+    /// `not` and selector absence hold without a location.
+    fn bytecode(&self, program: &Program) -> Option<Vec<Location>> {
+        match self {
+            Matcher::All(matchers) => {
+                let mut locations = Vec::new();
+                for matcher in matchers {
+                    locations.extend(matcher.bytecode(program)?);
+                }
+                Some(locations)
+            }
+            Matcher::Any(matchers) => any(matchers.iter().map(|matcher| matcher.bytecode(program))),
+            Matcher::Not(matcher) => match matcher.bytecode(program) {
+                Some(_) => None,
+                None => Some(Vec::new()),
+            },
+            Matcher::Opcodes { sequence, within } => {
+                let instructions = &program.instructions;
+                let found: Vec<Location> = instructions
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, instruction)| instruction.opcode == sequence[0])
+                    .filter(|(start, _)| {
+                        let mut at = *start;
+                        sequence[1..].iter().all(|opcode| {
+                            let window = instructions.iter().enumerate().skip(at + 1).take(*within);
+                            match window.into_iter().find(|(_, i)| i.opcode == *opcode) {
+                                Some((next, _)) => {
+                                    at = next;
+                                    true
+                                }
+                                None => false,
+                            }
+                        })
+                    })
+                    .map(|(_, instruction)| location(program, instruction.offset))
+                    .collect();
+                non_empty(found)
+            }
+            Matcher::Selector(selector) => program
+                .entry_of(selector)
+                .map(|entry| vec![location(program, entry)]),
+            Matcher::StorageSlot(slot) => non_empty(
+                program
+                    .instructions
+                    .iter()
+                    .filter(|i| !i.immediate.is_empty() && trim_leading_zeros(&i.immediate) == slot.as_slice())
+                    .map(|i| location(program, i.offset))
+                    .collect(),
+            ),
+            Matcher::SourceRegex(_) => None,
+        }
+    }
+
+    /// Source counterpart of [`Matcher::bytecode`]
+    fn source(&self, source: &SourceText) -> Option<Vec<Location>> {
+        match self {
+            Matcher::All(matchers) => {
+                let mut locations = Vec::new();
+                for matcher in matchers {
+                    locations.extend(matcher.source(source)?);
+                }
+                Some(locations)
+            }
+            Matcher::Any(matchers) => any(matchers.iter().map(|matcher| matcher.source(source))),
+            Matcher::Not(matcher) => match matcher.source(source) {
+                Some(_) => None,
+                None => Some(Vec::new()),
+            },
+            Matcher::SourceRegex(regex) => non_empty(
+                regex
+                    .find_iter(source.text())
+                    .map(|found| source.location(found.start(), found.end()))
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+}
+
+/// Record that a leaf tests `leaf`; a rule tests one kind of input
+fn claim(target: &mut Option<Target>, leaf: Target) -> Result<(), String> {
+    match target.replace(leaf) {
+        Some(previous) if previous != leaf => Err("cannot combine bytecode and source conditions".to_string()),
+        _ => Ok(()),
+    }
+}
+
+/// Locations of every branch that holds, `None` if none does
+fn any(results: impl Iterator<Item = Option<Vec<Location>>>) -> Option<Vec<Location>> {
+    results.fold(None, |matched, result| match (matched, result) {
+        (Some(mut locations), Some(more)) => {
+            locations.extend(more);
+            Some(locations)
+        }
+        (matched, result) => matched.or(result),
+    })
+}
+
+fn non_empty(locations: Vec<Location>) -> Option<Vec<Location>> {
+    (!locations.is_empty()).then_some(locations)
+}
+
+fn location(program: &Program, offset: u32) -> Location {
+    Location::Bytecode {
+        offset,
+        selector: program.selector_at(offset),
+    }
+}
+
+fn parse_opcode(name: &str) -> Option<u8> {
+    match name.strip_prefix("0x") {
+        Some(hex) => u8::from_str_radix(hex, 16).ok(),
+        None => bytecode::opcode_by_name(name),
+    }
+}
+
+fn parse_hex(value: &str) -> Result<Vec<u8>, String> {
+    let digits = value.strip_prefix("0x").unwrap_or(value);
+    let digits = if digits.len() % 2 == 1 { format!("0{}", digits) } else { digits.to_string() };
+    hex::decode(digits).map_err(|e| format!("'{}' is not hex: {}", value, e))
+}
+
+fn trim_leading_zeros(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|byte| *byte != 0).unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+/// A compiled declarative rule
+#[derive(Debug, Clone)]
+pub struct DslRule {
+    metadata: RuleMetadata,
+    confidence: f32,
+    description: String,
+    target: Target,
+    matcher: Matcher,
+    /// Hash of the definition, so an edited rule changes the ruleset version
+    revision: u64,
+}
+
+impl DslRule {
+    /// Validate and compile a definition
+    pub fn compile(definition: &RuleDefinition) -> Result<Self, String> {
+        let fail = |e: String| format!("rule '{}': {}", definition.id, e);
+
+        if definition.id.trim().is_empty() {
+            return Err("rule without an id".to_string());
+        }
+        if !(0.0..=1.0).contains(&definition.confidence) {
+            return Err(fail("confidence must be within 0..=1".to_string()));
+        }
+
+        let mut target = None;
+        let matcher = Matcher::compile(&definition.condition, &mut target).map_err(fail)?;
+        let target = target.ok_or_else(|| fail("`match` tests neither bytecode nor source".to_string()))?;
+
+        let name = non_blank(&definition.name, &definition.id);
+        let title = non_blank(&definition.title, &name);
+        let revision = fxhash::hash64(&serde_json::to_string(definition).unwrap_or_default());
+
+        Ok(Self {
+            metadata: RuleMetadata {
+                id: definition.id.clone(),
+                name,
+                owasp: definition.category.clone(),
+                swc: definition.swc.clone(),
+                default_severity: definition.severity.clone(),
+                remediation: definition.remediation.clone(),
+                references: definition.references.clone(),
+                title: title.clone(),
+            },
+            confidence: definition.confidence,
+            description: non_blank(&definition.description, &title),
+            target,
+            matcher,
+            revision,
+        })
+    }
+
+    /// One finding per supporting location, or an unlocated one if the
+    /// condition held without any
+    fn findings(&self, locations: Option<Vec<Location>>) -> Vec<RiskFinding> {
+        let Some(locations) = locations else {
+            return Vec::new();
+        };
+        let locations = if locations.is_empty() { vec![None] } else { locations.into_iter().map(Some).collect() };
+        locations
+            .into_iter()
+            .map(|location| RiskFinding {
+                // Filled in by the scanner from the rule's metadata
+                rule_id: String::new(),
+                category: self.metadata.owasp.clone(),
+                severity: self.metadata.default_severity.clone(),
+                description: self.description.clone(),
+                location,
+                // Filled in by the scanner from the scanned input
+                origin: Default::default(),
+                confidence: self.confidence,
+            })
+            .collect()
+    }
+}
+
+fn non_blank(value: &str, fallback: &str) -> String {
+    if value.trim().is_empty() { fallback.to_string() } else { value.to_string() }
+}
+
+impl ScanRule for DslRule {
+    fn name(&self) -> String {
+        self.metadata.name.clone()
+    }
+
+    fn category(&self) -> String {
+        self.metadata.owasp.clone()
+    }
+
+    fn metadata(&self) -> RuleMetadata {
+        self.metadata.clone()
+    }
+
+    fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    fn check_bytecode(&self, _contract_address: &str, program: &Program) -> Vec<RiskFinding> {
+        if self.target != Target::Bytecode {
+            return Vec::new();
+        }
+        self.findings(self.matcher.bytecode(program))
+    }
+
+    fn check_source(&self, _contract_address: &str, source: &SourceText) -> Vec<RiskFinding> {
+        if self.target != Target::Source {
+            return Vec::new();
+        }
+        self.findings(self.matcher.source(source))
+    }
+
+    fn check_transaction(&self, _contract_address: &str, _tx_data: &str) -> Vec<RiskFinding> {
+        Vec::new()
+    }
+}
+
+/// Whether a path is a rule file
+fn is_rule_file(path: &Path) -> bool {
+    matches!(path.extension().and_then(|ext| ext.to_str()), Some("yaml" | "yml" | "toml"))
+}
+
+/// Parse a YAML rule file, writing conditions as single-key maps
+/// (`all: [...]`) rather than YAML tags
+fn parse_yaml(data: &str) -> Result<RuleFile, serde_yaml::Error> {
+    serde_yaml::with::singleton_map_recursive::deserialize(serde_yaml::Deserializer::from_str(data))
+}
+
+/// Read and compile the rules of a `.yaml`/`.yml` or `.toml` file
+pub fn read_rule_file(path: &Path) -> Result<Vec<DslRule>, String> {
+    let data = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let file: RuleFile = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("yaml" | "yml") => parse_yaml(&data).map_err(|e| format!("{}: {}", path.display(), e))?,
+        Some("toml") => toml::from_str(&data).map_err(|e| format!("{}: {}", path.display(), e))?,
+        _ => return Err(format!("{}: expected a .yaml, .yml or .toml file", path.display())),
+    };
+    file.rules
+        .iter()
+        .map(DslRule::compile)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Compile every rule file in a directory
+///
+/// Files that fail to parse or compile are skipped and reported in the
+/// error list.
+pub fn load_dir(dir: &Path) -> io::Result<(Vec<DslRule>, Vec<String>)> {
+    let mut rules = Vec::new();
+    let mut errors = Vec::new();
+    for path in rule_files(dir)? {
+        match read_rule_file(&path) {
+            Ok(compiled) => rules.extend(compiled),
+            Err(e) => errors.push(e),
+        }
+    }
+    Ok((rules, errors))
+}
+
+fn rule_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| is_rule_file(path))
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+/// Rule files with their modification time and size
+fn dir_signature(dir: &Path) -> Vec<(PathBuf, Option<SystemTime>, u64)> {
+    rule_files(dir)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|path| {
+            let meta = fs::metadata(&path).ok();
+            let modified = meta.as_ref().and_then(|meta| meta.modified().ok());
+            let len = meta.map_or(0, |meta| meta.len());
+            (path, modified, len)
+        })
+        .collect()
+}
+
+/// Call `reload` whenever a rule file in `dir` is added, changed or removed
+///
+/// The directory is checked every `interval`. `reload` keeps the current
+/// rules when it fails; the next change triggers another attempt.
+pub fn spawn_rule_reload<F>(dir: PathBuf, interval: Duration, reload: F) -> tokio::task::JoinHandle<()>
+where
+    F: Fn() -> Result<usize, String> + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        let mut signature = dir_signature(&dir);
+        loop {
+            ticker.tick().await;
+            let current = dir_signature(&dir);
+            if current == signature {
+                continue;
+            }
+            signature = current;
+            match reload() {
+                Ok(count) => tracing::info!("Reloaded {} custom rules from {}", count, dir.display()),
+                Err(e) => tracing::warn!("Keeping current rules, reload failed: {}", e),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Dispatcher for 0xa9059cbb -> 0x0b; CALLER SLOAD EQ at 0x0c, then
+    // PUSH1 0x03 (slot 3) at 0x0f
+    const CODE: &str = "0x63a9059cbb1461000b57005b33541460035b";
+
+    fn rule(yaml: &str) -> DslRule {
+        let file = parse_yaml(yaml).unwrap();
+        DslRule::compile(&file.rules[0]).unwrap()
+    }
+
+    fn offsets(findings: &[RiskFinding]) -> Vec<u32> {
+        findings
+            .iter()
+            .map(|f| match &f.location {
+                Some(Location::Bytecode { offset, selector }) => {
+                    assert_eq!(selector.as_deref(), Some("0xa9059cbb"));
+                    *offset
+                }
+                other => panic!("unexpected location {:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_bytecode_conditions_combine() {
+        let program = Program::decode(CODE);
+        let matched = rule(
+            "rules:
+  - id: SCAM-001
+    category: OWASP-SC01
+    severity: high
+    confidence: 0.8
+    match:
+      all:
+        - selector: '0xa9059cbb'
+        - not: { selector: '0x40c10f19' }
+        - opcodes: { sequence: [CALLER, EQ], within: 2 }
+        - any:
+            - storage_slot: '0x0000000000000000000000000000000000000000000000000000000000000003'
+            - storage_slot: '0xdead'
+",
+        );
+        let findings = matched.check_bytecode("0x", &program);
+        assert_eq!(offsets(&findings), [0x0b, 0x0c, 0x0f]);
+        assert!(findings.iter().all(|f| f.severity == Severity::High && f.category == "OWASP-SC01"));
+        assert!(matched.check_source("0x", &SourceText::new("CALLER")).is_empty());
+
+        let adjacent = rule(
+            "rules:
+  - id: SCAM-002
+    category: OWASP-SC01
+    severity: low
+    confidence: 0.5
+    match:
+      opcodes: { sequence: [CALLER, EQ] }
+",
+        );
+        assert!(adjacent.check_bytecode("0x", &program).is_empty());
+    }
+
+    #[test]
+    fn test_toml_source_rules_load_from_dir() {
+        let dir = std::env::temp_dir().join(format!("dsl-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("honeypot.toml"),
+            r#"[[rules]]
+id = "SRC-001"
+title = "Blocked seller list"
+category = "OWASP-SC01"
+severity = "medium"
+confidence = 0.6
+
+[rules.match]
+all = [
+    { source_regex = 'mapping\(address\s*=>\s*bool\)[^;]*[Bb]lack' },
+    { not = { source_regex = "renounceOwnership" } },
+]
+"#,
+        )
+        .unwrap();
+        fs::write(dir.join("broken.yaml"), "rules:\n  - id: BAD-001\n").unwrap();
+        fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let (rules, errors) = load_dir(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("broken.yaml"));
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].metadata().name, "SRC-001");
+        assert_eq!(rules[0].metadata().title, "Blocked seller list");
+
+        let source = SourceText::new("contract T {\n    mapping(address => bool) public blacklist;\n}\n");
+        let findings = rules[0].check_source("0x", &source);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(
+            findings[0].location,
+            Some(Location::Source { start_line: 2, start_column: 5, end_line: 2, end_column: 42 })
+        );
+        assert_eq!(findings[0].description, "Blocked seller list");
+    }
+
+    #[test]
+    fn test_mixed_targets_rejected() {
+        let file = parse_yaml(
+            "rules:
+  - id: MIX-001
+    category: OWASP-SC01
+    severity: low
+    confidence: 0.5
+    match:
+      any:
+        - selector: '0xa9059cbb'
+        - source_regex: 'transfer'
+",
+        )
+        .unwrap();
+        assert!(DslRule::compile(&file.rules[0]).unwrap_err().contains("cannot combine"));
+    }
+}
@@ -0,0 +1,327 @@
+//! Source Input - Contract source submitted with an assessment request
+//!
+//! Accepts a single Solidity file or solc standard-JSON input. Standard
+//! JSON carries several files keyed by source unit name; its remappings
+//! resolve the imports between them the way solc does.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::CompilerRange;
+use crate::scanner::solidity::version::VersionRange;
+use crate::scanner::source::SourceText;
+
+/// Source of the assessed contract
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SourceInput {
+    /// One Solidity file
+    File(String),
+    /// solc `--standard-json` input
+    StandardJson(StandardJsonInput),
+}
+
+/// solc standard-JSON input; settings other than remappings are ignored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StandardJsonInput {
+    #[serde(default = "default_language")]
+    pub language: String,
+    /// Files by source unit name, e.g. `contracts/Vault.sol`
+    pub sources: BTreeMap<String, SourceEntry>,
+    #[serde(default)]
+    pub settings: SourceSettings,
+}
+
+fn default_language() -> String {
+    "Solidity".to_string()
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SourceEntry {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    /// Locations solc would fetch the file from; not supported
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub urls: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SourceSettings {
+    /// `[context:]prefix=target`
+    #[serde(default)]
+    pub remappings: Vec<String>,
+}
+
+/// Import remapping `[context:]prefix=target`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Remapping {
+    /// Applies only to files whose name starts with this; empty for all
+    pub context: String,
+    pub prefix: String,
+    pub target: String,
+}
+
+impl Remapping {
+    pub fn parse(remapping: &str) -> Result<Self, String> {
+        let (left, target) = remapping
+            .split_once('=')
+            .ok_or_else(|| format!("remapping '{}' has no '='", remapping))?;
+        let (context, prefix) = left.split_once(':').unwrap_or(("", left));
+        if prefix.is_empty() {
+            return Err(format!("remapping '{}' has an empty prefix", remapping));
+        }
+        Ok(Self {
+            context: context.to_string(),
+            prefix: prefix.to_string(),
+            target: target.to_string(),
+        })
+    }
+}
+
+/// A parsed file ready to scan
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    /// Source unit name; `None` for a single submitted file
+    pub name: Option<String>,
+    pub text: SourceText,
+    /// Submitted files this one imports, by source unit name; imports of
+    /// files not submitted are left out. Contracts find their bases in
+    /// these files and the ones they import in turn.
+    pub imports: Vec<String>,
+}
+
+impl SourceInput {
+    /// Total size of the submitted source in bytes
+    pub fn len(&self) -> usize {
+        match self {
+            SourceInput::File(content) => content.len(),
+            SourceInput::StandardJson(input) => input
+                .sources
+                .values()
+                .filter_map(|entry| entry.content.as_ref())
+                .map(String::len)
+                .sum(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Check the input can be scanned, without parsing it
+    pub fn validate(&self) -> Result<(), String> {
+        let SourceInput::StandardJson(input) = self else {
+            return Ok(());
+        };
+        if input.language != "Solidity" {
+            return Err(format!("unsupported language '{}', expected 'Solidity'", input.language));
+        }
+        if input.sources.is_empty() {
+            return Err("standard JSON input has no sources".to_string());
+        }
+        if let Some((name, _)) = input.sources.iter().find(|(_, entry)| entry.content.is_none()) {
+            return Err(format!("source '{}' has no content; urls are not fetched", name));
+        }
+        for remapping in &input.settings.remappings {
+            Remapping::parse(remapping)?;
+        }
+        Ok(())
+    }
+
+    /// Parse every file and resolve the imports between them
+    pub fn files(&self) -> Result<Vec<SourceFile>, String> {
+        self.validate()?;
+        let input = match self {
+            SourceInput::File(content) => {
+                return Ok(vec![SourceFile { name: None, text: SourceText::new(content), imports: Vec::new() }]);
+            }
+            SourceInput::StandardJson(input) => input,
+        };
+
+        let remappings = input
+            .settings
+            .remappings
+            .iter()
+            .map(|remapping| Remapping::parse(remapping))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let files: Vec<SourceFile> = input
+            .sources
+            .iter()
+            .map(|(name, entry)| {
+                let text = SourceText::new(entry.content.as_deref().unwrap_or_default());
+                let mut imports: Vec<String> = text
+                    .ast()
+                    .imports
+                    .iter()
+                    .map(|import| resolve_import(name, &import.path, &remappings))
+                    .filter(|resolved| input.sources.contains_key(resolved))
+                    .collect();
+                imports.sort();
+                imports.dedup();
+                SourceFile { name: Some(name.clone()), text, imports }
+            })
+            .collect();
+
+        // Files of one submission are built by one compiler
+        let range = files
+            .iter()
+            .filter_map(|file| file.text.compiler_range())
+            .fold(None, |acc: Option<VersionRange>, range| {
+                Some(acc.map_or_else(|| range.clone(), |acc| acc.intersect(range)))
+            });
+        let texts: BTreeMap<&str, &SourceText> =
+            files.iter().filter_map(|file| Some((file.name.as_deref()?, &file.text))).collect();
+        let imports: BTreeMap<&str, &[String]> =
+            files.iter().filter_map(|file| Some((file.name.as_deref()?, file.imports.as_slice()))).collect();
+        Ok(files
+            .iter()
+            .map(|file| {
+                let closure = import_closure(file, &imports).into_iter().filter_map(|name| texts.get(name).copied());
+                SourceFile {
+                    name: file.name.clone(),
+                    text: file.text.clone().with_compiler_range(range.clone()).with_imports(closure),
+                    imports: file.imports.clone(),
+                }
+            })
+            .collect())
+    }
+}
+
+/// Names of the submitted files `file` imports, directly or through other
+/// imports, nearest first
+fn import_closure<'f>(file: &'f SourceFile, imports: &BTreeMap<&'f str, &'f [String]>) -> Vec<&'f str> {
+    let mut seen: BTreeSet<&str> = file.name.as_deref().into_iter().collect();
+    let mut closure: Vec<&str> = Vec::new();
+    let mut pending: Vec<&str> = file.imports.iter().map(String::as_str).collect();
+    let mut index = 0;
+    while let Some(name) = pending.get(index).copied() {
+        index += 1;
+        if seen.insert(name) {
+            closure.push(name);
+            pending.extend(imports.get(name).into_iter().flat_map(|names| names.iter().map(String::as_str)));
+        }
+    }
+    closure
+}
+
+/// Compiler range of parsed files, reported with the assessment; `None`
+/// if no file has a `pragma solidity`
+pub fn compiler_metadata(files: &[SourceFile]) -> Option<CompilerRange> {
+    let range = files.first()?.text.compiler_range()?;
+    Some(CompilerRange {
+        pragmas: files
+            .iter()
+            .flat_map(|file| &file.text.ast().pragmas)
+            .filter(|pragma| pragma.name == "solidity")
+            .map(|pragma| pragma.value.clone())
+            .collect(),
+        resolved: range.to_string(),
+        min_version: range.min().map(|version| version.to_string()),
+    })
+}
+
+/// Source unit name imported by `path` from the file `importer`
+///
+/// Relative paths are resolved against the importer's directory; other
+/// paths go through the remapping with the longest matching context, then
+/// the longest matching prefix, as solc does.
+pub fn resolve_import(importer: &str, path: &str, remappings: &[Remapping]) -> String {
+    if path.starts_with("./") || path.starts_with("../") {
+        let directory = importer.rsplit_once('/').map_or("", |(directory, _)| directory);
+        return normalize(&format!("{}/{}", directory, path));
+    }
+
+    remappings
+        .iter()
+        .filter(|remapping| importer.starts_with(&remapping.context) && path.starts_with(&remapping.prefix))
+        .max_by_key(|remapping| (remapping.context.len(), remapping.prefix.len()))
+        .map_or_else(
+            || path.to_string(),
+            |remapping| format!("{}{}", remapping.target, &path[remapping.prefix.len()..]),
+        )
+}
+
+/// Remove `.` and `..` segments from a path
+fn normalize(path: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_standard_json_imports_are_resolved() {
+        let input: SourceInput = serde_json::from_str(
+            r#"{
+                "language": "Solidity",
+                "sources": {
+                    "contracts/Vault.sol": {"content": "import \"./lib/Math.sol\";\nimport \"@oz/security/ReentrancyGuard.sol\";\nimport \"forge-std/Test.sol\";\ncontract Vault {}"},
+                    "contracts/lib/Math.sol": {"content": "library Math {}"},
+                    "lib/openzeppelin/security/ReentrancyGuard.sol": {"content": "abstract contract ReentrancyGuard {}"}
+                },
+                "settings": {"optimizer": {"enabled": true}, "remappings": ["@oz/=lib/openzeppelin/", "test:@oz/=mocks/"]}
+            }"#,
+        )
+        .unwrap();
+        assert!(input.validate().is_ok());
+
+        let files = input.files().unwrap();
+        let vault = files.iter().find(|file| file.name.as_deref() == Some("contracts/Vault.sol")).unwrap();
+        assert_eq!(vault.imports, ["contracts/lib/Math.sol", "lib/openzeppelin/security/ReentrancyGuard.sol"]);
+        assert_eq!(vault.text.ast().contracts[0].name.name, "Vault");
+
+        let remappings = [Remapping::parse("@oz/=lib/openzeppelin/").unwrap(), Remapping::parse("test:@oz/=mocks/").unwrap()];
+        assert_eq!(resolve_import("test/Vault.t.sol", "@oz/A.sol", &remappings), "mocks/A.sol");
+        assert_eq!(resolve_import("a/b/C.sol", "../../D.sol", &remappings), "D.sol");
+    }
+
+    #[test]
+    fn test_invalid_input_is_rejected() {
+        let single: SourceInput = serde_json::from_str(r#""contract A {}""#).unwrap();
+        assert!(matches!(single, SourceInput::File(_)));
+        assert_eq!(single.files().unwrap()[0].name, None);
+
+        let vyper: SourceInput = serde_json::from_str(r#"{"language": "Vyper", "sources": {"a.vy": {"content": ""}}}"#).unwrap();
+        assert!(vyper.validate().unwrap_err().contains("Vyper"));
+        let remote: SourceInput = serde_json::from_str(r#"{"sources": {"a.sol": {"urls": ["ipfs://x"]}}}"#).unwrap();
+        assert!(remote.validate().unwrap_err().contains("a.sol"));
+        let remapping: SourceInput =
+            serde_json::from_str(r#"{"sources": {"a.sol": {"content": ""}}, "settings": {"remappings": ["oops"]}}"#).unwrap();
+        assert!(remapping.validate().is_err());
+    }
+
+    #[test]
+    fn test_compiler_range_is_intersected_across_files() {
+        let input: SourceInput = serde_json::from_str(
+            r#"{"sources": {
+                "a.sol": {"content": "pragma solidity >=0.6.0 <0.8.0;\ncontract A {}"},
+                "b.sol": {"content": "pragma solidity ^0.7.6;\ncontract B {}"},
+                "c.sol": {"content": "contract C {}"}
+            }}"#,
+        )
+        .unwrap();
+        let files = input.files().unwrap();
+        assert!(files.iter().all(|file| file.text.compiler_range().unwrap().to_string() == ">=0.7.6 <0.8.0"));
+
+        let metadata = compiler_metadata(&files).unwrap();
+        assert_eq!(metadata.pragmas, [">=0.6.0 <0.8.0", "^0.7.6"]);
+        assert_eq!(metadata.resolved, ">=0.7.6 <0.8.0");
+        assert_eq!(metadata.min_version.as_deref(), Some("0.7.6"));
+
+        let unpinned: SourceInput = serde_json::from_str(r#""contract A {}""#).unwrap();
+        assert_eq!(compiler_metadata(&unpinned.files().unwrap()), None);
+    }
+}
@@ -11,27 +11,61 @@
 //! 8. tx.origin Usage
 //! 9. Flash Loan Attacks
 //! 10. Input Validation
+//!
+//! plus known solc compiler bugs for the compiler version in the pragma or
+//! the bytecode metadata.
 
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
 
-use crate::models::{RiskFinding, Severity};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
 
+use crate::models::{Location, Origin, RiskFinding, Severity};
+
+pub mod bytecode;
+pub mod dsl;
+pub mod input;
 pub mod rules;
+pub mod selection;
+pub mod solc_bugs;
+pub mod solidity;
+pub mod source;
 
+use bytecode::Program;
+use input::SourceFile;
 use rules::*;
+use source::SourceText;
+
+/// Revision of the built-in rule logic
+///
+/// Bump whenever a rule's detection logic changes so cached verdicts
+/// produced by the previous logic are treated as misses.
+pub const RULESET_REVISION: u32 = 10;
 
 /// OWASP Scanner configuration
 #[derive(Debug, Clone)]
 pub struct ScannerConfig {
     /// Enable all rules by default
     pub enable_all: bool,
-    /// Specific rules to enable
+    /// Specific rules to enable, by id or name
     pub enabled_rules: Vec<String>,
-    /// Rules to disable
+    /// Rules to disable, by id or name
     pub disabled_rules: Vec<String>,
-    /// Timeout for scanning (milliseconds)
+    /// Deadline for one scan (milliseconds); rules not finished by then
+    /// are reported as timed out
     pub timeout_ms: u64,
+    /// Threads of the scanner's rule pool; 0 uses one per CPU and 1 runs
+    /// the rules of a scan in turn
+    pub threads: usize,
+    /// Inputs smaller than this many bytes have their rules run in turn as
+    /// one pool job, where fanning each rule out costs more than it saves
+    pub parallel_min_bytes: usize,
+    /// Directory of declarative rule files scanned alongside the built-in rules
+    pub rules_dir: Option<PathBuf>,
 }
 
 impl Default for ScannerConfig {
@@ -41,6 +75,43 @@ impl Default for ScannerConfig {
             enabled_rules: vec![],
             disabled_rules: vec![],
             timeout_ms: 3000, // 3 seconds max
+            threads: 0,
+            parallel_min_bytes: 8 * 1024,
+            rules_dir: None,
+        }
+    }
+}
+
+/// Findings of one scan and the rules that missed its deadline
+#[derive(Debug, Clone, Default)]
+pub struct ScanOutcome {
+    pub findings: Vec<RiskFinding>,
+    /// Ids of the rules that did not finish before the deadline
+    pub timed_out: Vec<String>,
+    /// Run time of each finished rule in milliseconds, by rule id
+    pub rule_timings: BTreeMap<String, f64>,
+    /// Version hash of the ruleset that ran
+    pub ruleset_version: String,
+}
+
+impl ScanOutcome {
+    /// Whether some rules did not finish, so findings may be missing
+    pub fn is_partial(&self) -> bool {
+        !self.timed_out.is_empty()
+    }
+    
+    /// Add the findings of another scan of the same contract
+    ///
+    /// Timings of a rule that ran in both scans are summed.
+    pub fn merge(&mut self, other: ScanOutcome) {
+        self.findings.extend(other.findings);
+        for rule_id in other.timed_out {
+            if !self.timed_out.contains(&rule_id) {
+                self.timed_out.push(rule_id);
+            }
+        }
+        for (rule_id, elapsed) in other.rule_timings {
+            *self.rule_timings.entry(rule_id).or_default() += elapsed;
         }
     }
 }
@@ -48,45 +119,167 @@ impl Default for ScannerConfig {
 /// OWASP Smart Contract Scanner
 pub struct OwaspScanner {
     config: ScannerConfig,
-    rules: Vec<Box<dyn ScanRule + Send + Sync>>,
+    /// Active rules; replaced whole when custom rules are reloaded, while
+    /// scans in flight finish with the set they started with
+    active: RwLock<Arc<RuleSet>>,
+    /// Rule pool, started by the first scan
+    pool: OnceLock<rayon::ThreadPool>,
+}
+
+/// A rule shared between the scanner and its pool threads
+type SharedRule = Arc<dyn ScanRule + Send + Sync>;
+
+/// The rules a scanner runs at one point in time
+struct RuleSet {
+    rules: Vec<SharedRule>,
+    /// Metadata of every built-in and custom rule, including disabled ones
+    known: Vec<RuleMetadata>,
+    ruleset_version: String,
+}
+
+impl RuleSet {
+    /// The built-in rules plus `custom`, filtered by `config`
+    ///
+    /// Fails if a custom rule reuses the id of another rule.
+    fn build(config: &ScannerConfig, custom: Vec<SharedRule>) -> Result<Self, String> {
+        let mut all = OwaspScanner::builtin_rules();
+        let mut ids: HashSet<String> = all.iter().map(|rule| rule.id()).collect();
+        for rule in custom {
+            if !ids.insert(rule.id()) {
+                return Err(format!("duplicate rule id '{}'", rule.id()));
+            }
+            all.push(rule);
+        }
+        
+        let known = sorted_catalogue(&all);
+        let rules = OwaspScanner::init_rules(config, all);
+        let ruleset_version = OwaspScanner::compute_ruleset_version(&rules);
+        Ok(Self { rules, known, ruleset_version })
+    }
 }
 
 impl OwaspScanner {
     /// Create a new scanner with default configuration
     pub fn new() -> Self {
-        let config = ScannerConfig::default();
-        let rules = Self::init_rules(&config);
-        
-        Self { config, rules }
+        Self::with_config(ScannerConfig::default())
     }
     
     /// Create scanner with custom configuration
+    ///
+    /// Custom rules are loaded from `config.rules_dir`; files that fail to
+    /// load are skipped with a warning.
     pub fn with_config(config: ScannerConfig) -> Self {
-        let rules = Self::init_rules(&config);
-        Self { config, rules }
-    }
-    
-    /// Initialize all OWASP scanning rules
-    fn init_rules(config: &ScannerConfig) -> Vec<Box<dyn ScanRule + Send + Sync>> {
-        let mut rules: Vec<Box<dyn ScanRule + Send + Sync>> = vec![
-            Box::new(AccessControlRule),
-            Box::new(ArithmeticRule),
-            Box::new(DelegatecallRule),
-            Box::new(OracleManipulationRule),
-            Box::new(ReentrancyRule),
-            Box::new(UncheckedCallRule),
-            Box::new(TimestampDependenceRule),
-            Box::new(TxOriginRule),
-            Box::new(FlashLoanRule),
-            Box::new(InputValidationRule),
-        ];
+        let custom = match &config.rules_dir {
+            Some(dir) => match dsl::load_dir(dir) {
+                Ok((rules, errors)) => {
+                    for e in errors {
+                        tracing::warn!("Skipped rule file {}", e);
+                    }
+                    rules
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to read rules directory {}: {}", dir.display(), e);
+                    Vec::new()
+                }
+            },
+            None => Vec::new(),
+        };
+        let custom: Vec<SharedRule> = custom.into_iter().map(|rule| Arc::new(rule) as SharedRule).collect();
+        
+        let rule_set = RuleSet::build(&config, custom).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring custom rules: {}", e);
+            RuleSet::build(&config, Vec::new()).expect("built-in rule ids are unique")
+        });
+        Self::from_rule_set(config, rule_set)
+    }
+    
+    fn from_rule_set(config: ScannerConfig, rule_set: RuleSet) -> Self {
+        Self {
+            config,
+            active: RwLock::new(Arc::new(rule_set)),
+            pool: OnceLock::new(),
+        }
+    }
+    
+    /// Reload the custom rules from `rules_dir`
+    ///
+    /// Returns the number of custom rules loaded. If any file fails to load
+    /// the current rules are kept, so a half-saved edit never drops rules.
+    pub fn reload_custom_rules(&self) -> Result<usize, String> {
+        let Some(dir) = &self.config.rules_dir else {
+            return Err("no rules directory configured".to_string());
+        };
+        let (rules, errors) = dsl::load_dir(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        if !errors.is_empty() {
+            return Err(errors.join("; "));
+        }
+        
+        let count = rules.len();
+        let custom = rules.into_iter().map(|rule| Arc::new(rule) as SharedRule).collect();
+        let rule_set = RuleSet::build(&self.config, custom)?;
+        *self.active.write() = Arc::new(rule_set);
+        Ok(count)
+    }
+    
+    /// The active rule set
+    fn rule_set(&self) -> Arc<RuleSet> {
+        Arc::clone(&self.active.read())
+    }
+    
+    /// Hash the engine version, rule revision and active rule set
+    ///
+    /// Changes whenever the crate is released, [`RULESET_REVISION`] is bumped,
+    /// a rule is enabled/disabled or a custom rule is edited, so cached
+    /// results can be tied to the exact rules that produced them.
+    fn compute_ruleset_version(rules: &[SharedRule]) -> String {
+        let mut names: Vec<String> = rules
+            .iter()
+            .map(|r| match r.revision() {
+                0 => r.name(),
+                revision => format!("{}@{:016x}", r.name(), revision),
+            })
+            .collect();
+        names.sort();
+        
+        let fingerprint = format!(
+            "{}|{}|{}",
+            env!("CARGO_PKG_VERSION"),
+            RULESET_REVISION,
+            names.join(",")
+        );
         
+        format!("{:016x}", fxhash::hash64(&fingerprint))
+    }
+    
+    /// Every built-in scanning rule
+    fn builtin_rules() -> Vec<SharedRule> {
+        vec![
+            Arc::new(AccessControlRule),
+            Arc::new(ArithmeticRule),
+            Arc::new(DelegatecallRule),
+            Arc::new(OracleManipulationRule),
+            Arc::new(ReentrancyRule),
+            Arc::new(UncheckedCallRule),
+            Arc::new(TimestampDependenceRule),
+            Arc::new(TxOriginRule),
+            Arc::new(FlashLoanRule),
+            Arc::new(InputValidationRule),
+            Arc::new(CompilerBugRule),
+        ]
+    }
+    
+    /// Keep the rules enabled by `config`
+    fn init_rules(config: &ScannerConfig, mut rules: Vec<SharedRule>) -> Vec<SharedRule> {
         // Filter rules based on configuration
+        let listed = |list: &[String], rule: &(dyn ScanRule + Send + Sync)| {
+            list.contains(&rule.name()) || list.contains(&rule.id())
+        };
+        
         if !config.enable_all {
-            rules.retain(|rule| config.enabled_rules.contains(&rule.name()));
+            rules.retain(|rule| listed(&config.enabled_rules, rule.as_ref()));
         }
         
-        rules.retain(|rule| !config.disabled_rules.contains(&rule.name()));
+        rules.retain(|rule| !listed(&config.disabled_rules, rule.as_ref()));
         
         rules
     }
@@ -95,32 +288,56 @@ impl OwaspScanner {
     pub fn scan_bytecode(&self,
         contract_address: &str,
         bytecode: &str,
-    ) -> Vec<RiskFinding> {
-        let mut findings = Vec::new();
-        
-        for rule in &self.rules {
-            if let Some(finding) = rule.check_bytecode(contract_address, bytecode) {
-                findings.push(finding);
-            }
+    ) -> ScanOutcome {
+        self.scan_program(contract_address, Arc::new(Program::decode(bytecode)))
+    }
+    
+    /// Scan already decoded contract code for vulnerabilities
+    ///
+    /// Every rule reads the same decoded program.
+    pub fn scan_program(&self,
+        contract_address: &str,
+        program: Arc<Program>,
+    ) -> ScanOutcome {
+        self.collect(contract_address, ScanInput::Bytecode(program), Origin::Bytecode, self.deadline())
+    }
+    
+    /// Scan bytecode and the submitted source files of one contract
+    ///
+    /// All of it shares one `timeout_ms` deadline, so submitting more files
+    /// does not extend the scan; rules left for later files once it passes
+    /// are reported as timed out.
+    pub fn scan_contract(&self,
+        contract_address: &str,
+        bytecode: &str,
+        files: &[SourceFile],
+    ) -> ScanOutcome {
+        let deadline = self.deadline();
+        let program = ScanInput::Bytecode(Arc::new(Program::decode(bytecode)));
+        let mut outcome = self.collect(contract_address, program, Origin::Bytecode, deadline);
+        for file in files {
+            let origin = Origin::Source { file: file.name.clone() };
+            outcome.merge(self.collect(contract_address, ScanInput::Source(file.text.clone()), origin, deadline));
         }
-        
-        findings
+        outcome
     }
     
     /// Scan contract source code for vulnerabilities
     pub fn scan_source(&self,
         contract_address: &str,
         source_code: &str,
-    ) -> Vec<RiskFinding> {
-        let mut findings = Vec::new();
-        
-        for rule in &self.rules {
-            if let Some(finding) = rule.check_source(contract_address, source_code) {
-                findings.push(finding);
-            }
-        }
-        
-        findings
+    ) -> ScanOutcome {
+        let origin = Origin::Source { file: None };
+        self.collect(contract_address, ScanInput::Source(SourceText::new(source_code)), origin, self.deadline())
+    }
+    
+    /// Scan one submitted source file; findings name the file as their origin
+    pub fn scan_source_file(&self,
+        contract_address: &str,
+        file: &SourceFile,
+    ) -> ScanOutcome {
+        let origin = Origin::Source { file: file.name.clone() };
+        self.collect(contract_address, ScanInput::Source(file.text.clone()), origin, self.deadline())
     }
     
     /// Scan transaction data for risks
@@ -128,21 +345,177 @@ impl OwaspScanner {
         &self,
         contract_address: &str,
         tx_data: &str,
-    ) -> Vec<RiskFinding> {
-        let mut findings = Vec::new();
+    ) -> ScanOutcome {
+        self.collect(contract_address, ScanInput::Transaction(tx_data.into()), Origin::Transaction, self.deadline())
+    }
+    
+    /// Deadline of a scan starting now
+    fn deadline(&self) -> Instant {
+        Instant::now() + Duration::from_millis(self.config.timeout_ms)
+    }
+    
+    /// Run every rule, keeping the first finding per (rule, location)
+    ///
+    /// Each finding is stamped with the id of the rule that produced it and
+    /// with `origin`.
+    /// Rules run on the pool until `deadline` has passed: rules not yet
+    /// started are skipped, and a rule still running at the deadline is
+    /// no longer waited for and has its findings dropped. Both are reported
+    /// as timed out. Rules can see the deadline through [`deadline_passed`]. Findings are merged in rule order whether the rules ran
+    /// in turn or side by side.
+    fn collect(&self, contract_address: &str, input: ScanInput, origin: Origin, deadline: Instant) -> ScanOutcome {
+        let rule_set = self.rule_set();
+        let rules = &rule_set.rules;
+        let results = if self.runs_in_parallel(rules, &input) {
+            self.run_parallel(rules, contract_address, input, deadline)
+        } else {
+            self.run_sequential(rules, contract_address, input, deadline)
+        };
+        
+        let mut seen: HashSet<(String, Option<Location>)> = HashSet::new();
+        let mut outcome = ScanOutcome {
+            ruleset_version: rule_set.ruleset_version.clone(),
+            ..ScanOutcome::default()
+        };
         
-        for rule in &self.rules {
-            if let Some(finding) = rule.check_transaction(contract_address, tx_data) {
-                findings.push(finding);
+        for (rule, result) in rules.iter().zip(results) {
+            let rule_id = rule.id();
+            let Some((findings, elapsed)) = result else {
+                outcome.timed_out.push(rule_id);
+                continue;
+            };
+            
+            outcome.rule_timings.insert(rule_id.clone(), elapsed.as_secs_f64() * 1000.0);
+            for mut finding in findings {
+                finding.rule_id = rule_id.clone();
+                finding.origin = origin.clone();
+                if seen.insert((rule_id.clone(), finding.location.clone())) {
+                    outcome.findings.push(finding);
+                }
             }
         }
         
-        findings
+        outcome
+    }
+    
+    /// Whether `input` is worth handing to the rule pool
+    fn runs_in_parallel(&self, rules: &[SharedRule], input: &ScanInput) -> bool {
+        self.config.threads != 1
+            && rules.len() > 1
+            && input.len() >= self.config.parallel_min_bytes
+    }
+    
+    /// Run the rules on the pool, waiting for them until `deadline`
+    ///
+    /// Rules that report after the deadline are left to finish on the pool;
+    /// their results are discarded. Built-in rules stop at the deadline
+    /// through [`deadline_passed`], so their threads are soon free again;
+    /// declarative rules only run linear-time regexes.
+    fn run_parallel(
+        &self,
+        rules: &[SharedRule],
+        contract_address: &str,
+        input: ScanInput,
+        deadline: Instant,
+    ) -> Vec<Option<RuleResult>> {
+        let pool = self.pool();
+        let contract_address: Arc<str> = contract_address.into();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        
+        for (index, rule) in rules.iter().enumerate() {
+            let rule = Arc::clone(rule);
+            let input = input.clone();
+            let contract_address = Arc::clone(&contract_address);
+            let tx = tx.clone();
+            pool.spawn(move || {
+                if Instant::now() >= deadline {
+                    return;
+                }
+                let started = Instant::now();
+                let findings = input.check(rule.as_ref(), &contract_address, deadline);
+                // A rule returning past the deadline may have stopped short
+                if Instant::now() < deadline {
+                    let _ = tx.send((index, (findings, started.elapsed())));
+                }
+            });
+        }
+        drop(tx);
+        
+        wait_for_rules(rx, rules.len(), deadline)
+    }
+    
+    /// Run the rules in turn as one pool job, waiting for them until `deadline`
+    ///
+    /// The job stops before the next rule once the deadline has passed; a
+    /// rule stuck past it is left to finish on the pool, as in
+    /// [`OwaspScanner::run_parallel`].
+    fn run_sequential(
+        &self,
+        rules: &[SharedRule],
+        contract_address: &str,
+        input: ScanInput,
+        deadline: Instant,
+    ) -> Vec<Option<RuleResult>> {
+        let job_rules = rules.to_vec();
+        let contract_address: Arc<str> = contract_address.into();
+        let (tx, rx) = crossbeam_channel::unbounded();
+        
+        self.pool().spawn(move || {
+            for (index, rule) in job_rules.iter().enumerate() {
+                if Instant::now() >= deadline {
+                    return;
+                }
+                let started = Instant::now();
+                let findings = input.check(rule.as_ref(), &contract_address, deadline);
+                // A rule returning past the deadline may have stopped short
+                if Instant::now() >= deadline || tx.send((index, (findings, started.elapsed()))).is_err() {
+                    return;
+                }
+            }
+        });
+        
+        wait_for_rules(rx, rules.len(), deadline)
+    }
+    
+    /// The rule pool, started on first use
+    fn pool(&self) -> &rayon::ThreadPool {
+        self.pool.get_or_init(|| {
+            let threads = match self.config.threads {
+                0 => num_cpus::get(),
+                n => n,
+            };
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .thread_name(|i| format!("scanner-{}", i))
+                .build()
+                .expect("failed to start scanner rule pool")
+        })
     }
     
+    /// Get the scanner configuration
+    pub fn config(&self) -> &ScannerConfig {
+        &self.config
+    }
+
+    /// Version hash of the active ruleset
+    pub fn ruleset_version(&self) -> String {
+        self.rule_set().ruleset_version.clone()
+    }
+
     /// Get list of available rules
     pub fn available_rules(&self) -> Vec<String> {
-        self.rules.iter().map(|r| r.name()).collect()
+        self.rule_set().rules.iter().map(|r| r.name()).collect()
+    }
+
+    /// Metadata of the active rules, ordered by rule id
+    pub fn catalogue(&self) -> Vec<RuleMetadata> {
+        sorted_catalogue(&self.rule_set().rules)
+    }
+
+    /// Metadata of every built-in and custom rule, enabled or not, ordered
+    /// by rule id
+    pub fn known_rules(&self) -> Vec<RuleMetadata> {
+        self.rule_set().known.clone()
     }
 }
 
@@ -152,7 +525,118 @@ impl Default for OwaspScanner {
     }
 }
 
+thread_local! {
+    /// Deadline of the scan whose rule runs on this thread
+    static RULE_DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// Whether the scan running the current rule has passed its deadline
+///
+/// Rules check this in loops whose length depends on the input and return
+/// early once it holds, since what they return is discarded anyway. This
+/// hands a rule's pool thread back soon after the deadline instead of
+/// keeping it until the rule finishes.
+pub fn deadline_passed() -> bool {
+    RULE_DEADLINE.with(|deadline| deadline.get().is_some_and(|deadline| Instant::now() >= deadline))
+}
+
+/// Results sent by rule jobs, indexed by rule, received until `deadline`
+/// or until every job has dropped its sender
+fn wait_for_rules(
+    rx: crossbeam_channel::Receiver<(usize, RuleResult)>,
+    count: usize,
+    deadline: Instant,
+) -> Vec<Option<RuleResult>> {
+    let mut results: Vec<Option<RuleResult>> = vec![None; count];
+    block_on_pool(|| {
+        while let Ok((index, result)) = rx.recv_deadline(deadline) {
+            results[index] = Some(result);
+        }
+    });
+    results
+}
+
+/// Findings of one rule and how long it ran
+type RuleResult = (Vec<RiskFinding>, Duration);
+
+/// Input of one scan, shared by every rule
+#[derive(Clone)]
+enum ScanInput {
+    Bytecode(Arc<Program>),
+    Source(SourceText),
+    Transaction(Arc<str>),
+}
+
+impl ScanInput {
+    /// Size of the input in bytes
+    fn len(&self) -> usize {
+        match self {
+            ScanInput::Bytecode(program) => program.len(),
+            ScanInput::Source(source) => source.len(),
+            ScanInput::Transaction(tx_data) => tx_data.len(),
+        }
+    }
+
+    /// Run `rule`, which sees `deadline` through [`deadline_passed`]
+    fn check(
+        &self,
+        rule: &(dyn ScanRule + Send + Sync),
+        contract_address: &str,
+        deadline: Instant,
+    ) -> Vec<RiskFinding> {
+        let outer = RULE_DEADLINE.with(|current| current.replace(Some(deadline)));
+        let findings = match self {
+            ScanInput::Bytecode(program) => rule.check_bytecode(contract_address, program),
+            ScanInput::Source(source) => rule.check_source(contract_address, source),
+            ScanInput::Transaction(tx_data) => rule.check_transaction(contract_address, tx_data),
+        };
+        RULE_DEADLINE.with(|current| current.set(outer));
+        findings
+    }
+}
+
+/// Run `wait` without stalling a multi-threaded tokio runtime
+///
+/// Waiting for the pool blocks the calling thread; on a runtime worker the
+/// worker's other tasks are moved elsewhere first.
+fn block_on_pool<R>(wait: impl FnOnce() -> R) -> R {
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == tokio::runtime::RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(wait)
+        }
+        _ => wait(),
+    }
+}
+
+fn sorted_catalogue(rules: &[SharedRule]) -> Vec<RuleMetadata> {
+    let mut catalogue: Vec<RuleMetadata> = rules.iter().map(|r| r.metadata()).collect();
+    catalogue.sort_by(|a, b| a.id.cmp(&b.id));
+    catalogue
+}
+
+/// Catalogue entry describing a rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleMetadata {
+    /// Stable identifier reported as `rule_id` on findings, e.g. `REENT-001`
+    pub id: String,
+    /// Rule name, as used in scanner configuration
+    pub name: String,
+    pub title: String,
+    /// OWASP Smart Contract Top 10 category, or `COMPILER` for known
+    /// compiler bugs, which the Top 10 does not cover
+    pub owasp: String,
+    /// Related SWC registry entries
+    pub swc: Vec<String>,
+    /// Severity of a typical finding; individual findings may differ
+    pub default_severity: Severity,
+    pub remediation: String,
+    pub references: Vec<String>,
+}
+
 /// Trait for individual scanning rules
+///
+/// Each check returns every issue it finds, located as precisely as the
+/// input allows; the scanner drops repeats of the same (rule, location).
 pub trait ScanRule {
     /// Rule name
     fn name(&self) -> String;
@@ -160,24 +644,709 @@ pub trait ScanRule {
     /// OWASP category
     fn category(&self) -> String;
     
-    /// Check bytecode for vulnerability
+    /// Catalogue entry for the rule
+    fn metadata(&self) -> RuleMetadata;
+    
+    /// Stable rule id; never changes once published
+    fn id(&self) -> String {
+        self.metadata().id
+    }
+    
+    /// Revision of the rule's detection logic
+    ///
+    /// Built-in rules leave this at 0 and are covered by [`RULESET_REVISION`];
+    /// rules loaded from files hash their definition.
+    fn revision(&self) -> u64 {
+        0
+    }
+    
+    /// Check decoded bytecode for vulnerability
     fn check_bytecode(
         &self,
         contract_address: &str,
-        bytecode: &str,
-    ) -> Option<RiskFinding>;
+        program: &Program,
+    ) -> Vec<RiskFinding>;
     
     /// Check source code for vulnerability
     fn check_source(
         &self,
         contract_address: &str,
-        source_code: &str,
-    ) -> Option<RiskFinding>;
+        source: &SourceText,
+    ) -> Vec<RiskFinding>;
     
     /// Check transaction data for risks
     fn check_transaction(
         &self,
         contract_address: &str,
         tx_data: &str,
-    ) -> Option<RiskFinding>;
+    ) -> Vec<RiskFinding>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Severity;
+
+    /// Scanner running exactly `rules`
+    fn test_scanner(config: ScannerConfig, rules: Vec<SharedRule>) -> OwaspScanner {
+        OwaspScanner::from_rule_set(
+            config,
+            RuleSet { rules, known: Vec::new(), ruleset_version: String::new() },
+        )
+    }
+
+    const FIVE_CALLS: &str = "\
+pragma solidity ^0.8.19;
+contract Vault {
+    function a(address to) external { payable(to).transfer(1); }
+    function b(address to) external { payable(to).transfer(2); }
+    function c(address to) external { payable(to).send(3); }
+    function d(address to) external { to.call{value: 4}(\"\"); }
+    function e(address to) external { payable(to).transfer(5); }
+}
+";
+
+    #[test]
+    fn test_each_call_is_a_located_finding() {
+        let scanner = OwaspScanner::new();
+        let findings = scanner.scan_source("0xvault", FIVE_CALLS).findings;
+
+        let mut lines: Vec<u32> = findings
+            .iter()
+            .filter(|f| f.category == "OWASP-SC05")
+            .map(|f| match f.location {
+                Some(Location::Source { start_line, .. }) => start_line,
+                ref other => panic!("unexpected location {:?}", other),
+            })
+            .collect();
+        lines.sort_unstable();
+        assert_eq!(lines, [3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn test_comments_and_strings_do_not_trigger_rules() {
+        let scanner = OwaspScanner::new();
+        let source = "\
+pragma solidity ^0.8.19;
+// tx.origin, delegatecall and selfdestruct are never used here
+contract Clock {
+    /* uses block.timestamp, not now */
+    string constant NOTE = \"require(tx.origin == owner); now\";
+    mapping(address => bool) known;
+    function stamp() external returns (uint256) {
+        known[msg.sender] = true;
+        return block.timestamp;
+    }
+}
+";
+        let findings = scanner.scan_source("0xclock", source).findings;
+
+        let located: Vec<(&str, u32)> = findings
+            .iter()
+            .filter_map(|f| match f.location {
+                Some(Location::Source { start_line, .. }) => Some((f.rule_id.as_str(), start_line)),
+                _ => None,
+            })
+            .collect();
+        assert!(located.iter().all(|(id, _)| !["ORIGIN-001", "DELEG-001", "AC-001"].contains(id)));
+        assert_eq!(
+            located.iter().filter(|(id, _)| *id == "TIME-001").collect::<Vec<_>>(),
+            [&("TIME-001", 9)]
+        );
+    }
+
+    #[test]
+    fn test_access_control_is_checked_per_function() {
+        let scanner = OwaspScanner::new();
+        let source = "\
+contract Token {
+    address owner;
+    mapping(address => uint256) balances;
+    modifier onlyOwner() { require(msg.sender == owner); _; }
+    modifier restricted() { if (msg.sender != owner) revert(); _; }
+    function mint(address to, uint256 amount) external onlyOwner { _mint(to, amount); }
+    function airdrop(address to) external { _mint(to, 1); }
+    function kill() public restricted { selfdestruct(payable(owner)); }
+    function destroy() external { _destroy(); }
+    function withdraw() external { uint256 amount = balances[msg.sender]; balances[msg.sender] = 0; payable(msg.sender).transfer(amount); }
+    function setFee(uint256 fee) external { require(msg.sender == owner); }
+    function _destroy() internal { selfdestruct(payable(owner)); }
+    function _mint(address to, uint256 amount) internal { balances[to] += amount; }
+}
+";
+        let findings: Vec<(u32, String)> = scanner
+            .scan_source("0xtoken", source)
+            .findings
+            .into_iter()
+            .filter(|f| f.rule_id == "AC-001")
+            .map(|f| match f.location {
+                Some(Location::Source { start_line, .. }) => (start_line, f.description),
+                ref other => panic!("unexpected location {:?}", other),
+            })
+            .collect();
+
+        assert_eq!(findings.len(), 2, "{:?}", findings);
+        assert_eq!(findings[0].0, 7);
+        assert!(findings[0].1.contains("'airdrop' reaches mint()"));
+        assert_eq!(findings[1].0, 9);
+        assert!(findings[1].1.contains("'destroy' reaches selfdestruct through '_destroy'"));
+    }
+
+    #[test]
+    fn test_access_checks_must_compare_the_caller_with_state() {
+        let scanner = OwaspScanner::new();
+        let source = "\
+contract Token {
+    address _owner;
+    mapping(bytes32 => mapping(address => bool)) roles;
+    mapping(address => uint256) balances;
+    function mintA(address to) external { _checkAmount(1); _mint(to); }
+    function mintB(address to) external { authorizeTransfer(); _mint(to); }
+    function mintC(address to) external { require(msg.sender != address(0)); _mint(to); }
+    function mintD(address to) external onlyMinter { _mint(to); }
+    function mintE(address to) external { _checkOwner(); _mint(to); }
+    function mintF(address to) external onlyRole(\"MINTER\") { _mint(to); }
+    function mintG(address to) external { require(roles[\"MINTER\"][_msgSender()]); _mint(to); }
+    function _checkAmount(uint256 amount) internal pure { require(amount > 0); }
+    function authorizeTransfer() internal view { require(balances[msg.sender] > 0); }
+    modifier onlyMinter() { require(msg.sender == tx.origin); _; }
+    function _checkOwner() internal view { if (owner() != _msgSender()) revert(); }
+    function owner() public view returns (address) { return _owner; }
+    modifier onlyRole(bytes32 role) { _checkRole(role, msg.sender); _; }
+    function _checkRole(bytes32 role, address account) internal view { if (!roles[role][account]) revert(); }
+    function _mint(address to) internal { balances[to] += 1; }
+}
+";
+        let mut lines: Vec<u32> = scanner
+            .scan_source("0xtoken", source)
+            .findings
+            .into_iter()
+            .filter(|f| f.rule_id == "AC-001")
+            .map(|f| match f.location {
+                Some(Location::Source { start_line, .. }) => start_line,
+                ref other => panic!("unexpected location {:?}", other),
+            })
+            .collect();
+        lines.sort_unstable();
+
+        // Checks of anything but the caller against state do not count
+        assert_eq!(lines, [5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn test_access_control_follows_imported_bases() {
+        let input: input::SourceInput = serde_json::from_str(
+            r#"{"sources": {
+                "contracts/Vault.sol": {"content": "import \"@oz/access/Ownable.sol\";\ncontract Vault is Ownable {\n    function kill() external restricted { _destroy(); }\n    function destroy() external { _destroy(); }\n}"},
+                "lib/oz/access/Ownable.sol": {"content": "import \"./Context.sol\";\nabstract contract Ownable is Context {\n    address owner;\n    modifier restricted() { require(_msgSender() == owner); _; }\n}"},
+                "lib/oz/access/Context.sol": {"content": "abstract contract Context {\n    function _destroy() internal { selfdestruct(payable(msg.sender)); }\n}"}
+            }, "settings": {"remappings": ["@oz/=lib/oz/"]}}"#,
+        )
+        .unwrap();
+        let files = input.files().unwrap();
+        let vault = files.iter().find(|file| file.name.as_deref() == Some("contracts/Vault.sol")).unwrap();
+
+        // `restricted` and `_destroy` are only known through the imports
+        let findings: Vec<u32> = OwaspScanner::new()
+            .scan_source_file("0xvault", vault)
+            .findings
+            .into_iter()
+            .filter(|f| f.rule_id == "AC-001")
+            .map(|f| match f.location {
+                Some(Location::Source { start_line, .. }) => start_line,
+                ref other => panic!("unexpected location {:?}", other),
+            })
+            .collect();
+        assert_eq!(findings, [4]);
+    }
+
+    #[test]
+    fn test_reentrancy_follows_statement_order() {
+        let scanner = OwaspScanner::new();
+        let source = "\
+interface IERC20 { function transfer(address to, uint256 amount) external returns (bool); }
+contract Bank {
+    mapping(address => uint256) balances;
+    mapping(address => bool) paid;
+    IERC20 token;
+    modifier nonReentrant() { _; }
+    function withdraw() external {
+        (bool ok, ) = msg.sender.call{value: balances[msg.sender]}(\"\");
+        require(ok);
+        balances[msg.sender] = 0;
+    }
+    function move(address to, uint256 amount) external nonReentrant { balances[msg.sender] -= amount; balances[to] += amount; }
+    function settle() external nonReentrant { paid[msg.sender] = true; token.transfer(msg.sender, 1); }
+    function pay(bool early) external nonReentrant {
+        if (early) { token.transfer(msg.sender, 1); } else { paid[msg.sender] = true; }
+    }
+    function sweep(address to) external nonReentrant { _pay(to); _clear(); }
+    function claim() external nonReentrant { IERC20(address(token)).transfer(msg.sender, 1); paid[msg.sender] = true; }
+    function _pay(address to) internal { IERC20(address(token)).transfer(to, 1); }
+    function _clear() internal { delete paid[msg.sender]; }
+}
+";
+        let findings: Vec<(u32, Severity, String)> = scanner
+            .scan_source("0xbank", source)
+            .findings
+            .into_iter()
+            .filter(|f| f.rule_id == "REENT-001")
+            .map(|f| match f.location {
+                Some(Location::Source { start_line, .. }) => (start_line, f.severity, f.description),
+                ref other => panic!("unexpected location {:?}", other),
+            })
+            .collect();
+
+        // Written after the call; guarded functions are not reported
+        // themselves, nor is an order that settles state first or a write
+        // on the other branch
+        let late: Vec<u32> =
+            findings.iter().filter(|f| f.1 == Severity::Critical).map(|f| f.0).collect();
+        assert_eq!(late, [10], "{:?}", findings);
+        assert!(findings[0].2.contains("'balances' written after the external call on line 8 in 'withdraw'"));
+
+        // `move` is guarded but `withdraw` is not, so it can be reentered
+        // while `balances` is stale; `claim` and `sweep` share a guard with
+        // the other users of `paid`
+        let cross: Vec<&str> =
+            findings.iter().filter(|f| f.1 == Severity::High).map(|f| f.2.as_str()).collect();
+        assert_eq!(cross, ["Cross-function reentrancy: 'move' uses 'balances', which 'withdraw' updates after an external call"]);
+    }
+
+    #[test]
+    fn test_compiler_range_drives_arithmetic_and_abi_coder() {
+        let scanner = OwaspScanner::new();
+        let rule_findings = |source: &str, rule_id: &str| -> Vec<(Severity, String)> {
+            scanner
+                .scan_source("0xtoken", source)
+                .findings
+                .into_iter()
+                .filter(|f| f.rule_id == rule_id)
+                .map(|f| (f.severity, f.description))
+                .collect()
+        };
+
+        for pragma in [">=0.6.0 <0.8.0", "0.7.6", "~0.5", "^0.4.24"] {
+            let source = format!("pragma solidity {};\ncontract T {{ uint256 total; }}\n", pragma);
+            let findings = rule_findings(&source, "ARITH-001");
+            assert_eq!(findings.len(), 1, "{}", pragma);
+            assert_eq!(findings[0].0, Severity::High, "{}", pragma);
+        }
+        let straddling = rule_findings("pragma solidity >=0.7.0 <0.9.0;\ncontract T {}\n", "ARITH-001");
+        assert_eq!(straddling[0].0, Severity::Medium);
+        assert!(straddling[0].1.contains(">=0.7.0 <0.9.0"));
+        assert!(rule_findings("pragma solidity >=0.8.0;\ncontract T {}\n", "ARITH-001").is_empty());
+
+        let experimental = rule_findings(
+            "pragma solidity ^0.5.0;\npragma experimental ABIEncoderV2;\ncontract T { function f(uint256 a) external { require(a > 0); } }\n",
+            "INPUT-001",
+        );
+        assert_eq!(experimental.len(), 1);
+        assert!(experimental[0].1.contains("Experimental ABI coder v2"));
+        assert!(rule_findings(
+            "pragma solidity ^0.6.12;\npragma experimental ABIEncoderV2;\ncontract T { function f(uint256 a) external { require(a > 0); } }\n",
+            "INPUT-001",
+        )
+        .is_empty());
+        let legacy_coder = rule_findings(
+            "pragma solidity ^0.8.0;\npragma abicoder v1;\ncontract T { function f(uint256 a) external { require(a > 0); } }\n",
+            "INPUT-001",
+        );
+        assert_eq!(legacy_coder.len(), 1);
+        assert_eq!(legacy_coder[0].0, Severity::Low);
+        // `pragma abicoder` rules out the compilers where v2 was experimental
+        assert!(rule_findings(
+            "pragma solidity >=0.5.0 <0.9.0;\npragma experimental ABIEncoderV2;\npragma abicoder v2;\ncontract T { function f(uint256 a) external { require(a > 0); } }\n",
+            "INPUT-001",
+        )
+        .is_empty());
+        assert!(rule_findings(
+            "pragma solidity >=0.5.0 <0.8.0;\npragma abicoder v1;\ncontract T { function f(uint256 a) external { require(a > 0); } }\n",
+            "INPUT-001",
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_known_compiler_bugs_from_pragma_and_metadata() {
+        let scanner = OwaspScanner::new();
+        let bugs = |findings: Vec<RiskFinding>| -> Vec<RiskFinding> {
+            findings.into_iter().filter(|f| f.rule_id == "SOLC-001").collect()
+        };
+        let source_bugs = |source: &str| bugs(scanner.scan_source("0xtoken", source).findings);
+
+        let pinned = source_bugs("pragma solidity 0.4.24;\ncontract T {}\n");
+        assert_eq!(pinned.len(), 1);
+        assert_eq!(pinned[0].severity, Severity::High);
+        assert!(pinned[0].description.contains("ExpExponentCleanup"));
+        assert!(!pinned[0].description.contains("EventStructWrongData"));
+        assert_eq!(pinned[0].location, Some(Location::Source { start_line: 1, start_column: 1, end_line: 1, end_column: 24 }));
+
+        // ABI coder v2 bugs only count once the pragma enables it
+        let encoder = source_bugs("pragma solidity 0.4.24;\npragma experimental ABIEncoderV2;\ncontract T {}\n");
+        assert!(encoder[0].description.contains("EventStructWrongData"));
+
+        // A floating pragma is only charged with bugs its newest release has
+        assert!(source_bugs("pragma solidity ^0.8.20;\ncontract T {}\n").is_empty());
+        assert!(source_bugs("contract T {}\n").is_empty());
+
+        // {"ipfs": <34 bytes>, "solc": 0.8.14}: the worst bug needs the Yul
+        // optimizer, which the bytecode does not confirm
+        let cbor = format!("a264697066735822{}64736f6c634300080e", "11".repeat(34));
+        let code = format!("0x00{}{:04x}", cbor, cbor.len() / 2);
+        let compiled = bugs(scanner.scan_bytecode("0xtoken", &code).findings);
+        assert_eq!(compiled.len(), 1);
+        assert_eq!(compiled[0].severity, Severity::High);
+        assert_eq!(compiled[0].confidence, 0.6);
+        assert!(compiled[0].description.contains("StorageWriteRemovalBeforeConditionalTermination"));
+        assert!(compiled[0].description.starts_with("Compiled with solc 0.8.14"));
+        assert!(compiled[0].description.contains("InlineAssemblyMemorySideEffects"));
+        assert!(matches!(compiled[0].location, Some(Location::Bytecode { offset: 1, .. })));
+    }
+
+    #[test]
+    fn test_bytecode_findings_carry_offset_and_selector() {
+        let scanner = OwaspScanner::new();
+        let findings = scanner.scan_bytecode("0xproxy", "0x63a9059cbb1461000e576100f4005b5bf4").findings;
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(
+            findings[0].location,
+            Some(Location::Bytecode { offset: 0x10, selector: Some("0xa9059cbb".to_string()) })
+        );
+    }
+
+    struct RepeatingRule;
+
+    impl ScanRule for RepeatingRule {
+        fn name(&self) -> String {
+            "REPEATING".to_string()
+        }
+
+        fn category(&self) -> String {
+            "TEST".to_string()
+        }
+
+        fn metadata(&self) -> RuleMetadata {
+            RuleMetadata {
+                id: "TEST-001".to_string(),
+                name: self.name(),
+                title: "Repeats every finding".to_string(),
+                owasp: self.category(),
+                swc: vec![],
+                default_severity: Severity::Low,
+                remediation: String::new(),
+                references: vec![],
+            }
+        }
+
+        fn check_bytecode(&self, _contract_address: &str, _program: &Program) -> Vec<RiskFinding> {
+            Vec::new()
+        }
+
+        fn check_source(&self, _contract_address: &str, source: &SourceText) -> Vec<RiskFinding> {
+            let mut findings: Vec<RiskFinding> = source
+                .occurrences("x")
+                .map(|location| RiskFinding {
+                    rule_id: String::new(),
+                    category: self.category(),
+                    severity: Severity::Low,
+                    description: "x".to_string(),
+                    location: Some(location),
+                    origin: Default::default(),
+                    confidence: 0.5,
+                })
+                .collect();
+            findings.extend(findings.clone());
+            findings
+        }
+
+        fn check_transaction(&self, _contract_address: &str, _tx_data: &str) -> Vec<RiskFinding> {
+            Vec::new()
+        }
+    }
+
+    #[test]
+    fn test_findings_deduplicated_by_rule_and_location() {
+        let scanner = test_scanner(
+            ScannerConfig::default(),
+            vec![Arc::new(RepeatingRule), Arc::new(RepeatingRule)],
+        );
+
+        let findings = scanner.scan_source("0x", "x\nx x").findings;
+        assert_eq!(findings.len(), 3);
+        assert!(findings.iter().all(|f| f.rule_id == "TEST-001"));
+    }
+
+    #[test]
+    fn test_custom_rules_load_and_reload() {
+        let dir = std::env::temp_dir().join(format!("rules-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let write = |confidence: f32| {
+            std::fs::write(
+                dir.join("proxy.yaml"),
+                format!(
+                    "rules:\n  - id: PROXY-001\n    category: OWASP-SC03\n    severity: medium\n    \
+                     confidence: {}\n    match:\n      opcodes: {{ sequence: [DELEGATECALL] }}\n",
+                    confidence
+                ),
+            )
+            .unwrap()
+        };
+        write(0.5);
+
+        let scanner = OwaspScanner::with_config(ScannerConfig {
+            rules_dir: Some(dir.clone()),
+            ..ScannerConfig::default()
+        });
+        assert!(scanner.known_rules().iter().any(|rule| rule.id == "PROXY-001"));
+        let outcome = scanner.scan_bytecode("0xproxy", "0xf4");
+        assert!(outcome.findings.iter().any(|f| f.rule_id == "PROXY-001" && f.confidence == 0.5));
+        let version = scanner.ruleset_version();
+
+        // An edited rule is picked up and changes the ruleset version
+        write(0.9);
+        assert_eq!(scanner.reload_custom_rules(), Ok(1));
+        assert_ne!(scanner.ruleset_version(), version);
+        let outcome = scanner.scan_bytecode("0xproxy", "0xf4");
+        assert!(outcome.findings.iter().any(|f| f.rule_id == "PROXY-001" && f.confidence == 0.9));
+
+        // A broken file or a duplicate id keeps the current rules
+        std::fs::write(dir.join("broken.yaml"), "rules: [").unwrap();
+        assert!(scanner.reload_custom_rules().is_err());
+        std::fs::remove_file(dir.join("broken.yaml")).unwrap();
+        std::fs::write(
+            dir.join("dup.yaml"),
+            "rules:\n  - id: AC-001\n    category: X\n    severity: low\n    confidence: 0.1\n    \
+             match: { selector: '0x12345678' }\n",
+        )
+        .unwrap();
+        assert!(scanner.reload_custom_rules().unwrap_err().contains("duplicate rule id 'AC-001'"));
+        assert!(scanner.available_rules().contains(&"PROXY-001".to_string()));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_catalogue_ids_are_unique_and_stamped() {
+        let scanner = OwaspScanner::new();
+        let catalogue = scanner.catalogue();
+        let ids: HashSet<&str> = catalogue.iter().map(|rule| rule.id.as_str()).collect();
+        assert_eq!(ids.len(), scanner.available_rules().len());
+        assert!(catalogue.iter().all(|rule| !rule.references.is_empty()));
+
+        let findings = scanner.scan_source("0xvault", FIVE_CALLS).findings;
+        assert!(!findings.is_empty());
+        assert!(findings.iter().all(|f| ids.contains(f.rule_id.as_str())));
+        assert!(findings.iter().any(|f| f.rule_id == "REENT-001"));
+    }
+
+    /// Sleeps, then reports one unlocated finding
+    struct SleepingRule(&'static str, Duration);
+
+    impl ScanRule for SleepingRule {
+        fn name(&self) -> String {
+            self.0.to_string()
+        }
+
+        fn category(&self) -> String {
+            "TEST".to_string()
+        }
+
+        fn metadata(&self) -> RuleMetadata {
+            RuleMetadata {
+                id: self.0.to_string(),
+                name: self.name(),
+                title: "Sleeps".to_string(),
+                owasp: self.category(),
+                swc: vec![],
+                default_severity: Severity::Low,
+                remediation: String::new(),
+                references: vec![],
+            }
+        }
+
+        fn check_bytecode(&self, _contract_address: &str, _program: &Program) -> Vec<RiskFinding> {
+            Vec::new()
+        }
+
+        fn check_source(&self, _contract_address: &str, _source: &SourceText) -> Vec<RiskFinding> {
+            std::thread::sleep(self.1);
+            vec![RiskFinding {
+                rule_id: String::new(),
+                category: self.category(),
+                severity: Severity::Low,
+                description: self.0.to_string(),
+                location: None,
+                origin: Default::default(),
+                confidence: 0.5,
+            }]
+        }
+
+        fn check_transaction(&self, _contract_address: &str, _tx_data: &str) -> Vec<RiskFinding> {
+            Vec::new()
+        }
+    }
+
+    fn sleeping_scanner(config: ScannerConfig) -> OwaspScanner {
+        test_scanner(
+            config,
+            vec![
+                Arc::new(SleepingRule("FAST-001", Duration::ZERO)),
+                Arc::new(SleepingRule("SLOW-001", Duration::from_millis(100))),
+                Arc::new(SleepingRule("LATE-001", Duration::ZERO)),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_deadline_returns_partial_findings() {
+        let scanner = sleeping_scanner(ScannerConfig { timeout_ms: 50, threads: 1, ..ScannerConfig::default() });
+
+        let started = Instant::now();
+        let outcome = scanner.scan_source("0x", "");
+        assert!(started.elapsed() < Duration::from_millis(1000));
+
+        assert!(outcome.is_partial());
+        assert_eq!(outcome.timed_out, ["SLOW-001", "LATE-001"]);
+        assert_eq!(outcome.findings.len(), 1);
+        assert_eq!(outcome.findings[0].rule_id, "FAST-001");
+        assert_eq!(outcome.rule_timings.keys().collect::<Vec<_>>(), ["FAST-001"]);
+    }
+
+    #[test]
+    fn test_parallel_deadline_keeps_rules_that_finished() {
+        let scanner = sleeping_scanner(ScannerConfig {
+            timeout_ms: 50,
+            threads: 3,
+            parallel_min_bytes: 0,
+            ..ScannerConfig::default()
+        });
+
+        let started = Instant::now();
+        let outcome = scanner.scan_source("0x", "");
+        assert!(started.elapsed() < Duration::from_millis(1000));
+
+        // A slow rule no longer holds up the rules after it
+        assert_eq!(outcome.timed_out, ["SLOW-001"]);
+        let ids: Vec<&str> = outcome.findings.iter().map(|f| f.rule_id.as_str()).collect();
+        assert_eq!(ids, ["FAST-001", "LATE-001"]);
+        assert_eq!(outcome.rule_timings.len(), 2);
+    }
+
+    #[test]
+    fn test_parallel_scan_matches_sequential() {
+        let source = FIVE_CALLS.repeat(200);
+        let sequential = OwaspScanner::with_config(ScannerConfig { threads: 1, ..ScannerConfig::default() });
+        let parallel = OwaspScanner::with_config(ScannerConfig { threads: 4, ..ScannerConfig::default() });
+        assert!(source.len() >= parallel.config().parallel_min_bytes);
+
+        let expected = sequential.scan_source("0xvault", &source);
+        let outcome = parallel.scan_source("0xvault", &source);
+        assert!(parallel.pool.get().is_some());
+        assert!(!outcome.is_partial());
+        let located = |outcome: &ScanOutcome| -> Vec<(String, Option<Location>)> {
+            outcome.findings.iter().map(|f| (f.rule_id.clone(), f.location.clone())).collect()
+        };
+        assert_eq!(located(&outcome), located(&expected));
+        assert_eq!(
+            outcome.rule_timings.keys().collect::<Vec<_>>(),
+            expected.rule_timings.keys().collect::<Vec<_>>()
+        );
+
+        // Tiny inputs run in turn and give the same findings
+        let tiny = OwaspScanner::with_config(ScannerConfig { threads: 4, ..ScannerConfig::default() });
+        assert!(!tiny.runs_in_parallel(&tiny.rule_set().rules, &ScanInput::Source(SourceText::new(FIVE_CALLS))));
+        let expected = sequential.scan_source("0xvault", FIVE_CALLS);
+        assert_eq!(located(&tiny.scan_source("0xvault", FIVE_CALLS)), located(&expected));
+    }
+
+    #[test]
+    fn test_contract_scan_shares_one_deadline_across_files() {
+        let scanner = test_scanner(
+            ScannerConfig { timeout_ms: 150, threads: 1, ..ScannerConfig::default() },
+            vec![Arc::new(SleepingRule("SLOW-001", Duration::from_millis(60)))],
+        );
+        let files: Vec<SourceFile> = (0..6)
+            .map(|i| SourceFile {
+                name: Some(format!("File{}.sol", i)),
+                text: SourceText::new(""),
+                imports: Vec::new(),
+            })
+            .collect();
+
+        // Six 60ms files would take 360ms with a deadline per file
+        let started = Instant::now();
+        let outcome = scanner.scan_contract("0x", "", &files);
+        assert!(started.elapsed() < Duration::from_millis(300));
+        assert!(outcome.is_partial());
+        assert!(outcome.findings.len() < files.len());
+    }
+
+    /// Works until the scan's deadline, as built-in rules do on a
+    /// pathological contract
+    struct SpinningRule;
+
+    impl ScanRule for SpinningRule {
+        fn name(&self) -> String {
+            "SPIN-001".to_string()
+        }
+
+        fn category(&self) -> String {
+            "TEST".to_string()
+        }
+
+        fn metadata(&self) -> RuleMetadata {
+            RuleMetadata { title: "Spins".to_string(), ..SleepingRule("SPIN-001", Duration::ZERO).metadata() }
+        }
+
+        fn check_bytecode(&self, _contract_address: &str, _program: &Program) -> Vec<RiskFinding> {
+            Vec::new()
+        }
+
+        fn check_source(&self, _contract_address: &str, _source: &SourceText) -> Vec<RiskFinding> {
+            let started = Instant::now();
+            while !deadline_passed() && started.elapsed() < Duration::from_secs(5) {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            Vec::new()
+        }
+
+        fn check_transaction(&self, _contract_address: &str, _tx_data: &str) -> Vec<RiskFinding> {
+            Vec::new()
+        }
+    }
+
+    #[test]
+    fn test_pool_recovers_after_timed_out_rule() {
+        let scanner = test_scanner(
+            ScannerConfig { timeout_ms: 50, threads: 1, ..ScannerConfig::default() },
+            vec![Arc::new(SleepingRule("FAST-001", Duration::ZERO)), Arc::new(SpinningRule)],
+        );
+
+        // Each scan's spinning rule gives the one pool thread back at its
+        // deadline, so the next scan's rules still get to run
+        for _ in 0..3 {
+            let outcome = scanner.scan_source("0x", "");
+            assert_eq!(outcome.timed_out, ["SPIN-001"]);
+            assert_eq!(outcome.findings.len(), 1);
+        }
+        assert!(!deadline_passed());
+    }
+
+    #[test]
+    fn test_sequential_deadline_does_not_wait_for_stuck_rule() {
+        let scanner = test_scanner(
+            ScannerConfig { timeout_ms: 50, threads: 1, ..ScannerConfig::default() },
+            vec![
+                Arc::new(SleepingRule("FAST-001", Duration::ZERO)),
+                Arc::new(SleepingRule("STUCK-001", Duration::from_secs(2))),
+            ],
+        );
+
+        let started = Instant::now();
+        let outcome = scanner.scan_source("0x", "");
+        assert!(started.elapsed() < Duration::from_millis(500));
+        assert_eq!(outcome.timed_out, ["STUCK-001"]);
+        assert_eq!(outcome.findings.len(), 1);
+    }
 }
@@ -1,7 +1,439 @@
 //! OWASP Smart Contract Top 10 Scanning Rules
 
-use crate::models::{RiskFinding, Severity};
-use crate::scanner::ScanRule;
+use crate::models::{Location, RiskFinding, Severity};
+use crate::scanner::bytecode::{self, Program};
+use crate::scanner::solc_bugs::{BugList, KnownBug};
+use crate::scanner::solidity::analysis::ContractScope;
+use crate::scanner::solidity::ast::{
+    ContractKind, Expression, ExpressionKind, Function, FunctionKind, Mutability, SourceUnit, Span, StatementKind,
+};
+use crate::scanner::solidity::version::{Version, VersionRange};
+use crate::scanner::source::SourceText;
+use crate::scanner::{deadline_passed, RuleMetadata, ScanRule};
+
+const OWASP_TOP_10: &str = "https://owasp.org/www-project-smart-contract-top-10/";
+const SWC_REGISTRY: &str = "https://swcregistry.io/docs/";
+
+/// Instructions searched around a `CALLER` for the owner comparison
+const OWNER_CHECK_WINDOW: usize = 8;
+
+fn finding(
+    category: String,
+    severity: Severity,
+    description: &str,
+    location: Option<Location>,
+    confidence: f32,
+) -> RiskFinding {
+    RiskFinding {
+        // Filled in by the scanner from the rule's metadata
+        rule_id: String::new(),
+        category,
+        severity,
+        description: description.to_string(),
+        location,
+        // Filled in by the scanner from the scanned input
+        origin: Default::default(),
+        confidence,
+    }
+}
+
+/// Catalogue entry referencing the OWASP Top 10 and each SWC entry
+fn metadata(
+    rule: &dyn ScanRule,
+    id: &str,
+    title: &str,
+    swc: &[&str],
+    default_severity: Severity,
+    remediation: &str,
+) -> RuleMetadata {
+    let references = std::iter::once(OWASP_TOP_10.to_string())
+        .chain(swc.iter().map(|entry| format!("{}{}", SWC_REGISTRY, entry)))
+        .collect();
+
+    RuleMetadata {
+        id: id.to_string(),
+        name: rule.name(),
+        title: title.to_string(),
+        owasp: rule.category(),
+        swc: swc.iter().map(|entry| entry.to_string()).collect(),
+        default_severity,
+        remediation: remediation.to_string(),
+        references,
+    }
+}
+
+/// Location of a bytecode instruction, attributed to its function
+fn bytecode_location(program: &Program, offset: u32) -> Location {
+    Location::Bytecode {
+        offset,
+        selector: program.selector_at(offset),
+    }
+}
+
+/// Every expression in the source, in source order
+fn expressions(unit: &SourceUnit) -> Vec<&Expression> {
+    let mut expressions = Vec::new();
+    unit.walk_expressions(&mut |expression| expressions.push(expression));
+    expressions
+}
+
+/// Spans of the `member` name in every `<expr>.member` access
+fn member_spans(unit: &SourceUnit, member: &str) -> Vec<Span> {
+    expressions(unit)
+        .into_iter()
+        .filter_map(|expression| match &expression.kind {
+            ExpressionKind::Member { member: name, .. } if name.name == member => Some(name.span),
+            _ => None,
+        })
+        .collect()
+}
+
+/// `target.call(...)`, `target.call{...}(...)` or `target.delegatecall(...)`,
+/// with the span of the member name
+fn low_level_call(expression: &Expression) -> Option<Span> {
+    let (_, member) = expression.unparenthesized().member_call()?;
+    matches!(member.name.as_str(), "call" | "delegatecall").then_some(member.span)
+}
+
+/// Lines with an identifier containing one of `patterns`, e.g.
+/// `latestRoundData`, each line once
+fn identifier_lines(source: &SourceText, patterns: &[&str]) -> Vec<u32> {
+    let mut lines: Vec<u32> = source
+        .identifiers()
+        .filter(|(name, _)| patterns.iter().any(|p| name.contains(p)))
+        .map(|(_, span)| source.line_of(span.start))
+        .collect();
+    lines.dedup();
+    lines
+}
+
+/// Operations that need access control when named by a called or calling
+/// function, matched as the start of a camelCase name: `mint`, `_mint`,
+/// `mintTo`, `setFeeRate`
+const PRIVILEGED_NAMES: &[&str] = &["mint", "setFee", "blacklist", "withdraw", "upgradeTo"];
+
+
+/// Privileged operation `name` refers to, e.g. `mint` for `_mintBatch`
+fn privileged_name(name: &str) -> Option<&'static str> {
+    let name = name.trim_start_matches('_');
+    PRIVILEGED_NAMES.iter().copied().find(|operation| {
+        let camel_prefix = name
+            .strip_prefix(operation)
+            .is_some_and(|rest| !rest.starts_with(|c: char| c.is_ascii_lowercase()));
+        camel_prefix || (*operation == "blacklist" && name.contains("Blacklist"))
+    })
+}
+
+/// Name and span of the function called by `name(...)` or `expr.name(...)`
+fn called_function(expression: &Expression) -> Option<(&str, Span)> {
+    let ExpressionKind::Call { callee, .. } = &expression.kind else {
+        return None;
+    };
+    match &callee.kind {
+        ExpressionKind::Identifier(name) => Some((name, callee.span)),
+        _ => expression.member_call().map(|(_, member)| (member.name.as_str(), member.span)),
+    }
+}
+
+/// `msg.sender` or `_msgSender()`
+fn is_sender(expression: &Expression) -> bool {
+    let expression = expression.unparenthesized();
+    expression.is_member_of("msg", "sender") || expression.called_name() == Some("_msgSender")
+}
+
+/// Whether `function` checks its caller against state
+///
+/// Accepted checks compare the caller with state (`msg.sender == owner`,
+/// `owner() != _msgSender()`), look the caller up in state in a condition
+/// (`require(admins[msg.sender])`, `!hasRole(ROLE, msg.sender)`), or call a
+/// function of the scope that does either, with the caller passed on
+/// through its parameters as in `_checkRole(ROLE, msg.sender)`. `callers`
+/// names the parameters holding the caller.
+fn checks_sender(
+    scope: &ContractScope,
+    function: &Function,
+    callers: &[&str],
+    visiting: &mut Vec<*const Function>,
+) -> bool {
+    let Some(body) = &function.body else {
+        return false;
+    };
+    if visiting.contains(&(function as *const Function)) {
+        return false;
+    }
+    visiting.push(function);
+    let is_caller = |expression: &Expression| {
+        let expression = expression.unparenthesized();
+        is_sender(expression) || expression.as_identifier().is_some_and(|name| callers.contains(&name))
+    };
+    // State, a member or entry of it, or a getter reading it
+    let is_authority = |expression: &Expression| {
+        let mut expression = expression.unparenthesized();
+        loop {
+            match &expression.kind {
+                ExpressionKind::Identifier(name) => return scope.names_state(function, name),
+                ExpressionKind::Member { expr, .. } | ExpressionKind::Index { base: expr, .. } => {
+                    expression = expr.unparenthesized()
+                }
+                _ => {
+                    return expression
+                        .called_name()
+                        .and_then(|name| scope.function(name))
+                        .is_some_and(|getter| !scope.state_accesses(getter).is_empty())
+                }
+            }
+        }
+    };
+    let is_lookup = |expression: &Expression| {
+        let expression = expression.unparenthesized();
+        match &expression.kind {
+            ExpressionKind::Index { index: Some(index), .. } => is_caller(index) && is_authority(expression),
+            ExpressionKind::Call { arguments, .. } => arguments.iter().any(is_caller) && is_authority(expression),
+            _ => false,
+        }
+    };
+
+    let mut checks = false;
+    let mut calls = Vec::new();
+    body.walk_expressions(&mut |expression| {
+        checks |= match &expression.kind {
+            ExpressionKind::Binary { operator, left, right } if operator == "==" || operator == "!=" => {
+                let is_bool = |side: &Expression| matches!(side.unparenthesized().kind, ExpressionKind::Bool(_));
+                (is_caller(left) && is_authority(right))
+                    || (is_caller(right) && is_authority(left))
+                    || (is_lookup(left) && is_bool(right))
+                    || (is_lookup(right) && is_bool(left))
+            }
+            ExpressionKind::Unary { operator, operand, .. } if operator == "!" => is_lookup(operand),
+            ExpressionKind::Call { arguments, .. }
+                if matches!(expression.called_name(), Some("require" | "assert")) =>
+            {
+                arguments.first().is_some_and(is_lookup)
+            }
+            ExpressionKind::Call { arguments, .. } => {
+                if let Some(callee) = expression.called_name().and_then(|name| scope.function(name)) {
+                    calls.push((callee, arguments));
+                }
+                false
+            }
+            _ => false,
+        };
+    });
+    checks = checks
+        || calls.into_iter().any(|(callee, arguments)| {
+            let callers = caller_parameters(callee, arguments, &is_caller);
+            checks_sender(scope, callee, &callers, visiting)
+        });
+    visiting.pop();
+    checks
+}
+
+/// Parameters of `callee` given the caller by `arguments`
+fn caller_parameters<'f>(
+    callee: &'f Function,
+    arguments: &[Expression],
+    is_caller: &impl Fn(&Expression) -> bool,
+) -> Vec<&'f str> {
+    callee
+        .parameters
+        .iter()
+        .zip(arguments)
+        .filter(|(_, argument)| is_caller(argument))
+        .filter_map(|(parameter, _)| Some(parameter.name.as_ref()?.name.as_str()))
+        .collect()
+}
+
+/// Whether `function` is restricted to authorized callers, by a modifier
+/// or a check in its body
+fn is_guarded(scope: &ContractScope, function: &Function) -> bool {
+    function.modifiers.iter().any(|invocation| {
+        scope.modifier(&invocation.name.name).is_some_and(|modifier| {
+            let is_caller = |argument: &Expression| is_sender(argument.unparenthesized());
+            let callers = caller_parameters(modifier, &invocation.arguments, &is_caller);
+            checks_sender(scope, modifier, &callers, &mut Vec::new())
+        })
+    }) || checks_sender(scope, function, &[], &mut Vec::new())
+}
+
+/// Whether `function` keeps per-caller accounts, e.g. `balances[msg.sender]`
+fn indexes_by_sender(function: &Function) -> bool {
+    let mut indexes = false;
+    if let Some(body) = &function.body {
+        body.walk_expressions(&mut |expression| {
+            indexes |= matches!(&expression.kind, ExpressionKind::Index { index: Some(index), .. } if is_sender(index));
+        });
+    }
+    indexes
+}
+
+/// First privileged operation performed by `function`, and whether it is
+/// one of the dangerous ones (selfdestruct, delegatecall, upgrades)
+///
+/// Operations known only by name are ignored in payable functions and in
+/// functions that keep per-caller accounts, which is how public sales and
+/// withdrawals of the caller's own balance look.
+fn privileged_operation(function: &Function) -> Option<(String, bool)> {
+    let by_name = function.mutability != Some(Mutability::Payable) && !indexes_by_sender(function);
+    if by_name {
+        if let Some(operation) = privileged_name(function.display_name()) {
+            return Some((format!("{}()", operation), operation == "upgradeTo"));
+        }
+    }
+
+    let mut found = None;
+    function.body.as_ref()?.walk_expressions(&mut |expression| {
+        if found.is_some() {
+            return;
+        }
+        found = match &expression.kind {
+            ExpressionKind::Call { callee, .. } if matches!(callee.as_identifier(), Some("selfdestruct" | "suicide")) => {
+                Some(("selfdestruct".to_string(), true))
+            }
+            ExpressionKind::Member { member, .. } if member.name == "delegatecall" => {
+                Some(("delegatecall".to_string(), true))
+            }
+            _ => called_function(expression)
+                .filter(|_| by_name)
+                .and_then(|(name, _)| privileged_name(name))
+                .map(|operation| (format!("{}()", operation), operation == "upgradeTo")),
+        };
+    });
+    found
+}
+
+/// Whether `function` carries a reentrancy lock modifier, e.g.
+/// `nonReentrant` or Uniswap's `lock`
+fn has_reentrancy_guard(function: &Function) -> bool {
+    function.modifiers.iter().any(|modifier| {
+        let name = modifier.name.name.to_ascii_lowercase();
+        name.contains("reentran") || name == "lock"
+    })
+}
+
+/// First compiler checking arithmetic by default
+const CHECKED_ARITHMETIC: Version = Version::new(0, 8, 0);
+
+/// First compiler where ABI coder v2 is no longer experimental
+const STABLE_ABI_CODER_V2: Version = Version::new(0, 6, 0);
+
+/// First compiler accepting `pragma abicoder`
+const ABI_CODER_PRAGMA: Version = Version::new(0, 7, 5);
+
+/// First compiler encoding with ABI coder v2 by default
+const ABI_CODER_V2_DEFAULT: Version = Version::new(0, 8, 0);
+
+/// Pragmas selecting an ABI coder the compiler range makes risky:
+/// experimental v2 on compilers where it was still experimental, or v1
+/// chosen over the validating v2 default of 0.8
+fn abi_coder_findings(category: &str, source: &SourceText) -> Vec<RiskFinding> {
+    let mut range = source.compiler_range().cloned().unwrap_or_else(VersionRange::any);
+    // `pragma abicoder` alone implies 0.7.5 or later
+    if source.ast().pragmas.iter().any(|pragma| pragma.name == "abicoder") {
+        range = range.intersect(&VersionRange::at_least(ABI_CODER_PRAGMA));
+    }
+    source
+        .ast()
+        .pragmas
+        .iter()
+        .filter_map(|pragma| match (pragma.name.as_str(), pragma.value.as_str()) {
+            ("experimental", "ABIEncoderV2") if range.admits_below(STABLE_ABI_CODER_V2) => Some(finding(
+                category.to_string(),
+                Severity::Medium,
+                &format!(
+                    "Experimental ABI coder v2 enabled for Solidity {} - experimental before 0.6.0 and affected by encoder bugs",
+                    range
+                ),
+                Some(source.span_location(pragma.span)),
+                0.7,
+            )),
+            ("abicoder", "v1") if range.admits_from(ABI_CODER_V2_DEFAULT) => Some(finding(
+                category.to_string(),
+                Severity::Low,
+                "ABI coder v1 selected - calldata is not validated as with the v2 default (dirty address bits, out-of-range enums)",
+                Some(source.span_location(pragma.span)),
+                0.6,
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Whether the source is encoded with ABI coder v2, `None` when that
+/// depends on which admitted compiler builds it
+fn uses_abi_coder_v2(source: &SourceText, range: &VersionRange) -> Option<bool> {
+    for pragma in &source.ast().pragmas {
+        match (pragma.name.as_str(), pragma.value.as_str()) {
+            ("experimental", "ABIEncoderV2") | ("abicoder", "v2") => return Some(true),
+            ("abicoder", "v1") => return Some(false),
+            _ => {}
+        }
+    }
+    if range.is_below(ABI_CODER_V2_DEFAULT) {
+        Some(false)
+    } else if range.admits_below(ABI_CODER_V2_DEFAULT) {
+        None
+    } else {
+        Some(true)
+    }
+}
+
+/// One finding listing `bugs`, rated by the most severe one
+///
+/// Bugs needing ABI coder v2 are dropped when the contract does not use it.
+/// Confidence is lowered when every bug of the top severity depends on
+/// settings that cannot be confirmed (optimizer, EVM version, ABI coder).
+fn compiler_bug_finding(
+    category: String,
+    subject: &str,
+    bugs: Vec<&KnownBug>,
+    abi_coder_v2: Option<bool>,
+    location: Option<Location>,
+) -> Option<RiskFinding> {
+    let mut bugs: Vec<(&KnownBug, bool)> = bugs
+        .into_iter()
+        .filter(|bug| !(bug.conditions.abi_encoder_v2 == Some(true) && abi_coder_v2 == Some(false)))
+        .map(|bug| {
+            let unconfirmed = bug.conditions.needs_settings()
+                || (bug.conditions.abi_encoder_v2 == Some(true) && abi_coder_v2.is_none());
+            (bug, unconfirmed)
+        })
+        .collect();
+    bugs.sort_by(|(a, _), (b, _)| b.risk().cmp(&a.risk()).then_with(|| a.fixed.cmp(&b.fixed)));
+
+    let severity = bugs.first()?.0.risk();
+    let confirmed = bugs
+        .iter()
+        .any(|(bug, unconfirmed)| bug.risk() == severity && !unconfirmed);
+
+    let listed: Vec<String> = bugs
+        .iter()
+        .map(|(bug, _)| {
+            let conditions = &bug.conditions;
+            let mut notes = vec![bug.severity.clone()];
+            if conditions.abi_encoder_v2 == Some(true) {
+                notes.push("ABI coder v2".to_string());
+            }
+            if conditions.optimizer == Some(true) {
+                notes.push("optimizer".to_string());
+            }
+            if conditions.yul_optimizer == Some(true) {
+                notes.push("Yul optimizer".to_string());
+            }
+            if let Some(evm) = &conditions.evm_version {
+                notes.push(format!("EVM {}", evm));
+            }
+            format!("{} ({}, fixed in {})", bug.name, notes.join(", "), bug.fixed)
+        })
+        .collect();
+
+    Some(finding(
+        category,
+        severity,
+        &format!("{} affected by {} known compiler bug(s): {}", subject, listed.len(), listed.join("; ")),
+        location,
+        if confirmed { 0.85 } else { 0.6 },
+    ))
+}
 
 // ============================================================================
 // Rule 1: Access Control
@@ -17,75 +449,104 @@ impl ScanRule for AccessControlRule {
         "OWASP-SC01".to_string()
     }
     
+    fn metadata(&self) -> RuleMetadata {
+        metadata(
+            self,
+            "AC-001",
+            "Missing or weak access control",
+            &["SWC-105", "SWC-106"],
+            Severity::High,
+            "Restrict privileged functions such as selfdestruct and delegatecall with an access modifier (onlyOwner, role checks) and review who can reach them.",
+        )
+    }
+    
     fn check_bytecode(&self,
         _contract_address: &str,
-        bytecode: &str,
-    ) -> Option<RiskFinding> {
-        // Check for common access control patterns in bytecode
-        let patterns = vec![
-            "owner",
-            "onlyOwner",
-            "admin",
-            "role",
-        ];
-        
-        let has_access_control = patterns.iter().any(|p| {
-            bytecode.to_lowercase().contains(p)
-        });
+        program: &Program,
+    ) -> Vec<RiskFinding> {
+        // msg.sender compared against a stored address: CALLER with an
+        // SLOAD and an EQ close by
+        let instructions = &program.instructions;
         
-        if has_access_control {
-            Some(RiskFinding {
-                category: self.category(),
-                severity: Severity::Medium,
-                description: "Contract contains access control mechanisms. Verify proper implementation.".to_string(),
-                location: None,
-                confidence: 0.7,
+        instructions
+            .iter()
+            .enumerate()
+            .filter(|(_, i)| i.opcode == bytecode::CALLER)
+            .filter(|(index, _)| {
+                let window = &instructions[index.saturating_sub(OWNER_CHECK_WINDOW)
+                    ..(index + OWNER_CHECK_WINDOW + 1).min(instructions.len())];
+                window.iter().any(|i| i.opcode == bytecode::SLOAD)
+                    && window.iter().any(|i| i.opcode == bytecode::EQ)
             })
-        } else {
-            None
-        }
+            .map(|(_, i)| finding(
+                self.category(),
+                Severity::Medium,
+                "Contract contains access control mechanisms. Verify proper implementation.",
+                Some(bytecode_location(program, i.offset)),
+                0.7,
+            ))
+            .collect()
     }
     
     fn check_source(
         &self,
         _contract_address: &str,
-        source_code: &str,
-    ) -> Option<RiskFinding> {
-        // Check for missing access control modifiers
-        let dangerous_patterns = vec![
-            "function.*selfdestruct",
-            "function.*delegatecall",
-        ];
-        
-        for pattern in dangerous_patterns {
-            // Simple string matching (regex would be better)
-            if source_code.contains("selfdestruct") || source_code.contains("delegatecall") {
-                // Check if protected by modifier
-                let has_modifier = source_code.contains("onlyOwner") 
-                    || source_code.contains("onlyAdmin")
-                    || source_code.contains("require(msg.sender == owner)");
-                
-                if !has_modifier {
-                    return Some(RiskFinding {
-                        category: self.category(),
-                        severity: Severity::High,
-                        description: "Dangerous function may lack proper access control".to_string(),
-                        location: None,
-                        confidence: 0.8,
-                    });
+        source: &SourceText,
+    ) -> Vec<RiskFinding> {
+        // Each externally callable function, and the internal functions it
+        // calls, must be guarded before reaching a privileged operation
+        let units = source.units();
+        let mut findings = Vec::new();
+
+        for scope in ContractScope::all(&units) {
+            if scope.contract.kind == ContractKind::Library {
+                continue;
+            }
+            for function in &scope.contract.functions {
+                if deadline_passed() {
+                    return findings;
+                }
+                if function.body.is_none() || !function.is_externally_callable() || is_guarded(&scope, function) {
+                    continue;
                 }
+
+                let reached = scope.reachable(function, |callee| is_guarded(&scope, callee));
+                let Some((operation, via, dangerous)) = reached
+                    .iter()
+                    .find_map(|callee| privileged_operation(callee).map(|(operation, dangerous)| (operation, callee, dangerous)))
+                else {
+                    continue;
+                };
+
+                let via = if std::ptr::eq(*via, function) {
+                    String::new()
+                } else {
+                    format!(" through '{}'", via.display_name())
+                };
+                findings.push(finding(
+                    self.category(),
+                    Severity::High,
+                    &format!(
+                        "Function '{}' reaches {}{} without an access control modifier or msg.sender check",
+                        function.display_name(),
+                        operation,
+                        via,
+                    ),
+                    Some(source.span_location(function.header)),
+                    if dangerous { 0.85 } else { 0.65 },
+                ));
             }
         }
-        
-        None
+
+        findings
     }
     
     fn check_transaction(
         &self,
         _contract_address: &str,
         _tx_data: &str,
-    ) -> Option<RiskFinding> {
-        None // Transaction-level check not applicable
+    ) -> Vec<RiskFinding> {
+        Vec::new() // Transaction-level check not applicable
     }
 }
 
@@ -103,68 +564,104 @@ impl ScanRule for ArithmeticRule {
         "OWASP-SC02".to_string()
     }
     
+    fn metadata(&self) -> RuleMetadata {
+        metadata(
+            self,
+            "ARITH-001",
+            "Integer overflow and underflow",
+            &["SWC-101"],
+            Severity::High,
+            "Compile with Solidity 0.8 or later for checked arithmetic, or use SafeMath on older compilers; keep unchecked blocks to provably safe operations.",
+        )
+    }
+    
     fn check_bytecode(
         &self,
         _contract_address: &str,
-        _bytecode: &str,
-    ) -> Option<RiskFinding> {
+        _program: &Program,
+    ) -> Vec<RiskFinding> {
         // Bytecode-level arithmetic checks would require EVM analysis
-        None
+        Vec::new()
     }
     
     fn check_source(
         &self,
         _contract_address: &str,
-        source_code: &str,
-    ) -> Option<RiskFinding> {
-        // Check for unchecked arithmetic
-        let unchecked_patterns = vec![
-            "unchecked {",
-        ];
-        
-        let pragma_pattern = "pragma solidity";
-        
+        source: &SourceText,
+    ) -> Vec<RiskFinding> {
+        let unit = source.ast();
+
         // Check if using Solidity < 0.8 without SafeMath
-        if source_code.contains(pragma_pattern) {
-            let uses_old_solidity = source_code.contains("pragma solidity ^0.4.")
-                || source_code.contains("pragma solidity ^0.5.")
-                || source_code.contains("pragma solidity ^0.6.")
-                || source_code.contains("pragma solidity ^0.7.");
-            
-            let has_safemath = source_code.contains("SafeMath")
-                || source_code.contains("using SafeMath");
-            
-            if uses_old_solidity && !has_safemath {
-                return Some(RiskFinding {
-                    category: self.category(),
-                    severity: Severity::High,
-                    description: "Using Solidity < 0.8 without SafeMath - vulnerable to integer overflow/underflow".to_string(),
-                    location: None,
-                    confidence: 0.9,
-                });
+        let has_safemath = source.identifiers().any(|(name, _)| name == "SafeMath");
+
+        // The range is narrowed by the other submitted files, so `>=0.6.0`
+        // here may still be known to build with 0.7
+        let unchecked_compiler = match source.compiler_range() {
+            Some(range) if !has_safemath && range.is_below(CHECKED_ARITHMETIC) => Some((
+                Severity::High,
+                format!("Using Solidity < 0.8 ({}) without SafeMath - vulnerable to integer overflow/underflow", range),
+                0.9,
+            )),
+            Some(range) if !has_safemath && range.admits_below(CHECKED_ARITHMETIC) => Some((
+                Severity::Medium,
+                format!(
+                    "Pragma admits Solidity < 0.8 ({}) without SafeMath - arithmetic is unchecked if built with an older compiler",
+                    range
+                ),
+                0.6,
+            )),
+            _ => None,
+        };
+        if let Some((severity, description, confidence)) = unchecked_compiler {
+            let findings: Vec<RiskFinding> = unit
+                .pragmas
+                .iter()
+                .filter(|pragma| pragma.name == "solidity")
+                .map(|pragma| finding(
+                    self.category(),
+                    severity.clone(),
+                    &description,
+                    Some(source.span_location(pragma.span)),
+                    confidence,
+                ))
+                .collect();
+
+            if !findings.is_empty() {
+                return findings;
             }
         }
-        
+
         // Check for unchecked blocks in Solidity 0.8+
-        if unchecked_patterns.iter().any(|p| source_code.contains(p)) {
-            return Some(RiskFinding {
-                category: self.category(),
-                severity: Severity::Medium,
-                description: "Unchecked arithmetic block detected - verify safety".to_string(),
-                location: None,
-                confidence: 0.75,
-            });
+        let mut unchecked = Vec::new();
+        for (_, function) in unit.functions() {
+            if let Some(body) = &function.body {
+                body.walk(&mut |statement| {
+                    if matches!(&statement.kind, StatementKind::Block(block) if block.unchecked) {
+                        // Just the `unchecked` keyword
+                        unchecked.push(Span::new(statement.span.start, statement.span.start + "unchecked".len()));
+                    }
+                });
+            }
         }
-        
-        None
+
+        unchecked
+            .into_iter()
+            .map(|span| finding(
+                self.category(),
+                Severity::Medium,
+                "Unchecked arithmetic block detected - verify safety",
+                Some(source.span_location(span)),
+                0.75,
+            ))
+            .collect()
     }
     
     fn check_transaction(
         &self,
         _contract_address: &str,
         _tx_data: &str,
-    ) -> Option<RiskFinding> {
-        None
+    ) -> Vec<RiskFinding> {
+        Vec::new()
     }
 }
 
@@ -182,49 +679,60 @@ impl ScanRule for DelegatecallRule {
         "OWASP-SC03".to_string()
     }
     
+    fn metadata(&self) -> RuleMetadata {
+        metadata(
+            self,
+            "DELEG-001",
+            "Delegatecall to untrusted code",
+            &["SWC-112"],
+            Severity::High,
+            "Only delegatecall into trusted, immutable implementations, restrict who can change the target and keep the storage layout compatible.",
+        )
+    }
+    
     fn check_bytecode(
         &self,
         _contract_address: &str,
-        bytecode: &str,
-    ) -> Option<RiskFinding> {
+        program: &Program,
+    ) -> Vec<RiskFinding> {
         // DELEGATECALL opcode: 0xF4
-        if bytecode.contains("f4") {
-            Some(RiskFinding {
-                category: self.category(),
-                severity: Severity::High,
-                description: "Contract uses DELEGATECALL - verify implementation for proxy pattern safety".to_string(),
-                location: None,
-                confidence: 0.85,
-            })
-        } else {
-            None
-        }
+        program
+            .instructions
+            .iter()
+            .filter(|i| i.opcode == bytecode::DELEGATECALL)
+            .map(|i| finding(
+                self.category(),
+                Severity::High,
+                "Contract uses DELEGATECALL - verify implementation for proxy pattern safety",
+                Some(bytecode_location(program, i.offset)),
+                0.85,
+            ))
+            .collect()
     }
     
     fn check_source(
         &self,
         _contract_address: &str,
-        source_code: &str,
-    ) -> Option<RiskFinding> {
-        if source_code.contains("delegatecall") {
-            Some(RiskFinding {
-                category: self.category(),
-                severity: Severity::High,
-                description: "DELEGATECALL usage detected - ensure proper access control".to_string(),
-                location: None,
-                confidence: 0.9,
-            })
-        } else {
-            None
-        }
+        source: &SourceText,
+    ) -> Vec<RiskFinding> {
+        member_spans(source.ast(), "delegatecall")
+            .into_iter()
+            .map(|span| finding(
+                self.category(),
+                Severity::High,
+                "DELEGATECALL usage detected - ensure proper access control",
+                Some(source.span_location(span)),
+                0.9,
+            ))
+            .collect()
     }
     
     fn check_transaction(
         &self,
         _contract_address: &str,
         _tx_data: &str,
-    ) -> Option<RiskFinding> {
-        None
+    ) -> Vec<RiskFinding> {
+        Vec::new()
     }
 }
 
@@ -242,48 +750,78 @@ impl ScanRule for OracleManipulationRule {
         "OWASP-SC04".to_string()
     }
     
+    fn metadata(&self) -> RuleMetadata {
+        metadata(
+            self,
+            "ORACLE-001",
+            "Price oracle manipulation",
+            &[],
+            Severity::Medium,
+            "Use time-weighted or decentralized price feeds, check feed freshness and bound accepted price deviations.",
+        )
+    }
+    
     fn check_bytecode(
         &self,
         _contract_address: &str,
-        _bytecode: &str,
-    ) -> Option<RiskFinding> {
-        None
+        _program: &Program,
+    ) -> Vec<RiskFinding> {
+        Vec::new()
     }
     
     fn check_source(
         &self,
         _contract_address: &str,
-        source_code: &str,
-    ) -> Option<RiskFinding> {
-        let oracle_patterns = vec![
+        source: &SourceText,
+    ) -> Vec<RiskFinding> {
+        let oracle_patterns = [
             "Chainlink",
             "UniswapV2Oracle",
             "getReserves",
             "latestRoundData",
-            "price()",
         ];
-        
-        let has_oracle = oracle_patterns.iter().any(|p| source_code.contains(p));
-        
-        if has_oracle {
-            Some(RiskFinding {
-                category: self.category(),
-                severity: Severity::Medium,
-                description: "Oracle usage detected - verify manipulation safeguards".to_string(),
-                location: None,
-                confidence: 0.7,
+
+        // `price()` as a call, not a word in a comment or string
+        let price_calls = expressions(source.ast())
+            .into_iter()
+            .filter(|expression| match &expression.kind {
+                ExpressionKind::Call { callee, arguments } if arguments.is_empty() => {
+                    match &callee.kind {
+                        ExpressionKind::Identifier(name) => name == "price",
+                        ExpressionKind::Member { member, .. } => member.name == "price",
+                        _ => false,
+                    }
+                }
+                _ => false,
             })
-        } else {
-            None
-        }
+            .map(|expression| source.line_of(expression.span.start));
+
+        // One finding per line, however many patterns it matches
+        let mut lines: Vec<u32> = identifier_lines(source, &oracle_patterns)
+            .into_iter()
+            .chain(price_calls)
+            .collect();
+        lines.sort_unstable();
+        lines.dedup();
+
+        lines
+            .into_iter()
+            .map(|number| finding(
+                self.category(),
+                Severity::Medium,
+                "Oracle usage detected - verify manipulation safeguards",
+                Some(source.line_location(number)),
+                0.7,
+            ))
+            .collect()
     }
     
     fn check_transaction(
         &self,
         _contract_address: &str,
         _tx_data: &str,
-    ) -> Option<RiskFinding> {
-        None
+    ) -> Vec<RiskFinding> {
+        Vec::new()
     }
 }
 
@@ -301,51 +839,110 @@ impl ScanRule for ReentrancyRule {
         "OWASP-SC05".to_string()
     }
     
+    fn metadata(&self) -> RuleMetadata {
+        metadata(
+            self,
+            "REENT-001",
+            "Reentrancy through external calls",
+            &["SWC-107"],
+            Severity::Critical,
+            "Apply checks-effects-interactions: update state before external calls, and guard entry points with nonReentrant.",
+        )
+    }
+    
     fn check_bytecode(
         &self,
         _contract_address: &str,
-        _bytecode: &str,
-    ) -> Option<RiskFinding> {
-        None
+        _program: &Program,
+    ) -> Vec<RiskFinding> {
+        Vec::new()
     }
     
     fn check_source(
         &self,
         _contract_address: &str,
-        source_code: &str,
-    ) -> Option<RiskFinding> {
-        // Check for external calls before state changes
-        let external_call_patterns = vec![
-            ".call{value:",
-            ".transfer(",
-            ".send(",
-        ];
-        
-        let has_external_call = external_call_patterns.iter().any(|p| source_code.contains(p));
-        
-        // Check for reentrancy guard
-        let has_guard = source_code.contains("nonReentrant")
-            || source_code.contains("ReentrancyGuard");
-        
-        // Check for checks-effects-interactions pattern
-        let has_cei_pattern = source_code.contains("_balances[msg.sender] = 0")
-            || source_code.contains("balances[msg.sender] -=");
-        
-        if has_external_call && !has_guard && !has_cei_pattern {
-            return Some(RiskFinding {
-                category: self.category(),
-                severity: Severity::Critical,
-                description: "Potential reentrancy vulnerability - external call without guard".to_string(),
-                location: None,
-                confidence: 0.8,
-            });
+        source: &SourceText,
+    ) -> Vec<RiskFinding> {
+        let units = source.units();
+        let line = |span: Span| source.line_of(span.start);
+        let mut findings = Vec::new();
+
+        for scope in ContractScope::all(&units) {
+            if scope.contract.kind == ContractKind::Library {
+                continue;
+            }
+            let entries: Vec<&Function> = scope.functions().filter(|f| f.is_externally_callable()).collect();
+
+            for function in scope.contract.functions.iter().filter(|f| f.body.is_some() && f.is_externally_callable()) {
+                if deadline_passed() {
+                    return findings;
+                }
+                let effects = scope.effects(function);
+                let guarded = has_reentrancy_guard(function);
+
+                if !guarded {
+                    for write in &effects.late_writes {
+                        findings.push(finding(
+                            self.category(),
+                            Severity::Critical,
+                            &format!(
+                                "State variable '{}' written after the external call on line {} in '{}' - reentrancy (checks-effects-interactions violated)",
+                                write.variable,
+                                line(write.call.span),
+                                function.display_name(),
+                            ),
+                            Some(source.span_location(write.span)),
+                            0.85,
+                        ));
+                    }
+                    // Ether sent with state settled first: only a missing guard
+                    for call in effects.calls.iter().filter(|call| call.sends_value) {
+                        if !effects.late_writes.iter().any(|write| write.call == *call) {
+                            findings.push(finding(
+                                self.category(),
+                                Severity::Low,
+                                &format!("External call in '{}' sends ether without a reentrancy guard", function.display_name()),
+                                Some(source.span_location(call.span)),
+                                0.4,
+                            ));
+                        }
+                    }
+                }
+
+                // Second pass: another entry point reentered during the call
+                // sees the state this function has not updated yet. A shared
+                // guard on both functions prevents it.
+                let pending: Vec<&str> = effects.late_writes.iter().map(|write| write.variable.as_str()).collect();
+                if pending.is_empty() {
+                    continue;
+                }
+                for other in &entries {
+                    if std::ptr::eq(*other, function) || (guarded && has_reentrancy_guard(other)) {
+                        continue;
+                    }
+                    let accesses = scope.state_accesses(other);
+                    if let Some(shared) = pending.iter().find(|variable| accesses.contains(*variable)) {
+                        findings.push(finding(
+                            self.category(),
+                            Severity::High,
+                            &format!(
+                                "Cross-function reentrancy: '{}' uses '{}', which '{}' updates after an external call",
+                                other.display_name(),
+                                shared,
+                                function.display_name(),
+                            ),
+                            Some(source.span_location(other.header)),
+                            0.6,
+                        ));
+                    }
+                }
+            }
         }
-        
-        None
+        findings
     }
     
-    fn check_transaction(&self, _contract_address: &str, _tx_data: &str) -> Option<RiskFinding> {
-        None
+    fn check_transaction(&self, _contract_address: &str, _tx_data: &str) -> Vec<RiskFinding> {
+        Vec::new()
     }
 }
 
@@ -363,30 +960,85 @@ impl ScanRule for UncheckedCallRule {
         "OWASP-SC06".to_string()
     }
     
-    fn check_bytecode(&self, _contract_address: &str, _bytecode: &str) -> Option<RiskFinding> {
-        None
+    fn metadata(&self) -> RuleMetadata {
+        metadata(
+            self,
+            "CALL-001",
+            "Unchecked low-level call return value",
+            &["SWC-104"],
+            Severity::Medium,
+            "Check the success flag returned by call/delegatecall and revert or handle the failure.",
+        )
+    }
+    
+    fn check_bytecode(&self, _contract_address: &str, _program: &Program) -> Vec<RiskFinding> {
+        Vec::new()
     }
     
-    fn check_source(&self, _contract_address: &str, source_code: &str) -> Option<RiskFinding> {
-        // Check for low-level calls without success check
-        if source_code.contains(".call(") || source_code.contains(".delegatecall(") {
-            // This is a simplified check - real implementation would parse AST
-            if !source_code.contains("require(success") && !source_code.contains("if (!success") {
-                return Some(RiskFinding {
-                    category: self.category(),
-                    severity: Severity::Medium,
-                    description: "Low-level call may not check return value".to_string(),
-                    location: None,
-                    confidence: 0.6,
+    fn check_source(&self, _contract_address: &str, source: &SourceText) -> Vec<RiskFinding> {
+        // Check for low-level calls whose success flag is dropped or never read
+        let mut findings = Vec::new();
+
+        for (_, function) in source.ast().functions() {
+            let Some(body) = &function.body else {
+                continue;
+            };
+
+            body.walk(&mut |statement| {
+                let (call, success) = match &statement.kind {
+                    StatementKind::Expression(expression) => match &expression.unparenthesized().kind {
+                        // (success, ) = target.call(...)
+                        ExpressionKind::Assign { target, value, .. } => {
+                            let success = match &target.kind {
+                                ExpressionKind::Tuple(items) => items.first().and_then(Option::as_ref),
+                                _ => Some(&**target),
+                            };
+                            (low_level_call(value), success.and_then(Expression::as_identifier).map(str::to_string))
+                        }
+                        // target.call(...);
+                        _ => (low_level_call(expression), None),
+                    },
+                    // (bool success, ) = target.call(...);
+                    StatementKind::VariableDeclaration { declarations, value: Some(value) } => (
+                        low_level_call(value),
+                        declarations
+                            .first()
+                            .and_then(Option::as_ref)
+                            .and_then(|declaration| declaration.name.as_ref())
+                            .map(|name| name.name.clone()),
+                    ),
+                    _ => (None, None),
+                };
+                let Some(call) = call else {
+                    return;
+                };
+
+                let checked = success.is_some_and(|success| {
+                    let mut read = false;
+                    body.walk_expressions(&mut |expression| {
+                        read |= expression.span.start >= statement.span.end
+                            && expression.as_identifier() == Some(success.as_str());
+                    });
+                    read
                 });
-            }
+
+                if !checked {
+                    findings.push(finding(
+                        self.category(),
+                        Severity::Medium,
+                        "Low-level call may not check return value",
+                        Some(source.span_location(call)),
+                        0.6,
+                    ));
+                }
+            });
         }
-        
-        None
+
+        findings
     }
     
-    fn check_transaction(&self, _contract_address: &str, _tx_data: &str) -> Option<RiskFinding> {
-        None
+    fn check_transaction(&self, _contract_address: &str, _tx_data: &str) -> Vec<RiskFinding> {
+        Vec::new()
     }
 }
 
@@ -404,47 +1056,62 @@ impl ScanRule for TimestampDependenceRule {
         "OWASP-SC07".to_string()
     }
     
-    fn check_bytecode(&self, _contract_address: &str, _bytecode: &str) -> Option<RiskFinding> {
-        None
+    fn metadata(&self) -> RuleMetadata {
+        metadata(
+            self,
+            "TIME-001",
+            "Block timestamp dependence",
+            &["SWC-116", "SWC-120"],
+            Severity::Low,
+            "Do not use block.timestamp as a source of randomness or for decisions sensitive to a few seconds of drift.",
+        )
     }
     
-    fn check_source(&self, _contract_address: &str, source_code: &str) -> Option<RiskFinding> {
-        let timestamp_patterns = vec![
-            "block.timestamp",
-            "now",
-        ];
-        
-        let has_timestamp = timestamp_patterns.iter().any(|p| source_code.contains(p));
-        
+    fn check_bytecode(&self, _contract_address: &str, _program: &Program) -> Vec<RiskFinding> {
+        Vec::new()
+    }
+    
+    fn check_source(&self, _contract_address: &str, source: &SourceText) -> Vec<RiskFinding> {
+        // `block.timestamp`, and `now` as an identifier, not inside `known`,
+        // a comment or a string
+        let uses: Vec<Span> = expressions(source.ast())
+            .into_iter()
+            .filter(|expression| {
+                expression.is_member_of("block", "timestamp") || expression.as_identifier() == Some("now")
+            })
+            .map(|expression| expression.span)
+            .collect();
+
         // Check if used in critical logic
-        let critical_usage = source_code.contains("block.timestamp") 
-            && (source_code.contains("random") || source_code.contains("lottery"));
-        
-        if critical_usage {
-            return Some(RiskFinding {
-                category: self.category(),
-                severity: Severity::High,
-                description: "block.timestamp used in critical logic - miners can manipulate".to_string(),
-                location: None,
-                confidence: 0.85,
-            });
-        }
-        
-        if has_timestamp {
-            return Some(RiskFinding {
-                category: self.category(),
-                severity: Severity::Low,
-                description: "block.timestamp usage detected - verify not used for randomness".to_string(),
-                location: None,
-                confidence: 0.5,
+        let critical_usage = !uses.is_empty()
+            && source.identifiers().any(|(name, _)| {
+                let name = name.to_ascii_lowercase();
+                name.contains("random") || name.contains("lottery")
             });
-        }
-        
-        None
+
+        uses.into_iter()
+            .map(|span| if critical_usage {
+                finding(
+                    self.category(),
+                    Severity::High,
+                    "block.timestamp used in critical logic - miners can manipulate",
+                    Some(source.span_location(span)),
+                    0.85,
+                )
+            } else {
+                finding(
+                    self.category(),
+                    Severity::Low,
+                    "block.timestamp usage detected - verify not used for randomness",
+                    Some(source.span_location(span)),
+                    0.5,
+                )
+            })
+            .collect()
     }
     
-    fn check_transaction(&self, _contract_address: &str, _tx_data: &str) -> Option<RiskFinding> {
-        None
+    fn check_transaction(&self, _contract_address: &str, _tx_data: &str) -> Vec<RiskFinding> {
+        Vec::new()
     }
 }
 
@@ -462,37 +1129,79 @@ impl ScanRule for TxOriginRule {
         "OWASP-SC08".to_string()
     }
     
-    fn check_bytecode(&self, _contract_address: &str, _bytecode: &str) -> Option<RiskFinding> {
-        None
+    fn metadata(&self) -> RuleMetadata {
+        metadata(
+            self,
+            "ORIGIN-001",
+            "Authorization through tx.origin",
+            &["SWC-115"],
+            Severity::Critical,
+            "Authorize with msg.sender instead of tx.origin.",
+        )
     }
     
-    fn check_source(&self, _contract_address: &str, source_code: &str) -> Option<RiskFinding> {
-        if source_code.contains("tx.origin") {
-            // Check if used for authorization
-            if source_code.contains("tx.origin == owner") || source_code.contains("require(tx.origin") {
-                return Some(RiskFinding {
-                    category: self.category(),
-                    severity: Severity::Critical,
-                    description: "tx.origin used for authorization - vulnerable to phishing attacks".to_string(),
-                    location: None,
-                    confidence: 0.95,
-                });
+    fn check_bytecode(&self, _contract_address: &str, _program: &Program) -> Vec<RiskFinding> {
+        Vec::new()
+    }
+    
+    fn check_source(&self, _contract_address: &str, source: &SourceText) -> Vec<RiskFinding> {
+        let expressions = expressions(source.ast());
+
+        // tx.origin compared for equality or asserted directly is used for
+        // authorization
+        let mut authorizing = Vec::new();
+        for expression in &expressions {
+            match &expression.kind {
+                ExpressionKind::Binary { operator, left, right } if operator == "==" || operator == "!=" => {
+                    authorizing.extend(
+                        [left, right]
+                            .into_iter()
+                            .map(|side| side.unparenthesized())
+                            .filter(|side| side.is_member_of("tx", "origin"))
+                            .map(|side| side.span),
+                    );
+                }
+                ExpressionKind::Call { callee, arguments } if matches!(callee.as_identifier(), Some("require" | "assert")) => {
+                    authorizing.extend(
+                        arguments
+                            .first()
+                            .map(|argument| argument.unparenthesized())
+                            .filter(|argument| argument.is_member_of("tx", "origin"))
+                            .map(|argument| argument.span),
+                    );
+                }
+                _ => {}
             }
-            
-            return Some(RiskFinding {
-                category: self.category(),
-                severity: Severity::Medium,
-                description: "tx.origin usage detected - prefer msg.sender".to_string(),
-                location: None,
-                confidence: 0.8,
-            });
         }
-        
-        None
+
+        expressions
+            .iter()
+            .filter(|expression| expression.is_member_of("tx", "origin"))
+            .map(|expression| {
+                let location = Some(source.span_location(expression.span));
+                if authorizing.contains(&expression.span) {
+                    finding(
+                        self.category(),
+                        Severity::Critical,
+                        "tx.origin used for authorization - vulnerable to phishing attacks",
+                        location,
+                        0.95,
+                    )
+                } else {
+                    finding(
+                        self.category(),
+                        Severity::Medium,
+                        "tx.origin usage detected - prefer msg.sender",
+                        location,
+                        0.8,
+                    )
+                }
+            })
+            .collect()
     }
     
-    fn check_transaction(&self, _contract_address: &str, _tx_data: &str) -> Option<RiskFinding> {
-        None
+    fn check_transaction(&self, _contract_address: &str, _tx_data: &str) -> Vec<RiskFinding> {
+        Vec::new()
     }
 }
 
@@ -510,38 +1219,46 @@ impl ScanRule for FlashLoanRule {
         "OWASP-SC09".to_string()
     }
     
-    fn check_bytecode(&self, _contract_address: &str, _bytecode: &str) -> Option<RiskFinding> {
-        None
+    fn metadata(&self) -> RuleMetadata {
+        metadata(
+            self,
+            "FLASH-001",
+            "Flash loan price manipulation",
+            &[],
+            Severity::Medium,
+            "Do not derive prices or balances from state that can be moved within one transaction; use TWAPs and per-block limits.",
+        )
     }
     
-    fn check_source(&self, _contract_address: &str, source_code: &str) -> Option<RiskFinding> {
-        let flash_loan_patterns = vec![
+    fn check_bytecode(&self, _contract_address: &str, _program: &Program) -> Vec<RiskFinding> {
+        Vec::new()
+    }
+    
+    fn check_source(&self, _contract_address: &str, source: &SourceText) -> Vec<RiskFinding> {
+        let flash_loan_patterns = [
             "flashLoan",
             "FlashLoan",
-            "flash loan",
             "Aave",
             "Balancer",
             "UniswapV2Pair",
             "swap",
         ];
-        
-        let has_flash_loan = flash_loan_patterns.iter().any(|p| source_code.contains(p));
-        
-        if has_flash_loan {
-            return Some(RiskFinding {
-                category: self.category(),
-                severity: Severity::Medium,
-                description: "Flash loan related code detected - verify price manipulation safeguards".to_string(),
-                location: None,
-                confidence: 0.6,
-            });
-        }
-        
-        None
+
+        // One finding per line, however many patterns it matches
+        identifier_lines(source, &flash_loan_patterns)
+            .into_iter()
+            .map(|number| finding(
+                self.category(),
+                Severity::Medium,
+                "Flash loan related code detected - verify price manipulation safeguards",
+                Some(source.line_location(number)),
+                0.6,
+            ))
+            .collect()
     }
     
-    fn check_transaction(&self, _contract_address: &str, _tx_data: &str) -> Option<RiskFinding> {
-        None
+    fn check_transaction(&self, _contract_address: &str, _tx_data: &str) -> Vec<RiskFinding> {
+        Vec::new()
     }
 }
 
@@ -559,32 +1276,159 @@ impl ScanRule for InputValidationRule {
         "OWASP-SC10".to_string()
     }
     
-    fn check_bytecode(&self, _contract_address: &str, _bytecode: &str) -> Option<RiskFinding> {
-        None
+    fn metadata(&self) -> RuleMetadata {
+        metadata(
+            self,
+            "INPUT-001",
+            "Missing input validation",
+            &[],
+            Severity::Medium,
+            "Validate parameters of externally callable functions with require or custom errors.",
+        )
+    }
+    
+    fn check_bytecode(&self, _contract_address: &str, _program: &Program) -> Vec<RiskFinding> {
+        Vec::new()
     }
     
-    fn check_source(&self, _contract_address: &str, source_code: &str) -> Option<RiskFinding> {
+    fn check_source(&self, _contract_address: &str, source: &SourceText) -> Vec<RiskFinding> {
         // Check for external function parameters without validation
         // This is a simplified heuristic
         
-        let has_require = source_code.contains("require(");
-        let has_assert = source_code.contains("assert(");
-        let has_custom_error = source_code.contains("error ");
-        
-        if !has_require && !has_assert && !has_custom_error {
-            return Some(RiskFinding {
-                category: self.category(),
-                severity: Severity::Medium,
-                description: "Limited input validation detected - consider adding require statements".to_string(),
-                location: None,
-                confidence: 0.5,
-            });
+        let unit = source.ast();
+        let expressions = expressions(unit);
+
+        let has_require = expressions
+            .iter()
+            .any(|expression| matches!(expression.called_name(), Some("require" | "assert" | "revert")));
+        let has_custom_error = !unit.errors.is_empty()
+            || unit.contracts.iter().any(|contract| !contract.errors.is_empty());
+        let has_revert = unit.functions().any(|(_, function)| {
+            let mut reverts = false;
+            if let Some(body) = &function.body {
+                body.walk(&mut |statement| reverts |= matches!(statement.kind, StatementKind::Revert(_)));
+            }
+            reverts
+        });
+
+        let mut findings = abi_coder_findings(&self.category(), source);
+        if has_require || has_custom_error || has_revert {
+            return findings;
+        }
+
+        // Point at each externally callable function; fall back to a single
+        // contract-wide finding when none can be found
+        let mut locations: Vec<Option<Location>> = unit
+            .functions()
+            .filter(|(_, function)| {
+                function.kind == FunctionKind::Function
+                    && function.visibility.is_some()
+                    && function.is_externally_callable()
+            })
+            .map(|(_, function)| Some(source.span_location(function.header)))
+            .collect();
+
+        if locations.is_empty() {
+            locations.push(None);
         }
         
-        None
+        findings.extend(locations.into_iter().map(|location| finding(
+            self.category(),
+            Severity::Medium,
+            "Limited input validation detected - consider adding require statements",
+            location,
+            0.5,
+        )));
+        findings
     }
     
-    fn check_transaction(&self, _contract_address: &str, _tx_data: &str) -> Option<RiskFinding> {
-        None
+    fn check_transaction(&self, _contract_address: &str, _tx_data: &str) -> Vec<RiskFinding> {
+        Vec::new()
+    }
+}
+
+// ============================================================================
+// Rule 11: Known Compiler Bugs
+// ============================================================================
+pub struct CompilerBugRule;
+
+impl ScanRule for CompilerBugRule {
+    fn name(&self) -> String {
+        "KNOWN_COMPILER_BUG".to_string()
+    }
+
+    fn category(&self) -> String {
+        "COMPILER".to_string()
+    }
+
+    fn metadata(&self) -> RuleMetadata {
+        metadata(
+            self,
+            "SOLC-001",
+            "Known compiler bugs",
+            &["SWC-102"],
+            Severity::Medium,
+            "Build with a compiler release that fixes the listed bugs and pin it with an exact pragma; where an upgrade is not possible, check whether the bug conditions apply to the deployed code.",
+        )
+    }
+
+    fn check_bytecode(&self, _contract_address: &str, program: &Program) -> Vec<RiskFinding> {
+        let Some(metadata) = &program.metadata else {
+            return Vec::new();
+        };
+        let Some(version) = metadata.solc else {
+            return Vec::new();
+        };
+        let Some(bugs) = BugList::bundled().affecting(version) else {
+            return Vec::new();
+        };
+
+        // The metadata flags experimental pragmas, which is how ABI coder
+        // v2 was enabled before 0.6; from 0.8 it is the default
+        let abi_coder_v2 = if metadata.experimental {
+            Some(true)
+        } else if version < STABLE_ABI_CODER_V2 {
+            Some(false)
+        } else {
+            None
+        };
+
+        compiler_bug_finding(
+            self.category(),
+            &format!("Compiled with solc {}", version),
+            bugs,
+            abi_coder_v2,
+            Some(bytecode_location(program, metadata.offset)),
+        )
+        .into_iter()
+        .collect()
+    }
+
+    fn check_source(&self, _contract_address: &str, source: &SourceText) -> Vec<RiskFinding> {
+        let Some(range) = source.compiler_range() else {
+            return Vec::new();
+        };
+        // Only bugs no admitted compiler avoids are reported; a floating
+        // pragma is otherwise credited with its newest release
+        let Some(bugs) = BugList::bundled().unavoidable(range) else {
+            return Vec::new();
+        };
+        let Some(pragma) = source.ast().pragmas.iter().find(|pragma| pragma.name == "solidity") else {
+            return Vec::new();
+        };
+
+        compiler_bug_finding(
+            self.category(),
+            &format!("Every compiler admitted by the pragma ({}) is", range),
+            bugs,
+            uses_abi_coder_v2(source, range),
+            Some(source.span_location(pragma.span)),
+        )
+        .into_iter()
+        .collect()
+    }
+
+    fn check_transaction(&self, _contract_address: &str, _tx_data: &str) -> Vec<RiskFinding> {
+        Vec::new()
     }
 }
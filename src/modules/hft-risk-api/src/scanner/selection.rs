@@ -0,0 +1,241 @@
+//! Rule Selection - Which rules an assessment checks, and how they report
+//!
+//! Selections come from the config file, the API key and the request, in
+//! that order. Each layer can only narrow the rules checked by the previous
+//! one; overrides from later layers win field by field.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::{RiskFinding, Severity};
+use crate::scanner::RuleMetadata;
+
+/// Rule selection: `rules: {include, exclude, overrides}`
+///
+/// Rules are referenced by id (`REENT-001`) or name (`REENTRANCY`).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RuleSelection {
+    /// Rules to check; empty keeps every rule of the previous layer
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Rules not to check
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Per-rule severity and confidence overrides
+    #[serde(default)]
+    pub overrides: BTreeMap<String, RuleOverride>,
+}
+
+impl RuleSelection {
+    /// Read a selection from a `.toml` or `.json` file
+    pub fn read_file(path: &Path) -> Result<Self, String> {
+        let data = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(&data).map_err(|e| format!("{}: {}", path.display(), e)),
+            Some("json") => serde_json::from_str(&data).map_err(|e| format!("{}: {}", path.display(), e)),
+            _ => Err(format!("{}: expected a .toml or .json file", path.display())),
+        }
+    }
+}
+
+/// Replacement severity and/or confidence for a rule's findings
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RuleOverride {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub severity: Option<Severity>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f32>,
+}
+
+impl RuleOverride {
+    /// `other`'s fields where set, else `self`'s
+    fn merged(&self, other: &RuleOverride) -> RuleOverride {
+        RuleOverride {
+            severity: other.severity.clone().or_else(|| self.severity.clone()),
+            confidence: other.confidence.or(self.confidence),
+        }
+    }
+}
+
+/// Rules an assessment checked and the overrides applied to their findings
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct EffectiveRules {
+    /// Ids of the rules checked
+    pub checked: BTreeSet<String>,
+    /// Overrides by rule id
+    pub overrides: BTreeMap<String, RuleOverride>,
+}
+
+impl EffectiveRules {
+    /// Every rule of `catalogue`, without overrides
+    pub fn all(catalogue: &[RuleMetadata]) -> Self {
+        Self {
+            checked: catalogue.iter().map(|rule| rule.id.clone()).collect(),
+            overrides: BTreeMap::new(),
+        }
+    }
+
+    /// Narrow to `selection`, resolving rule references against `catalogue`
+    pub fn narrow(&self, selection: &RuleSelection, catalogue: &[RuleMetadata]) -> Result<Self, String> {
+        let resolve = |reference: &String| -> Result<String, String> {
+            catalogue
+                .iter()
+                .find(|rule| rule.id == *reference || rule.name == *reference)
+                .map(|rule| rule.id.clone())
+                .ok_or_else(|| format!("unknown rule '{}'", reference))
+        };
+
+        let mut checked = self.checked.clone();
+        if !selection.include.is_empty() {
+            let include = selection
+                .include
+                .iter()
+                .map(resolve)
+                .collect::<Result<BTreeSet<_>, _>>()?;
+            checked.retain(|id| include.contains(id));
+        }
+        for reference in &selection.exclude {
+            checked.remove(&resolve(reference)?);
+        }
+
+        let mut overrides = self.overrides.clone();
+        for (reference, rule_override) in &selection.overrides {
+            if let Some(confidence) = rule_override.confidence {
+                if !(0.0..=1.0).contains(&confidence) {
+                    return Err(format!("confidence override for '{}' must be within 0..=1", reference));
+                }
+            }
+            let id = resolve(reference)?;
+            let merged = overrides.get(&id).cloned().unwrap_or_default().merged(rule_override);
+            overrides.insert(id, merged);
+        }
+        overrides.retain(|id, _| checked.contains(id));
+
+        Ok(Self { checked, overrides })
+    }
+
+    /// Drop findings of unchecked rules and apply overrides to the rest
+    pub fn apply(&self, findings: &mut Vec<RiskFinding>) {
+        findings.retain(|finding| self.checked.contains(&finding.rule_id));
+        for finding in findings.iter_mut() {
+            if let Some(rule_override) = self.overrides.get(&finding.rule_id) {
+                if let Some(severity) = &rule_override.severity {
+                    finding.severity = severity.clone();
+                }
+                if let Some(confidence) = rule_override.confidence {
+                    finding.confidence = confidence;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::OwaspScanner;
+
+    #[test]
+    fn test_layers_only_narrow_and_overrides_merge() {
+        let catalogue = OwaspScanner::new().catalogue();
+        let config = RuleSelection {
+            exclude: vec!["FLASH_LOAN".to_string()],
+            overrides: BTreeMap::from([(
+                "REENT-001".to_string(),
+                RuleOverride { severity: Some(Severity::High), confidence: None },
+            )]),
+            ..Default::default()
+        };
+        let request = RuleSelection {
+            include: vec!["REENTRANCY".to_string(), "FLASH-001".to_string(), "TX_ORIGIN".to_string()],
+            overrides: BTreeMap::from([(
+                "REENTRANCY".to_string(),
+                RuleOverride { severity: None, confidence: Some(0.4) },
+            )]),
+            ..Default::default()
+        };
+
+        let effective = EffectiveRules::all(&catalogue)
+            .narrow(&config, &catalogue)
+            .and_then(|rules| rules.narrow(&request, &catalogue))
+            .unwrap();
+
+        // FLASH-001 was excluded by the config and cannot be re-enabled
+        assert_eq!(
+            effective.checked.iter().map(String::as_str).collect::<Vec<_>>(),
+            ["ORIGIN-001", "REENT-001"]
+        );
+        assert_eq!(
+            effective.overrides["REENT-001"],
+            RuleOverride { severity: Some(Severity::High), confidence: Some(0.4) }
+        );
+    }
+
+    #[test]
+    fn test_apply_filters_and_overrides_findings() {
+        let scanner = OwaspScanner::new();
+        let catalogue = scanner.catalogue();
+        let mut findings = scanner.scan_source(
+            "0xvault",
+            "contract V {\n    function f(address a) external { payable(a).transfer(1); require(tx.origin == owner); }\n}\n",
+        ).findings;
+        assert!(findings.iter().any(|f| f.rule_id == "ORIGIN-001"));
+
+        let selection = RuleSelection {
+            include: vec!["REENT-001".to_string()],
+            overrides: BTreeMap::from([(
+                "REENT-001".to_string(),
+                RuleOverride { severity: Some(Severity::Low), confidence: Some(0.3) },
+            )]),
+            ..Default::default()
+        };
+        EffectiveRules::all(&catalogue)
+            .narrow(&selection, &catalogue)
+            .unwrap()
+            .apply(&mut findings);
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule_id, "REENT-001");
+        assert_eq!(findings[0].severity, Severity::Low);
+        assert_eq!(findings[0].confidence, 0.3);
+    }
+
+    #[test]
+    fn test_read_toml_selection() {
+        let path = std::env::temp_dir().join(format!("rules-{}.toml", uuid::Uuid::new_v4()));
+        fs::write(
+            &path,
+            "exclude = [\"FLASH_LOAN\"]\n\n[overrides.TIME-001]\nseverity = \"medium\"\nconfidence = 0.7\n",
+        )
+        .unwrap();
+        let selection = RuleSelection::read_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(selection.exclude, ["FLASH_LOAN"]);
+        assert_eq!(
+            selection.overrides["TIME-001"],
+            RuleOverride { severity: Some(Severity::Medium), confidence: Some(0.7) }
+        );
+    }
+
+    #[test]
+    fn test_unknown_rules_and_bad_confidence_rejected() {
+        let catalogue = OwaspScanner::new().catalogue();
+        let all = EffectiveRules::all(&catalogue);
+
+        let unknown = RuleSelection { exclude: vec!["NOPE-001".to_string()], ..Default::default() };
+        assert!(all.narrow(&unknown, &catalogue).is_err());
+
+        let bad = RuleSelection {
+            overrides: BTreeMap::from([(
+                "AC-001".to_string(),
+                RuleOverride { severity: None, confidence: Some(1.5) },
+            )]),
+            ..Default::default()
+        };
+        assert!(all.narrow(&bad, &catalogue).is_err());
+    }
+}
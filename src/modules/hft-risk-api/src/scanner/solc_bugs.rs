@@ -0,0 +1,178 @@
+//! Known solc compiler bugs
+//!
+//! The list is bundled from `data/solc/`, which holds `bugs.json` and
+//! `bugs_by_version.json` in the format of the Solidity repository's
+//! `docs/` directory, so the files can be refreshed by copying the upstream
+//! ones over. Fields the scanner does not use (`uid`, `link`, release
+//! dates) are ignored.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+
+use crate::models::Severity;
+use crate::scanner::solidity::version::{Version, VersionRange};
+
+const BUGS: &str = include_str!("../../data/solc/bugs.json");
+const BUGS_BY_VERSION: &str = include_str!("../../data/solc/bugs_by_version.json");
+
+/// One entry of `bugs.json`
+#[derive(Debug, Clone, Deserialize)]
+pub struct KnownBug {
+    pub name: String,
+    pub summary: String,
+    /// First affected release; absent when every earlier release is affected
+    #[serde(default)]
+    pub introduced: Option<String>,
+    /// First release with the fix
+    pub fixed: String,
+    /// Upstream severity, e.g. `low` or `medium/high`
+    pub severity: String,
+    #[serde(default)]
+    pub conditions: BugConditions,
+}
+
+/// Compiler settings a bug needs in order to trigger
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BugConditions {
+    #[serde(rename = "ABIEncoderV2", default)]
+    pub abi_encoder_v2: Option<bool>,
+    #[serde(default)]
+    pub optimizer: Option<bool>,
+    #[serde(rename = "yulOptimizer", default)]
+    pub yul_optimizer: Option<bool>,
+    /// EVM version constraint such as `>=constantinople`
+    #[serde(rename = "evmVersion", default)]
+    pub evm_version: Option<String>,
+}
+
+impl BugConditions {
+    /// Whether the bug depends on compiler settings that cannot be recovered
+    /// from source or bytecode
+    pub fn needs_settings(&self) -> bool {
+        self.optimizer == Some(true) || self.yul_optimizer == Some(true) || self.evm_version.is_some()
+    }
+}
+
+impl KnownBug {
+    /// Upstream severity mapped onto the finding scale; combined levels
+    /// take the higher one, except `low/medium`, which is reported as medium
+    pub fn risk(&self) -> Severity {
+        match self.severity.trim() {
+            "very low" => Severity::Info,
+            "low" => Severity::Low,
+            "high" | "medium/high" => Severity::High,
+            _ => Severity::Medium,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct VersionEntry {
+    bugs: Vec<String>,
+}
+
+/// Bug list indexed by release
+#[derive(Debug)]
+pub struct BugList {
+    bugs: Vec<KnownBug>,
+    /// Release -> indices into `bugs`
+    releases: BTreeMap<Version, Vec<usize>>,
+}
+
+impl BugList {
+    /// The list bundled with the binary
+    pub fn bundled() -> &'static BugList {
+        static LIST: OnceLock<BugList> = OnceLock::new();
+        LIST.get_or_init(|| Self::parse(BUGS, BUGS_BY_VERSION).expect("bundled solc bug list is valid"))
+    }
+
+    /// Parse `bugs.json` and `bugs_by_version.json`
+    pub fn parse(bugs: &str, by_version: &str) -> Result<Self, String> {
+        let bugs: Vec<KnownBug> = serde_json::from_str(bugs).map_err(|e| format!("bugs.json: {}", e))?;
+        let by_version: HashMap<String, VersionEntry> =
+            serde_json::from_str(by_version).map_err(|e| format!("bugs_by_version.json: {}", e))?;
+
+        let index: HashMap<&str, usize> = bugs.iter().enumerate().map(|(i, bug)| (bug.name.as_str(), i)).collect();
+        let mut releases = BTreeMap::new();
+        for (release, entry) in by_version {
+            let version = Version::parse(&release).ok_or_else(|| format!("invalid release '{}'", release))?;
+            let affected = entry
+                .bugs
+                .iter()
+                .map(|name| {
+                    index
+                        .get(name.as_str())
+                        .copied()
+                        .ok_or_else(|| format!("release {} lists unknown bug '{}'", release, name))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            releases.insert(version, affected);
+        }
+        Ok(Self { bugs, releases })
+    }
+
+    /// Releases the list covers, oldest first
+    pub fn releases(&self) -> impl Iterator<Item = Version> + '_ {
+        self.releases.keys().copied()
+    }
+
+    /// Bugs affecting `version`; `None` for a release the list does not know
+    pub fn affecting(&self, version: Version) -> Option<Vec<&KnownBug>> {
+        let affected = self.releases.get(&version)?;
+        Some(affected.iter().map(|&i| &self.bugs[i]).collect())
+    }
+
+    /// Bugs affecting every known release in `range`
+    ///
+    /// These are the bugs the contract has whichever admitted compiler built
+    /// it. `None` when the range admits no known release.
+    pub fn unavoidable(&self, range: &VersionRange) -> Option<Vec<&KnownBug>> {
+        let mut admitted = self
+            .releases
+            .iter()
+            .filter(|(version, _)| range.contains(**version))
+            .map(|(_, affected)| affected);
+        let mut common = admitted.next()?.clone();
+        for affected in admitted {
+            common.retain(|i| affected.contains(i));
+        }
+        Some(common.into_iter().map(|i| &self.bugs[i]).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(bugs: &[&KnownBug]) -> Vec<String> {
+        bugs.iter().map(|bug| bug.name.clone()).collect()
+    }
+
+    #[test]
+    fn test_bundled_list_lookups() {
+        let list = BugList::bundled();
+        assert!(list.releases().any(|v| v == Version::new(0, 4, 24)));
+
+        let bugs = list.affecting(Version::new(0, 4, 24)).unwrap();
+        let exp = bugs.iter().find(|bug| bug.name == "ExpExponentCleanup").unwrap();
+        assert_eq!(exp.risk(), Severity::High);
+        assert!(list.affecting(Version::new(0, 9, 99)).is_none());
+
+        // Only bugs open across the whole range are unavoidable
+        let range = VersionRange::parse(">=0.8.13 <0.8.15").unwrap();
+        let common = names(&list.unavoidable(&range).unwrap());
+        assert!(common.contains(&"InlineAssemblyMemorySideEffects".to_string()));
+        assert!(!common.contains(&"AbiEncodeCallLiteralAsFixedBytesBug".to_string()));
+        assert!(list.unavoidable(&VersionRange::parse(">=0.9.0").unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_bug_names() {
+        let bugs = r#"[{"name": "A", "summary": "s", "fixed": "0.4.1", "severity": "low", "uid": "SOL-1"}]"#;
+        assert!(BugList::parse(bugs, r#"{"0.4.0": {"bugs": ["A"], "released": "2016-09-08"}}"#).is_ok());
+        let err = BugList::parse(bugs, r#"{"0.4.0": {"bugs": ["B"]}}"#).unwrap_err();
+        assert!(err.contains("unknown bug 'B'"));
+    }
+}
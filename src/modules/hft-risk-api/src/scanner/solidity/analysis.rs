@@ -0,0 +1,684 @@
+//! Contract analysis
+//!
+//! Views over the syntax tree that rules share: the members a contract
+//! sees through inheritance, the internal calls between its functions and
+//! the order in which a function calls out and writes state. Bases are
+//! looked up in the contract's own source unit, then in the units it
+//! imports; members of bases that were not submitted are unknown.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use super::ast::{
+    Block, Contract, ContractKind, Expression, ExpressionKind, Function, SourceUnit, Span, Statement,
+    StatementKind, VariableDeclaration,
+};
+
+/// C3 linearization of `contract`, most derived first
+///
+/// Solidity lists bases from most base-like to most derived, so the merge
+/// takes them in reverse. Bases `lookup` cannot find are left out; an
+/// inconsistent hierarchy, which solc rejects, falls back to the first
+/// remaining head and a cycle ends at the repeated contract.
+fn linearize<'a>(
+    contract: &'a Contract,
+    lookup: &impl Fn(&str) -> Option<&'a Contract>,
+    visiting: &mut Vec<*const Contract>,
+) -> Vec<&'a Contract> {
+    if visiting.contains(&(contract as *const Contract)) {
+        return vec![contract];
+    }
+    visiting.push(contract);
+    let bases: Vec<&'a Contract> = contract.bases.iter().rev().filter_map(|base| lookup(&base.name)).collect();
+    let mut sequences: Vec<Vec<&'a Contract>> =
+        bases.iter().map(|base| linearize(base, lookup, visiting)).collect();
+    sequences.push(bases);
+    visiting.pop();
+
+    let mut linearized = vec![contract];
+    loop {
+        sequences.retain(|sequence| !sequence.is_empty());
+        let Some(first) = sequences.first() else {
+            break;
+        };
+        // A head that appears in no other sequence's tail
+        let next = sequences
+            .iter()
+            .map(|sequence| sequence[0])
+            .find(|head| {
+                !sequences
+                    .iter()
+                    .any(|sequence| sequence[1..].iter().any(|other| std::ptr::eq(*other, *head)))
+            })
+            .unwrap_or(first[0]);
+        if !linearized.iter().any(|seen| std::ptr::eq(*seen, next)) {
+            linearized.push(next);
+        }
+        for sequence in &mut sequences {
+            sequence.retain(|candidate| !std::ptr::eq(*candidate, next));
+        }
+    }
+    linearized
+}
+
+/// Call into another contract, which can call back into this one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExternalCall {
+    /// The called member, e.g. `call` in `to.call{value: v}("")`
+    pub span: Span,
+    /// `transfer`, `send` or a `value` call option
+    pub sends_value: bool,
+}
+
+/// State variable written after an external call on some path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LateWrite {
+    pub variable: String,
+    /// The written expression, e.g. `balances[msg.sender]`
+    pub span: Span,
+    /// First external call on a path reaching the write
+    pub call: ExternalCall,
+}
+
+/// External calls and late state writes of a function, internal callees
+/// included
+#[derive(Debug, Default)]
+pub struct Effects {
+    pub calls: Vec<ExternalCall>,
+    pub late_writes: Vec<LateWrite>,
+}
+
+/// A contract with the members it inherits from bases in the same unit
+#[derive(Debug)]
+pub struct ContractScope<'a> {
+    pub contract: &'a Contract,
+    /// The contract and its bases in C3 linearization order, most derived
+    /// first, as solc resolves overrides and `super`
+    pub linearized: Vec<&'a Contract>,
+}
+
+impl<'a> ContractScope<'a> {
+    /// Scope of `contract`, with bases looked up in `units` in order
+    pub fn new(units: &[&'a SourceUnit], contract: &'a Contract) -> Self {
+        let lookup = |name: &str| {
+            units
+                .iter()
+                .flat_map(|unit| &unit.contracts)
+                .find(|candidate| candidate.name.name == name)
+        };
+        let linearized = linearize(contract, &lookup, &mut Vec::new());
+        Self { contract, linearized }
+    }
+
+    /// Scopes of every contract, abstract contract and library in the
+    /// first of `units`, the others being the units it imports
+    pub fn all(units: &[&'a SourceUnit]) -> Vec<Self> {
+        let Some(unit) = units.first() else {
+            return Vec::new();
+        };
+        unit.contracts
+            .iter()
+            .filter(|contract| contract.kind != ContractKind::Interface)
+            .map(|contract| Self::new(units, contract))
+            .collect()
+    }
+
+    /// Implemented functions visible in the contract, overrides first
+    pub fn functions(&self) -> impl Iterator<Item = &'a Function> + '_ {
+        self.linearized
+            .iter()
+            .flat_map(|contract| &contract.functions)
+            .filter(|function| function.body.is_some())
+    }
+
+    /// The function named `name`, the most derived override if several
+    pub fn function(&self, name: &str) -> Option<&'a Function> {
+        self.functions().find(|function| function.display_name() == name)
+    }
+
+    /// The modifier named `name`
+    pub fn modifier(&self, name: &str) -> Option<&'a Function> {
+        self.linearized
+            .iter()
+            .flat_map(|contract| &contract.modifiers)
+            .find(|modifier| modifier.display_name() == name)
+    }
+
+    /// State variables declared in the contract or its bases
+    pub fn state_variables(&self) -> impl Iterator<Item = &'a VariableDeclaration> + '_ {
+        self.linearized.iter().flat_map(|contract| &contract.state_variables)
+    }
+
+    /// The non-constant state variable named `name`
+    pub fn state_variable(&self, name: &str) -> Option<&'a VariableDeclaration> {
+        self.state_variables()
+            .find(|variable| !variable.constant && variable.name.as_ref().is_some_and(|own| own.name == name))
+    }
+
+    /// Position in `linearized` of the contract declaring `function`
+    fn declared_in(&self, function: &Function) -> usize {
+        self.linearized
+            .iter()
+            .position(|contract| contract.functions.iter().any(|own| std::ptr::eq(own, function)))
+            .unwrap_or(0)
+    }
+
+    /// Function of the scope called by `call`, made from a function
+    /// declared in `linearized[declared_in]`
+    ///
+    /// `super.f()` resolves to the next `f` after the declaring contract in
+    /// linearization order.
+    fn resolve(&self, declared_in: usize, call: &Expression) -> Option<&'a Function> {
+        let ExpressionKind::Call { callee, .. } = &call.kind else {
+            return None;
+        };
+        match &callee.kind {
+            ExpressionKind::Identifier(name) => self.function(name),
+            ExpressionKind::Member { expr, member } => match expr.as_identifier() {
+                Some("this") => self.function(&member.name),
+                Some("super") => self.linearized[declared_in + 1..]
+                    .iter()
+                    .flat_map(|contract| &contract.functions)
+                    .find(|base| base.body.is_some() && base.display_name() == member.name),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Functions of the scope called by name from `function`'s body
+    pub fn callees(&self, function: &Function) -> Vec<&'a Function> {
+        let declared_in = self.declared_in(function);
+        let mut callees: Vec<&'a Function> = Vec::new();
+        if let Some(body) = &function.body {
+            body.walk_expressions(&mut |expression| {
+                if let Some(found) = self.resolve(declared_in, expression) {
+                    if !callees.iter().any(|known| std::ptr::eq(*known, found)) {
+                        callees.push(found);
+                    }
+                }
+            });
+        }
+        callees
+    }
+
+    /// `function` and every function it reaches through internal calls,
+    /// without descending into functions for which `stop` holds
+    pub fn reachable(&self, function: &'a Function, stop: impl Fn(&Function) -> bool) -> Vec<&'a Function> {
+        let mut seen: HashSet<*const Function> = HashSet::from([function as *const Function]);
+        let mut reached = vec![function];
+        let mut index = 0;
+        while let Some(next) = reached.get(index).copied() {
+            // The caller's result is discarded past the deadline
+            if crate::scanner::deadline_passed() {
+                break;
+            }
+            index += 1;
+            for callee in self.callees(next) {
+                if !stop(callee) && seen.insert(callee as *const Function) {
+                    reached.push(callee);
+                }
+            }
+        }
+        reached
+    }
+
+    /// External calls made by `function` and the state variables it writes
+    /// after one of them, following statement order
+    ///
+    /// Both branches of an `if` are taken, loop bodies run twice so a write
+    /// at the top of a loop follows a call at its end, paths ending in
+    /// `revert` are dropped and internal calls are followed into the
+    /// callee.
+    pub fn effects(&self, function: &'a Function) -> Effects {
+        let mut walker = EffectWalker { scope: self, frames: Vec::new(), effects: Effects::default() };
+        walker.enter(function, Flow { reachable: true, call: None });
+        walker.effects
+    }
+
+    /// State variables `function` reads or writes, internal callees included
+    pub fn state_accesses(&self, function: &'a Function) -> BTreeSet<&'a str> {
+        let mut accessed = BTreeSet::new();
+        for reached in self.reachable(function, |_| false) {
+            let locals = locals(reached);
+            if let Some(body) = &reached.body {
+                body.walk_expressions(&mut |expression| {
+                    if let ExpressionKind::Identifier(name) = &expression.kind {
+                        if self.is_state(&locals, name) {
+                            accessed.insert(name.as_str());
+                        }
+                    }
+                });
+            }
+        }
+        accessed
+    }
+
+    /// Whether `name` in the body of `function` refers to state
+    pub fn names_state(&self, function: &Function, name: &str) -> bool {
+        self.is_state(&locals(function), name)
+    }
+
+    /// Whether `name` refers to state, either a state variable or a local
+    /// `storage` pointer
+    fn is_state(&self, locals: &HashMap<&str, &VariableDeclaration>, name: &str) -> bool {
+        match locals.get(name) {
+            Some(local) => local.location.as_deref() == Some("storage"),
+            None => self.state_variable(name).is_some(),
+        }
+    }
+}
+
+/// Parameters, return variables and local variables of `function` by name
+fn locals(function: &Function) -> HashMap<&str, &VariableDeclaration> {
+    let mut locals: HashMap<&str, &VariableDeclaration> = function
+        .parameters
+        .iter()
+        .chain(&function.returns)
+        .filter_map(|variable| Some((variable.name.as_ref()?.name.as_str(), variable)))
+        .collect();
+    if let Some(body) = &function.body {
+        body.walk(&mut |statement| {
+            if let StatementKind::VariableDeclaration { declarations, .. } = &statement.kind {
+                for variable in declarations.iter().flatten() {
+                    if let Some(name) = &variable.name {
+                        locals.insert(&name.name, variable);
+                    }
+                }
+            }
+        });
+    }
+    locals
+}
+
+/// Whether a type names a contract or interface: `IERC20`, not `uint256`,
+/// `address` or `IERC20[]`
+fn is_contract_type(text: &str) -> bool {
+    text.starts_with(|c: char| c.is_ascii_uppercase()) && text.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Element type of an array or mapping type, `IPool` for
+/// `mapping(address => IPool)`
+fn element_type(text: &str) -> &str {
+    match text.strip_suffix("[]") {
+        Some(element) => element,
+        None if text.starts_with("mapping") => {
+            text.rsplit("=>").next().unwrap_or(text).trim().trim_end_matches(')').trim()
+        }
+        None => text,
+    }
+}
+
+/// What is known at a point of a function: whether it is reached and
+/// whether an external call happened before it
+#[derive(Debug, Clone, Copy)]
+struct Flow {
+    reachable: bool,
+    call: Option<ExternalCall>,
+}
+
+impl Flow {
+    /// State after either of two paths
+    fn join(self, other: Flow) -> Flow {
+        match (self.reachable, other.reachable) {
+            (false, _) => other,
+            (_, false) => self,
+            _ => Flow { reachable: true, call: self.call.or(other.call) },
+        }
+    }
+}
+
+/// A function being walked, with the states at its `return`s
+struct Frame<'a> {
+    function: &'a Function,
+    declared_in: usize,
+    locals: HashMap<&'a str, &'a VariableDeclaration>,
+    returns: Vec<Flow>,
+}
+
+struct EffectWalker<'s, 'a> {
+    scope: &'s ContractScope<'a>,
+    /// Internal call stack; recursive calls are not followed
+    frames: Vec<Frame<'a>>,
+    effects: Effects,
+}
+
+impl<'a> EffectWalker<'_, 'a> {
+    /// Walk `function`'s body from `flow`, returning the state after it
+    fn enter(&mut self, function: &'a Function, flow: Flow) -> Flow {
+        let Some(body) = &function.body else {
+            return flow;
+        };
+        if self.frames.iter().any(|frame| std::ptr::eq(frame.function, function)) {
+            return flow;
+        }
+        self.frames.push(Frame {
+            function,
+            declared_in: self.scope.declared_in(function),
+            locals: locals(function),
+            returns: Vec::new(),
+        });
+        let mut end = flow;
+        self.block(body, &mut end);
+        let frame = self.frames.pop().expect("frame pushed above");
+        frame.returns.into_iter().fold(end, Flow::join)
+    }
+
+    fn block(&mut self, block: &'a Block, flow: &mut Flow) {
+        for statement in &block.statements {
+            self.statement(statement, flow);
+        }
+    }
+
+    fn statement(&mut self, statement: &'a Statement, flow: &mut Flow) {
+        match &statement.kind {
+            StatementKind::Block(block) => self.block(block, flow),
+            StatementKind::Expression(expression) | StatementKind::Emit(expression) => {
+                self.expression(expression, flow)
+            }
+            StatementKind::VariableDeclaration { value, .. } => {
+                if let Some(value) = value {
+                    self.expression(value, flow);
+                }
+            }
+            StatementKind::If { condition, then_branch, else_branch } => {
+                self.expression(condition, flow);
+                let mut then_flow = *flow;
+                self.statement(then_branch, &mut then_flow);
+                if let Some(else_branch) = else_branch {
+                    self.statement(else_branch, flow);
+                }
+                *flow = then_flow.join(*flow);
+            }
+            StatementKind::While { condition, body } => {
+                for _ in 0..2 {
+                    self.expression(condition, flow);
+                    let mut iteration = *flow;
+                    self.statement(body, &mut iteration);
+                    *flow = flow.join(iteration);
+                }
+            }
+            StatementKind::DoWhile { body, condition } => {
+                for _ in 0..2 {
+                    self.statement(body, flow);
+                    self.expression(condition, flow);
+                }
+            }
+            StatementKind::For { init, condition, update, body } => {
+                if let Some(init) = init {
+                    self.statement(init, flow);
+                }
+                for _ in 0..2 {
+                    if let Some(condition) = condition {
+                        self.expression(condition, flow);
+                    }
+                    let mut iteration = *flow;
+                    self.statement(body, &mut iteration);
+                    if let Some(update) = update {
+                        self.expression(update, &mut iteration);
+                    }
+                    *flow = flow.join(iteration);
+                }
+            }
+            StatementKind::Return(value) => {
+                if let Some(value) = value {
+                    self.expression(value, flow);
+                }
+                if flow.reachable {
+                    if let Some(frame) = self.frames.last_mut() {
+                        frame.returns.push(*flow);
+                    }
+                }
+                flow.reachable = false;
+            }
+            StatementKind::Revert(error) => {
+                self.expression(error, flow);
+                flow.reachable = false;
+            }
+            StatementKind::Try { call, body, catches } => {
+                self.expression(call, flow);
+                let entry = *flow;
+                self.block(body, flow);
+                for catch in catches {
+                    let mut caught = entry;
+                    self.block(catch, &mut caught);
+                    *flow = flow.join(caught);
+                }
+            }
+            StatementKind::Break
+            | StatementKind::Continue
+            | StatementKind::Assembly
+            | StatementKind::Placeholder
+            | StatementKind::Unparsed => {}
+        }
+    }
+
+    /// Walk `expression` in evaluation order: operands before the
+    /// operation, the assigned value before the write
+    fn expression(&mut self, expression: &'a Expression, flow: &mut Flow) {
+        if !flow.reachable {
+            return;
+        }
+        match &expression.kind {
+            ExpressionKind::Assign { target, value, .. } => {
+                self.expression(value, flow);
+                self.write(target, flow);
+            }
+            ExpressionKind::Unary { operator, operand, .. }
+                if matches!(operator.as_str(), "++" | "--" | "delete") =>
+            {
+                self.write(operand, flow)
+            }
+            ExpressionKind::Call { .. } => {
+                for child in expression.children() {
+                    self.expression(child, flow);
+                }
+                if expression.called_name() == Some("revert") {
+                    flow.reachable = false;
+                } else if let Some(call) = self.external_call(expression) {
+                    flow.call = flow.call.or(Some(call));
+                    if !self.effects.calls.contains(&call) {
+                        self.effects.calls.push(call);
+                    }
+                } else if let Some(callee) = self.frame().and_then(|frame| {
+                    self.scope.resolve(frame.declared_in, expression)
+                }) {
+                    *flow = self.enter(callee, *flow);
+                }
+            }
+            _ => {
+                for child in expression.children() {
+                    self.expression(child, flow);
+                }
+            }
+        }
+    }
+
+    /// Walk a write to `target`, recording it if it lands in state after
+    /// an external call
+    fn write(&mut self, target: &'a Expression, flow: &mut Flow) {
+        let target = target.unparenthesized();
+        if let ExpressionKind::Tuple(items) = &target.kind {
+            for item in items.iter().flatten() {
+                self.write(item, flow);
+            }
+            return;
+        }
+
+        self.place(target, flow);
+        let (Some(call), Some(name)) = (flow.call, target.root_identifier()) else {
+            return;
+        };
+        if flow.reachable && self.frame().is_some_and(|frame| self.scope.is_state(&frame.locals, name)) {
+            let write = LateWrite { variable: name.to_string(), span: target.span, call };
+            if !self.effects.late_writes.contains(&write) {
+                self.effects.late_writes.push(write);
+            }
+        }
+    }
+
+    /// Walk the expressions evaluated to locate a written place: the
+    /// indexes of `a[i][j]`, not `a` itself
+    fn place(&mut self, target: &'a Expression, flow: &mut Flow) {
+        match &target.unparenthesized().kind {
+            ExpressionKind::Identifier(_) => {}
+            ExpressionKind::Index { base, index } => {
+                self.place(base, flow);
+                if let Some(index) = index {
+                    self.expression(index, flow);
+                }
+            }
+            ExpressionKind::Member { expr, .. } => self.place(expr, flow),
+            _ => self.expression(target, flow),
+        }
+    }
+
+    fn frame(&self) -> Option<&Frame<'a>> {
+        self.frames.last()
+    }
+
+    /// `call` as a call into another contract: a low-level call, an ether
+    /// transfer, or a function called on a contract-typed value such as
+    /// `token.transfer(...)` or `IERC20(token).approve(...)`
+    fn external_call(&self, call: &Expression) -> Option<ExternalCall> {
+        let (object, member) = call.member_call()?;
+        let ExpressionKind::Call { callee, arguments } = &call.kind else {
+            return None;
+        };
+        let value_option = matches!(
+            &callee.kind,
+            ExpressionKind::CallOptions { options, .. } if options.iter().any(|(name, _)| name.name == "value")
+        );
+        let external = match member.name.as_str() {
+            "call" | "delegatecall" => true,
+            "send" => return Some(ExternalCall { span: member.span, sends_value: true }),
+            "transfer" if arguments.len() == 1 => {
+                return Some(ExternalCall { span: member.span, sends_value: true })
+            }
+            _ => self.is_contract_value(object),
+        };
+        external.then_some(ExternalCall { span: member.span, sends_value: value_option })
+    }
+
+    /// Whether `object` evaluates to a contract reference
+    fn is_contract_value(&self, object: &Expression) -> bool {
+        let type_of = |name: &str| {
+            self.frame()
+                .and_then(|frame| frame.locals.get(name).copied())
+                .or_else(|| self.scope.state_variable(name))
+                .map(|variable| variable.type_name.text.as_str())
+        };
+        match &object.unparenthesized().kind {
+            // `IERC20(token)`
+            ExpressionKind::Call { callee, arguments } => {
+                arguments.len() == 1 && callee.as_identifier().is_some_and(is_contract_type)
+            }
+            ExpressionKind::Identifier(name) => type_of(name).is_some_and(is_contract_type),
+            ExpressionKind::Index { base, .. } => base
+                .as_identifier()
+                .and_then(type_of)
+                .is_some_and(|text| is_contract_type(element_type(text))),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::solidity::parse;
+
+    fn reached_body(scope: &ContractScope, function: &Function) -> String {
+        let mut calls = String::new();
+        for reached in scope.reachable(function, |_| false) {
+            reached.body.as_ref().unwrap().walk_expressions(&mut |expression| {
+                if let Some(name) = expression.called_name() {
+                    calls.push_str(name);
+                    calls.push(' ');
+                }
+            });
+        }
+        calls
+    }
+
+    #[test]
+    fn test_inherited_functions_are_reachable() {
+        let syntax = parse(
+            "abstract contract Base {\n    function _burn() internal { selfdestruct(payable(msg.sender)); }\n    function _guard() internal {}\n}\n\
+             contract Token is Base {\n    function _burn() internal override { _guard(); super._burn(); }\n    function kill() external { _burn(); }\n}\n",
+        );
+        let token = &syntax.unit.contracts[1];
+        let scope = ContractScope::new(&[&syntax.unit], token);
+        assert_eq!(scope.linearized.iter().map(|c| c.name.name.as_str()).collect::<Vec<_>>(), ["Token", "Base"]);
+
+        let kill = scope.function("kill").unwrap();
+        let reached: Vec<&str> = scope.reachable(kill, |_| false).iter().map(|f| f.display_name()).collect();
+        // `_burn` resolves to the override, `super._burn` to the base
+        assert_eq!(reached, ["kill", "_burn", "_guard", "_burn"]);
+        assert!(reached_body(&scope, kill).contains("selfdestruct"));
+
+        let pruned = scope.reachable(kill, |f| f.display_name() == "_burn");
+        assert_eq!(pruned.len(), 1);
+    }
+
+    #[test]
+    fn test_diamond_inheritance_is_c3_linearized() {
+        let syntax = parse(
+            "contract Base { function f() public virtual {} modifier only() virtual { _; } }\n\
+             contract A is Base { function f() public virtual override {} modifier only() virtual override { _; } }\n\
+             contract B is Base {}\n\
+             contract C is A, B {}\n",
+        );
+        let c = &syntax.unit.contracts[3];
+        let scope = ContractScope::new(&[&syntax.unit], c);
+        let order: Vec<&str> = scope.linearized.iter().map(|contract| contract.name.name.as_str()).collect();
+        assert_eq!(order, ["C", "B", "A", "Base"]);
+
+        // `A`'s override wins over `Base`, which is only reached after it
+        let a = &syntax.unit.contracts[1];
+        assert!(std::ptr::eq(scope.function("f").unwrap(), &a.functions[0]));
+        assert!(std::ptr::eq(scope.modifier("only").unwrap(), &a.modifiers[0]));
+    }
+
+    #[test]
+    fn test_bases_are_found_in_imported_units() {
+        let imported = parse("abstract contract Ownable { address owner; modifier restricted() { require(msg.sender == owner); _; } }\n");
+        let syntax = parse("import \"./Ownable.sol\";\ncontract Vault is Ownable, Missing { function kill() external restricted {} }\n");
+
+        let own = ContractScope::all(&[&syntax.unit]);
+        assert_eq!(own[0].linearized.len(), 1);
+        assert!(own[0].modifier("restricted").is_none());
+
+        let scopes = ContractScope::all(&[&syntax.unit, &imported.unit]);
+        assert_eq!(scopes.len(), 1);
+        let names: Vec<&str> = scopes[0].linearized.iter().map(|contract| contract.name.name.as_str()).collect();
+        assert_eq!(names, ["Vault", "Ownable"]);
+        assert!(scopes[0].modifier("restricted").is_some());
+        assert!(scopes[0].state_variable("owner").is_some());
+    }
+
+    #[test]
+    fn test_effects_follow_execution_order() {
+        let syntax = parse(
+            "contract Pool {\n    mapping(address => uint256) shares;\n    IVault vault;\n    uint256 total;\n\
+             function sweep() external { _pay(); _clear(); }\n\
+             function branch(bool b) external { if (b) { vault.pull(); return; } total = 1; }\n\
+             function loop(uint256 n) external { for (uint256 i; i < n; i++) { total += i; payable(msg.sender).transfer(i); } }\n\
+             function _pay() internal { vault.pull(); }\n    function _clear() internal { delete shares[msg.sender]; }\n}\n",
+        );
+        let scope = ContractScope::new(&[&syntax.unit], &syntax.unit.contracts[0]);
+        let late = |name: &str| -> Vec<String> {
+            scope.effects(scope.function(name).unwrap()).late_writes.iter().map(|w| w.variable.clone()).collect()
+        };
+
+        // The write in `_clear` follows the call in `_pay`
+        assert_eq!(late("sweep"), ["shares"]);
+        // The branch making the call returns before the write
+        assert!(late("branch").is_empty());
+        assert_eq!(scope.effects(scope.function("branch").unwrap()).calls.len(), 1);
+        // The next iteration writes after this one's transfer
+        assert_eq!(late("loop"), ["total"]);
+        assert!(scope.effects(scope.function("loop").unwrap()).calls[0].sends_value);
+
+        assert_eq!(scope.state_accesses(scope.function("sweep").unwrap()).into_iter().collect::<Vec<_>>(), ["shares", "vault"]);
+    }
+}
@@ -0,0 +1,533 @@
+//! Solidity syntax tree
+//!
+//! Covers the declarations and code rules look at: contracts, state
+//! variables, functions and modifiers, statements and expressions. Inline
+//! assembly is kept as an opaque statement. Every node records the byte
+//! span it was parsed from.
+
+/// Byte range `start..end` in the source
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// Span from the start of `self` to the end of `other`
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.start, other.end.max(self.start))
+    }
+}
+
+/// Name with its span
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ident {
+    pub name: String,
+    pub span: Span,
+}
+
+/// A parsed source file
+#[derive(Debug, Clone, Default)]
+pub struct SourceUnit {
+    pub pragmas: Vec<Pragma>,
+    pub imports: Vec<Import>,
+    pub contracts: Vec<Contract>,
+    /// Functions declared outside any contract
+    pub functions: Vec<Function>,
+    /// Custom errors declared outside any contract
+    pub errors: Vec<Ident>,
+}
+
+/// `pragma <name> <value>;`
+#[derive(Debug, Clone)]
+pub struct Pragma {
+    /// `solidity`, `abicoder`, `experimental`
+    pub name: String,
+    /// Rest of the directive as written, e.g. `^0.8.0 <0.9.0`
+    pub value: String,
+    pub span: Span,
+}
+
+/// `import "<path>" ...;`
+#[derive(Debug, Clone)]
+pub struct Import {
+    pub path: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContractKind {
+    Contract,
+    AbstractContract,
+    Interface,
+    Library,
+}
+
+#[derive(Debug, Clone)]
+pub struct Contract {
+    pub kind: ContractKind,
+    pub name: Ident,
+    /// Inherited contracts, in declaration order
+    pub bases: Vec<Ident>,
+    pub state_variables: Vec<VariableDeclaration>,
+    /// Functions, constructors, fallback and receive functions
+    pub functions: Vec<Function>,
+    /// Modifier definitions
+    pub modifiers: Vec<Function>,
+    /// `using <library> for <type>;`
+    pub using: Vec<Ident>,
+    pub events: Vec<Ident>,
+    pub errors: Vec<Ident>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FunctionKind {
+    Function,
+    Constructor,
+    Fallback,
+    Receive,
+    Modifier,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    Public,
+    External,
+    Internal,
+    Private,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mutability {
+    Pure,
+    View,
+    Payable,
+}
+
+/// A function, constructor, fallback, receive function or modifier
+#[derive(Debug, Clone)]
+pub struct Function {
+    pub kind: FunctionKind,
+    /// `None` for constructors, fallback and receive functions
+    pub name: Option<Ident>,
+    pub parameters: Vec<VariableDeclaration>,
+    pub returns: Vec<VariableDeclaration>,
+    /// Explicit visibility, if any
+    pub visibility: Option<Visibility>,
+    pub mutability: Option<Mutability>,
+    pub is_virtual: bool,
+    /// Modifier invocations, including base constructor calls
+    pub modifiers: Vec<ModifierInvocation>,
+    /// `None` for declarations without a body
+    pub body: Option<Block>,
+    /// From the first keyword to the end of the body or `;`
+    pub span: Span,
+    /// From the first keyword to the start of the body
+    pub header: Span,
+}
+
+impl Function {
+    /// Name used in messages: the declared name or the kind
+    pub fn display_name(&self) -> &str {
+        match (&self.name, self.kind) {
+            (Some(name), _) => &name.name,
+            (None, FunctionKind::Constructor) => "constructor",
+            (None, FunctionKind::Fallback) => "fallback",
+            (None, FunctionKind::Receive) => "receive",
+            (None, _) => "",
+        }
+    }
+
+    /// Callable from outside the contract
+    ///
+    /// Functions without explicit visibility were public before 0.5.
+    pub fn is_externally_callable(&self) -> bool {
+        match self.kind {
+            FunctionKind::Fallback | FunctionKind::Receive => true,
+            FunctionKind::Function => matches!(self.visibility, None | Some(Visibility::Public | Visibility::External)),
+            FunctionKind::Constructor | FunctionKind::Modifier => false,
+        }
+    }
+
+    /// Whether a modifier with this name is applied
+    pub fn has_modifier(&self, name: &str) -> bool {
+        self.modifiers.iter().any(|modifier| modifier.name.name == name)
+    }
+}
+
+/// `name(arguments)` in a function header
+#[derive(Debug, Clone)]
+pub struct ModifierInvocation {
+    pub name: Ident,
+    pub arguments: Vec<Expression>,
+    pub span: Span,
+}
+
+/// State variable, parameter, return value or local variable
+#[derive(Debug, Clone)]
+pub struct VariableDeclaration {
+    pub type_name: TypeName,
+    /// `None` for unnamed parameters and skipped tuple components
+    pub name: Option<Ident>,
+    /// `storage`, `memory` or `calldata`
+    pub location: Option<String>,
+    /// `constant` or `immutable` state variable
+    pub constant: bool,
+    pub visibility: Option<Visibility>,
+    pub value: Option<Expression>,
+    pub span: Span,
+}
+
+/// A type as written, e.g. `mapping(address => uint256)`
+#[derive(Debug, Clone)]
+pub struct TypeName {
+    pub text: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub statements: Vec<Statement>,
+    /// `unchecked { ... }`
+    pub unchecked: bool,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct Statement {
+    pub kind: StatementKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum StatementKind {
+    Block(Block),
+    Expression(Expression),
+    /// `T a = value;` or `(T a, , T b) = value;`
+    VariableDeclaration {
+        declarations: Vec<Option<VariableDeclaration>>,
+        value: Option<Expression>,
+    },
+    If {
+        condition: Expression,
+        then_branch: Box<Statement>,
+        else_branch: Option<Box<Statement>>,
+    },
+    While {
+        condition: Expression,
+        body: Box<Statement>,
+    },
+    DoWhile {
+        body: Box<Statement>,
+        condition: Expression,
+    },
+    For {
+        init: Option<Box<Statement>>,
+        condition: Option<Expression>,
+        update: Option<Expression>,
+        body: Box<Statement>,
+    },
+    Return(Option<Expression>),
+    Emit(Expression),
+    /// `revert CustomError(...)`; `revert(...)` is a call expression
+    Revert(Expression),
+    Try {
+        call: Expression,
+        body: Block,
+        catches: Vec<Block>,
+    },
+    Break,
+    Continue,
+    /// Inline assembly, not parsed
+    Assembly,
+    /// `_;` in a modifier body
+    Placeholder,
+    /// A statement that could not be parsed; skipped up to the next `;` or
+    /// the end of its block
+    Unparsed,
+}
+
+#[derive(Debug, Clone)]
+pub struct Expression {
+    pub kind: ExpressionKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum ExpressionKind {
+    /// Identifier, including elementary type names such as `address`
+    Identifier(String),
+    Number(String),
+    /// String literal, quotes included
+    String(String),
+    Bool(bool),
+    /// `expr.member`
+    Member {
+        expr: Box<Expression>,
+        member: Ident,
+    },
+    /// `base[index]`, `base[start:end]` or `T[]`
+    Index {
+        base: Box<Expression>,
+        index: Option<Box<Expression>>,
+    },
+    /// `callee(arguments)`; named arguments are kept in order, by value
+    Call {
+        callee: Box<Expression>,
+        arguments: Vec<Expression>,
+    },
+    /// `callee{name: value, ...}`
+    CallOptions {
+        callee: Box<Expression>,
+        options: Vec<(Ident, Expression)>,
+    },
+    Unary {
+        operator: String,
+        operand: Box<Expression>,
+        prefix: bool,
+    },
+    Binary {
+        operator: String,
+        left: Box<Expression>,
+        right: Box<Expression>,
+    },
+    /// `target op value` for `=` and the compound assignments
+    Assign {
+        operator: String,
+        target: Box<Expression>,
+        value: Box<Expression>,
+    },
+    Conditional {
+        condition: Box<Expression>,
+        then_value: Box<Expression>,
+        else_value: Box<Expression>,
+    },
+    /// `(a, , b)`; a parenthesized expression is a one-element tuple
+    Tuple(Vec<Option<Expression>>),
+    /// `[a, b]`
+    Array(Vec<Expression>),
+    /// `new T`
+    New(TypeName),
+    /// `mapping(...)` or another type used as an expression
+    Type(TypeName),
+}
+
+impl Expression {
+    /// Name of an identifier expression
+    pub fn as_identifier(&self) -> Option<&str> {
+        match &self.kind {
+            ExpressionKind::Identifier(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Whether this is `object.member`, e.g. `msg.sender`
+    pub fn is_member_of(&self, object: &str, member: &str) -> bool {
+        match &self.kind {
+            ExpressionKind::Member { expr, member: name } => {
+                name.name == member && expr.as_identifier() == Some(object)
+            }
+            _ => false,
+        }
+    }
+
+    /// The expression without enclosing parentheses
+    pub fn unparenthesized(&self) -> &Expression {
+        match &self.kind {
+            ExpressionKind::Tuple(items) if items.len() == 1 => match &items[0] {
+                Some(inner) => inner.unparenthesized(),
+                None => self,
+            },
+            _ => self,
+        }
+    }
+
+    /// Member name called by `expr.member(...)` or `expr.member{...}(...)`,
+    /// with the object it is called on
+    pub fn member_call(&self) -> Option<(&Expression, &Ident)> {
+        let ExpressionKind::Call { callee, .. } = &self.kind else {
+            return None;
+        };
+        let callee = match &callee.kind {
+            ExpressionKind::CallOptions { callee, .. } => callee,
+            _ => callee,
+        };
+        match &callee.kind {
+            ExpressionKind::Member { expr, member } => Some((expr, member)),
+            _ => None,
+        }
+    }
+
+    /// Name of the function called by `name(...)`
+    pub fn called_name(&self) -> Option<&str> {
+        match &self.kind {
+            ExpressionKind::Call { callee, .. } => callee.as_identifier(),
+            _ => None,
+        }
+    }
+
+    /// Direct subexpressions, in source order
+    pub fn children(&self) -> Vec<&Expression> {
+        match &self.kind {
+            ExpressionKind::Member { expr, .. } => vec![expr],
+            ExpressionKind::Index { base, index } => std::iter::once(&**base).chain(index.as_deref()).collect(),
+            ExpressionKind::Call { callee, arguments } => std::iter::once(&**callee).chain(arguments).collect(),
+            ExpressionKind::CallOptions { callee, options } => {
+                std::iter::once(&**callee).chain(options.iter().map(|(_, value)| value)).collect()
+            }
+            ExpressionKind::Unary { operand, .. } => vec![operand],
+            ExpressionKind::Binary { left, right, .. } => vec![left, right],
+            ExpressionKind::Assign { target, value, .. } => vec![target, value],
+            ExpressionKind::Conditional { condition, then_value, else_value } => {
+                vec![condition, then_value, else_value]
+            }
+            ExpressionKind::Tuple(items) => items.iter().flatten().collect(),
+            ExpressionKind::Array(items) => items.iter().collect(),
+            ExpressionKind::Identifier(_)
+            | ExpressionKind::Number(_)
+            | ExpressionKind::String(_)
+            | ExpressionKind::Bool(_)
+            | ExpressionKind::New(_)
+            | ExpressionKind::Type(_) => Vec::new(),
+        }
+    }
+
+    /// Visit this expression and every subexpression, parents first
+    pub fn walk<'a>(&'a self, visit: &mut impl FnMut(&'a Expression)) {
+        visit(self);
+        for child in self.children() {
+            child.walk(visit);
+        }
+    }
+
+    /// Variable a write to this expression lands in: `balances` for
+    /// `balances[a].total`
+    pub fn root_identifier(&self) -> Option<&str> {
+        match &self.unparenthesized().kind {
+            ExpressionKind::Identifier(name) => Some(name),
+            ExpressionKind::Index { base, .. } => base.root_identifier(),
+            ExpressionKind::Member { expr, .. } => expr.root_identifier(),
+            _ => None,
+        }
+    }
+}
+
+impl Statement {
+    /// Visit this statement and every nested statement, parents first
+    pub fn walk<'a>(&'a self, visit: &mut impl FnMut(&'a Statement)) {
+        visit(self);
+        match &self.kind {
+            StatementKind::Block(block) => block.walk(visit),
+            StatementKind::If { then_branch, else_branch, .. } => {
+                then_branch.walk(visit);
+                if let Some(else_branch) = else_branch {
+                    else_branch.walk(visit);
+                }
+            }
+            StatementKind::While { body, .. } | StatementKind::DoWhile { body, .. } => body.walk(visit),
+            StatementKind::For { init, body, .. } => {
+                if let Some(init) = init {
+                    init.walk(visit);
+                }
+                body.walk(visit);
+            }
+            StatementKind::Try { body, catches, .. } => {
+                body.walk(visit);
+                catches.iter().for_each(|catch| catch.walk(visit));
+            }
+            _ => {}
+        }
+    }
+
+    /// Expressions written directly in this statement, not in nested ones
+    pub fn expressions(&self) -> Vec<&Expression> {
+        match &self.kind {
+            StatementKind::Expression(expression)
+            | StatementKind::Emit(expression)
+            | StatementKind::Revert(expression)
+            | StatementKind::Return(Some(expression))
+            | StatementKind::If { condition: expression, .. }
+            | StatementKind::While { condition: expression, .. }
+            | StatementKind::DoWhile { condition: expression, .. }
+            | StatementKind::Try { call: expression, .. } => vec![expression],
+            StatementKind::VariableDeclaration { value, .. } => value.iter().collect(),
+            StatementKind::For { condition, update, .. } => condition.iter().chain(update.iter()).collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl Block {
+    /// Visit every statement in the block, nested ones included
+    pub fn walk<'a>(&'a self, visit: &mut impl FnMut(&'a Statement)) {
+        self.statements.iter().for_each(|statement| statement.walk(visit));
+    }
+
+    /// Visit every expression in the block, subexpressions included
+    pub fn walk_expressions<'a>(&'a self, visit: &mut impl FnMut(&'a Expression)) {
+        self.walk(&mut |statement| {
+            for expression in statement.expressions() {
+                expression.walk(visit);
+            }
+        });
+    }
+}
+
+impl SourceUnit {
+    /// Every function and modifier, with the contract declaring it
+    pub fn functions(&self) -> impl Iterator<Item = (Option<&Contract>, &Function)> {
+        self.functions.iter().map(|function| (None, function)).chain(
+            self.contracts.iter().flat_map(|contract| {
+                contract
+                    .functions
+                    .iter()
+                    .chain(&contract.modifiers)
+                    .map(move |function| (Some(contract), function))
+            }),
+        )
+    }
+
+    /// Visit every expression in function bodies, modifier arguments and
+    /// state variable initializers
+    pub fn walk_expressions<'a>(&'a self, visit: &mut impl FnMut(&'a Expression)) {
+        for contract in &self.contracts {
+            for variable in &contract.state_variables {
+                if let Some(value) = &variable.value {
+                    value.walk(visit);
+                }
+            }
+        }
+        for (_, function) in self.functions() {
+            for modifier in &function.modifiers {
+                modifier.arguments.iter().for_each(|argument| argument.walk(visit));
+            }
+            if let Some(body) = &function.body {
+                body.walk_expressions(visit);
+            }
+        }
+    }
+
+    /// Every type name written in declarations
+    pub fn type_names(&self) -> Vec<&TypeName> {
+        let mut types = Vec::new();
+        for contract in &self.contracts {
+            types.extend(contract.state_variables.iter().map(|variable| &variable.type_name));
+        }
+        for (_, function) in self.functions() {
+            types.extend(function.parameters.iter().chain(&function.returns).map(|variable| &variable.type_name));
+            if let Some(body) = &function.body {
+                body.walk(&mut |statement| {
+                    if let StatementKind::VariableDeclaration { declarations, .. } = &statement.kind {
+                        types.extend(declarations.iter().flatten().map(|variable| &variable.type_name));
+                    }
+                });
+            }
+        }
+        types
+    }
+}
@@ -0,0 +1,180 @@
+//! Solidity lexer
+//!
+//! Splits source into identifiers, numbers, string literals and
+//! punctuation. Comments and whitespace are dropped, so nothing inside a
+//! comment ever reaches a rule; string literals keep their span but are
+//! never mistaken for code.
+
+use super::ast::Span;
+
+/// Kind of a token; its text is the source slice of its span
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// Identifier or keyword
+    Ident,
+    Number,
+    /// String literal, including `hex"..."` and `unicode"..."`
+    Str,
+    /// Operator or delimiter
+    Punct,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+/// Punctuation, longest first so the longest match wins
+const PUNCTUATION: &[&str] = &[
+    ">>>=", ">>=", "<<=", ">>>", "**=", "**", "==", "!=", "<=", ">=", "&&", "||", "++", "--", "+=", "-=",
+    "*=", "/=", "%=", "|=", "&=", "^=", "=>", "->", "<<", ">>", ":=", "+", "-", "*", "/", "%", "=", "<",
+    ">", "!", "~", "&", "|", "^", "?", ":", ";", ",", ".", "(", ")", "[", "]", "{", "}",
+];
+
+fn is_ident_start(c: u8) -> bool {
+    c.is_ascii_alphabetic() || c == b'_' || c == b'$'
+}
+
+fn is_ident_continue(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_' || c == b'$'
+}
+
+/// Tokenize `source`
+///
+/// Never fails: an unterminated comment or string runs to the end of the
+/// source, and a character that starts no token is skipped.
+pub fn tokenize(source: &str) -> Vec<Token> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        let c = bytes[pos];
+        let start = pos;
+
+        if c.is_ascii_whitespace() {
+            pos += 1;
+            continue;
+        }
+        if source[pos..].starts_with("//") {
+            pos = source[pos..].find('\n').map_or(bytes.len(), |i| pos + i);
+            continue;
+        }
+        if source[pos..].starts_with("/*") {
+            pos = source[pos + 2..].find("*/").map_or(bytes.len(), |i| pos + 2 + i + 2);
+            continue;
+        }
+
+        let kind = if c == b'"' || c == b'\'' {
+            pos = string_end(bytes, pos);
+            TokenKind::Str
+        } else if is_ident_start(c) {
+            while pos < bytes.len() && is_ident_continue(bytes[pos]) {
+                pos += 1;
+            }
+            // hex"..." and unicode"..." are single literals
+            let word = &source[start..pos];
+            if matches!(word, "hex" | "unicode") && matches!(bytes.get(pos), Some(b'"' | b'\'')) {
+                pos = string_end(bytes, pos);
+                TokenKind::Str
+            } else {
+                TokenKind::Ident
+            }
+        } else if c.is_ascii_digit() || (c == b'.' && bytes.get(pos + 1).is_some_and(u8::is_ascii_digit)) {
+            pos = number_end(bytes, pos);
+            TokenKind::Number
+        } else if let Some(punct) = PUNCTUATION.iter().find(|p| source[pos..].starts_with(**p)) {
+            pos += punct.len();
+            TokenKind::Punct
+        } else {
+            // Skip a whole character, which may be several bytes
+            pos += source[pos..].chars().next().map_or(1, char::len_utf8);
+            continue;
+        };
+
+        tokens.push(Token { kind, span: Span::new(start, pos) });
+    }
+
+    tokens
+}
+
+/// End of the string literal whose quote is at `start`
+fn string_end(bytes: &[u8], start: usize) -> usize {
+    let quote = bytes[start];
+    let mut pos = start + 1;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'\\' => pos += 2,
+            b'\n' => return pos,
+            c if c == quote => return pos + 1,
+            _ => pos += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// End of the number starting at `start`: decimal with `_`, `.` and an
+/// exponent, or `0x` hex
+fn number_end(bytes: &[u8], start: usize) -> usize {
+    let mut pos = start;
+    if bytes[pos] == b'0' && matches!(bytes.get(pos + 1), Some(b'x' | b'X')) {
+        pos += 2;
+        while pos < bytes.len() && (bytes[pos].is_ascii_hexdigit() || bytes[pos] == b'_') {
+            pos += 1;
+        }
+        return pos;
+    }
+
+    while pos < bytes.len() && (bytes[pos].is_ascii_digit() || bytes[pos] == b'_') {
+        pos += 1;
+    }
+    if bytes.get(pos) == Some(&b'.') && bytes.get(pos + 1).is_some_and(u8::is_ascii_digit) {
+        pos += 1;
+        while pos < bytes.len() && (bytes[pos].is_ascii_digit() || bytes[pos] == b'_') {
+            pos += 1;
+        }
+    }
+    if matches!(bytes.get(pos), Some(b'e' | b'E')) {
+        let digits = match bytes.get(pos + 1) {
+            Some(b'-') => pos + 2,
+            _ => pos + 1,
+        };
+        if bytes.get(digits).is_some_and(u8::is_ascii_digit) {
+            pos = digits;
+            while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+                pos += 1;
+            }
+        }
+    }
+    pos
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts<'a>(source: &'a str, tokens: &[Token]) -> Vec<&'a str> {
+        tokens.iter().map(|t| &source[t.span.start..t.span.end]).collect()
+    }
+
+    #[test]
+    fn test_comments_dropped_and_strings_kept_whole() {
+        let source = "a = now; // now\n/* block.timestamp */ s = \"now // not a comment\";\nx >>= 1e18;";
+        let tokens = tokenize(source);
+
+        assert_eq!(
+            texts(source, &tokens),
+            ["a", "=", "now", ";", "s", "=", "\"now // not a comment\"", ";", "x", ">>=", "1e18", ";"]
+        );
+        assert_eq!(tokens[6].kind, TokenKind::Str);
+        assert_eq!(tokens[10].kind, TokenKind::Number);
+    }
+
+    #[test]
+    fn test_unterminated_input_does_not_panic() {
+        assert_eq!(tokenize("/* open").len(), 0);
+        assert_eq!(tokenize("s = 'open").len(), 3);
+        assert_eq!(tokenize("hex\"00ff\" é 0x1F_ff").len(), 2);
+    }
+}
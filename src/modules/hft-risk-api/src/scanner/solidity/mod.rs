@@ -0,0 +1,32 @@
+//! Solidity front end
+//!
+//! Lexer and parser producing the syntax tree source rules walk. Rules see
+//! code only: comments are dropped by the lexer and string literals are
+//! single tokens, so neither can trigger a finding.
+
+pub mod analysis;
+pub mod ast;
+pub mod lexer;
+mod parser;
+pub mod version;
+
+pub use parser::ParseError;
+
+use ast::SourceUnit;
+use lexer::Token;
+
+/// Tokens and syntax tree of one source file
+#[derive(Debug, Default)]
+pub struct Syntax {
+    pub tokens: Vec<Token>,
+    pub unit: SourceUnit,
+    /// Constructs skipped by the parser; the rest of the file is still parsed
+    pub errors: Vec<ParseError>,
+}
+
+/// Tokenize and parse `source`
+pub fn parse(source: &str) -> Syntax {
+    let tokens = lexer::tokenize(source);
+    let (unit, errors) = parser::parse(source, &tokens);
+    Syntax { tokens, unit, errors }
+}
@@ -0,0 +1,1230 @@
+//! Solidity parser
+//!
+//! Recursive descent over the token stream. Parsing never fails as a whole:
+//! a declaration or statement that cannot be parsed is recorded as an
+//! error and skipped up to the next `;` or the end of its block, so one
+//! unsupported construct does not hide the rest of the file from rules.
+//! Nesting is bounded by [`MAX_DEPTH`] so hostile input cannot exhaust the
+//! stack.
+
+use super::ast::*;
+use super::lexer::{Token, TokenKind};
+
+/// A construct the parser could not understand
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub message: String,
+    pub span: Span,
+}
+
+type PResult<T> = Result<T, ParseError>;
+
+/// Number units, e.g. `1 ether`, `2 days`
+const UNITS: &[&str] = &[
+    "wei", "gwei", "szabo", "finney", "ether", "seconds", "minutes", "hours", "days", "weeks", "years",
+];
+
+const ASSIGNMENT_OPERATORS: &[&str] = &["=", "+=", "-=", "*=", "/=", "%=", "|=", "&=", "^=", "<<=", ">>=", ">>>="];
+
+const DATA_LOCATIONS: &[&str] = &["memory", "storage", "calldata"];
+
+/// Deepest nesting of statements and expressions the parser descends into
+///
+/// A level takes up to 16 KiB of stack in an unoptimized build, so this
+/// keeps a parse within half of a 2 MiB thread stack; real contracts nest
+/// far less.
+pub const MAX_DEPTH: usize = 64;
+
+/// Words that end a type and never name a variable
+const RESERVED: &[&str] = &[
+    "public", "private", "internal", "external", "constant", "immutable", "override", "virtual", "returns",
+    "memory", "storage", "calldata", "indexed", "payable", "view", "pure", "is", "returns", "transient",
+];
+
+/// Binding power of a binary operator; higher binds tighter
+fn precedence(operator: &str) -> Option<u8> {
+    Some(match operator {
+        "||" => 1,
+        "&&" => 2,
+        "==" | "!=" => 3,
+        "<" | ">" | "<=" | ">=" => 4,
+        "|" => 5,
+        "^" => 6,
+        "&" => 7,
+        "<<" | ">>" | ">>>" => 8,
+        "+" | "-" => 9,
+        "*" | "/" | "%" => 10,
+        "**" => 11,
+        _ => return None,
+    })
+}
+
+/// Parse a tokenized source file
+pub fn parse(source: &str, tokens: &[Token]) -> (SourceUnit, Vec<ParseError>) {
+    let mut parser = Parser { source, tokens, pos: 0, depth: 0, errors: Vec::new() };
+    let unit = parser.source_unit();
+    (unit, parser.errors)
+}
+
+struct Parser<'s> {
+    source: &'s str,
+    tokens: &'s [Token],
+    pos: usize,
+    /// Statements and expressions currently being parsed, one inside the other
+    depth: usize,
+    errors: Vec<ParseError>,
+}
+
+impl<'s> Parser<'s> {
+    // ------------------------------------------------------------------
+    // Token helpers
+    // ------------------------------------------------------------------
+
+    fn peek_at(&self, ahead: usize) -> Option<&'s str> {
+        self.tokens
+            .get(self.pos + ahead)
+            .map(|token| &self.source[token.span.start..token.span.end])
+    }
+
+    fn peek(&self) -> Option<&'s str> {
+        self.peek_at(0)
+    }
+
+    fn kind(&self) -> Option<TokenKind> {
+        self.tokens.get(self.pos).map(|token| token.kind)
+    }
+
+    fn is(&self, text: &str) -> bool {
+        self.peek() == Some(text)
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    /// Span of the next token, or an empty span at the end of the source
+    fn span(&self) -> Span {
+        self.tokens
+            .get(self.pos)
+            .map_or(Span::new(self.source.len(), self.source.len()), |token| token.span)
+    }
+
+    /// End of the last consumed token
+    fn prev_end(&self) -> usize {
+        self.pos
+            .checked_sub(1)
+            .and_then(|index| self.tokens.get(index))
+            .map_or(0, |token| token.span.end)
+    }
+
+    /// Span from `start` to the end of the last consumed token
+    fn since(&self, start: Span) -> Span {
+        Span::new(start.start, self.prev_end().max(start.start))
+    }
+
+    fn bump(&mut self) -> Option<&'s str> {
+        let text = self.peek();
+        if text.is_some() {
+            self.pos += 1;
+        }
+        text
+    }
+
+    fn eat(&mut self, text: &str) -> bool {
+        if self.is(text) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> PResult<T> {
+        Err(ParseError { message: message.into(), span: self.span() })
+    }
+
+    fn expect(&mut self, text: &str) -> PResult<()> {
+        if self.eat(text) {
+            Ok(())
+        } else {
+            self.error(format!("expected '{}', found {}", text, self.describe()))
+        }
+    }
+
+    fn describe(&self) -> String {
+        self.peek().map_or("end of input".to_string(), |text| format!("'{}'", text))
+    }
+
+    fn ident(&mut self) -> PResult<Ident> {
+        if self.kind() != Some(TokenKind::Ident) {
+            return self.error(format!("expected an identifier, found {}", self.describe()));
+        }
+        let span = self.span();
+        self.pos += 1;
+        Ok(Ident { name: self.source[span.start..span.end].to_string(), span })
+    }
+
+    /// Skip a declaration or statement: up to and including the next `;`
+    /// or balanced `{ ... }` at this level, stopping before a `}` that
+    /// closes the enclosing block
+    fn skip_item(&mut self) {
+        let mut depth = 0_usize;
+        while let Some(text) = self.peek() {
+            match text {
+                "(" | "[" | "{" => depth += 1,
+                ")" | "]" => depth = depth.saturating_sub(1),
+                "}" if depth == 0 => return,
+                "}" => {
+                    depth -= 1;
+                    if depth == 0 {
+                        self.pos += 1;
+                        return;
+                    }
+                }
+                ";" if depth == 0 => {
+                    self.pos += 1;
+                    return;
+                }
+                _ => {}
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// Skip a balanced group opened by the next token
+    fn skip_group(&mut self) -> PResult<()> {
+        let (open, close) = match self.peek() {
+            Some("(") => ("(", ")"),
+            Some("{") => ("{", "}"),
+            Some("[") => ("[", "]"),
+            _ => return self.error(format!("expected a group, found {}", self.describe())),
+        };
+        let mut depth = 0_usize;
+        while let Some(text) = self.bump() {
+            if text == open {
+                depth += 1;
+            } else if text == close {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(());
+                }
+            }
+        }
+        self.error(format!("unclosed '{}'", open))
+    }
+
+    /// Run `parse` one nesting level deeper
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> PResult<T>) -> PResult<T> {
+        let depth = self.depth;
+        let result = self.deeper().and_then(|()| parse(self));
+        self.depth = depth;
+        result
+    }
+
+    /// Enter one more nesting level, failing past [`MAX_DEPTH`]
+    fn deeper(&mut self) -> PResult<()> {
+        self.depth += 1;
+        self.check_chain(0)
+    }
+
+    /// Fail if `links` more levels, from a chain of operators or member
+    /// accesses built in a loop, would pass [`MAX_DEPTH`]
+    fn check_chain(&self, links: usize) -> PResult<()> {
+        if self.depth + links > MAX_DEPTH {
+            return self.error(format!("nesting deeper than {} levels", MAX_DEPTH));
+        }
+        Ok(())
+    }
+
+    /// Run `parse`, rewinding to the current token if it fails
+    fn attempt<T>(&mut self, parse: impl FnOnce(&mut Self) -> PResult<T>) -> Option<T> {
+        let start = self.pos;
+        match parse(self) {
+            Ok(value) => Some(value),
+            Err(_) => {
+                self.pos = start;
+                None
+            }
+        }
+    }
+
+    // ------------------------------------------------------------------
+    // Declarations
+    // ------------------------------------------------------------------
+
+    fn source_unit(&mut self) -> SourceUnit {
+        let mut unit = SourceUnit::default();
+        while !self.at_end() {
+            let start = self.pos;
+            if let Err(e) = self.top_level_item(&mut unit) {
+                self.errors.push(e);
+                self.pos = self.pos.max(start);
+                self.skip_item();
+            }
+            if self.pos == start {
+                // A stray `}`; skip it so parsing moves on
+                self.pos += 1;
+            }
+        }
+        unit
+    }
+
+    fn top_level_item(&mut self, unit: &mut SourceUnit) -> PResult<()> {
+        match self.peek() {
+            Some("pragma") => unit.pragmas.push(self.pragma()?),
+            Some("import") => unit.imports.push(self.import()?),
+            Some("abstract" | "contract" | "interface" | "library") => unit.contracts.push(self.contract()?),
+            Some("function") => unit.functions.push(self.function()?),
+            Some("error") => {
+                self.pos += 1;
+                unit.errors.push(self.ident()?);
+                self.skip_item();
+            }
+            _ => self.skip_item(),
+        }
+        Ok(())
+    }
+
+    fn pragma(&mut self) -> PResult<Pragma> {
+        let start = self.span();
+        self.expect("pragma")?;
+        let name = self.ident()?.name;
+        let value_start = self.span().start;
+        while !self.is(";") && !self.at_end() {
+            self.pos += 1;
+        }
+        let value = self.source[value_start.min(self.prev_end())..self.prev_end()].trim().to_string();
+        self.expect(";")?;
+        Ok(Pragma { name, value, span: self.since(start) })
+    }
+
+    fn import(&mut self) -> PResult<Import> {
+        let start = self.span();
+        self.expect("import")?;
+        let mut path = String::new();
+        while !self.is(";") && !self.at_end() {
+            if self.kind() == Some(TokenKind::Str) && path.is_empty() {
+                let span = self.span();
+                path = self.source[span.start + 1..span.end.saturating_sub(1).max(span.start + 1)].to_string();
+            }
+            self.pos += 1;
+        }
+        self.expect(";")?;
+        Ok(Import { path, span: self.since(start) })
+    }
+
+    fn contract(&mut self) -> PResult<Contract> {
+        let start = self.span();
+        let kind = match self.bump() {
+            Some("abstract") => {
+                self.expect("contract")?;
+                ContractKind::AbstractContract
+            }
+            Some("interface") => ContractKind::Interface,
+            Some("library") => ContractKind::Library,
+            _ => ContractKind::Contract,
+        };
+        let name = self.ident()?;
+
+        let mut bases = Vec::new();
+        if self.eat("is") {
+            loop {
+                let mut base = self.ident()?;
+                while self.eat(".") {
+                    base = self.ident()?;
+                }
+                bases.push(base);
+                if self.is("(") {
+                    self.skip_group()?;
+                }
+                if !self.eat(",") {
+                    break;
+                }
+            }
+        }
+
+        let mut contract = Contract {
+            kind,
+            name,
+            bases,
+            state_variables: Vec::new(),
+            functions: Vec::new(),
+            modifiers: Vec::new(),
+            using: Vec::new(),
+            events: Vec::new(),
+            errors: Vec::new(),
+            span: start,
+        };
+
+        self.expect("{")?;
+        while !self.is("}") && !self.at_end() {
+            let member_start = self.pos;
+            if let Err(e) = self.contract_member(&mut contract) {
+                self.errors.push(e);
+                self.skip_item();
+            }
+            if self.pos == member_start {
+                self.pos += 1;
+            }
+        }
+        self.expect("}")?;
+        contract.span = self.since(start);
+        Ok(contract)
+    }
+
+    fn contract_member(&mut self, contract: &mut Contract) -> PResult<()> {
+        match self.peek() {
+            Some("function" | "constructor" | "fallback" | "receive") => {
+                contract.functions.push(self.function()?);
+            }
+            Some("modifier") => contract.modifiers.push(self.function()?),
+            Some("event") => {
+                self.pos += 1;
+                contract.events.push(self.ident()?);
+                self.skip_item();
+            }
+            Some("error") => {
+                self.pos += 1;
+                contract.errors.push(self.ident()?);
+                self.skip_item();
+            }
+            Some("using") => {
+                self.pos += 1;
+                let mut library = self.ident()?;
+                while self.eat(".") {
+                    library = self.ident()?;
+                }
+                contract.using.push(library);
+                self.skip_item();
+            }
+            Some("struct" | "enum" | "type") => self.skip_item(),
+            _ => contract.state_variables.push(self.state_variable()?),
+        }
+        Ok(())
+    }
+
+    fn state_variable(&mut self) -> PResult<VariableDeclaration> {
+        let start = self.span();
+        let type_name = self.type_name()?;
+        let mut visibility = None;
+        let mut constant = false;
+        loop {
+            match self.peek() {
+                Some("public") => visibility = Some(Visibility::Public),
+                Some("private") => visibility = Some(Visibility::Private),
+                Some("internal") => visibility = Some(Visibility::Internal),
+                Some("constant" | "immutable") => constant = true,
+                Some("transient") => {}
+                Some("override") => {
+                    self.pos += 1;
+                    if self.is("(") {
+                        self.skip_group()?;
+                    }
+                    continue;
+                }
+                _ => break,
+            }
+            self.pos += 1;
+        }
+        let name = self.ident()?;
+        let value = if self.eat("=") { Some(self.expression()?) } else { None };
+        self.expect(";")?;
+        Ok(VariableDeclaration {
+            type_name,
+            name: Some(name),
+            location: None,
+            constant,
+            visibility,
+            value,
+            span: self.since(start),
+        })
+    }
+
+    fn function(&mut self) -> PResult<Function> {
+        let start = self.span();
+        let kind = match self.bump() {
+            Some("constructor") => FunctionKind::Constructor,
+            Some("fallback") => FunctionKind::Fallback,
+            Some("receive") => FunctionKind::Receive,
+            Some("modifier") => FunctionKind::Modifier,
+            _ => FunctionKind::Function,
+        };
+
+        let mut name = None;
+        if matches!(kind, FunctionKind::Function | FunctionKind::Modifier) && self.kind() == Some(TokenKind::Ident) {
+            name = Some(self.ident()?);
+        }
+        // `function () external` is the pre-0.6 fallback function
+        let kind = match (kind, &name) {
+            (FunctionKind::Function, None) => FunctionKind::Fallback,
+            _ => kind,
+        };
+
+        let parameters = if self.is("(") { self.parameters()? } else { Vec::new() };
+
+        let mut function = Function {
+            kind,
+            name,
+            parameters,
+            returns: Vec::new(),
+            visibility: None,
+            mutability: None,
+            is_virtual: false,
+            modifiers: Vec::new(),
+            body: None,
+            span: start,
+            header: start,
+        };
+
+        loop {
+            match self.peek() {
+                Some("{" | ";") | None => break,
+                Some("public") => function.visibility = Some(Visibility::Public),
+                Some("external") => function.visibility = Some(Visibility::External),
+                Some("internal") => function.visibility = Some(Visibility::Internal),
+                Some("private") => function.visibility = Some(Visibility::Private),
+                Some("pure") => function.mutability = Some(Mutability::Pure),
+                Some("view" | "constant") => function.mutability = Some(Mutability::View),
+                Some("payable") => function.mutability = Some(Mutability::Payable),
+                Some("virtual") => function.is_virtual = true,
+                Some("override") => {
+                    self.pos += 1;
+                    if self.is("(") {
+                        self.skip_group()?;
+                    }
+                    continue;
+                }
+                Some("returns") => {
+                    self.pos += 1;
+                    function.returns = self.parameters()?;
+                    continue;
+                }
+                Some(_) if self.kind() == Some(TokenKind::Ident) => {
+                    function.modifiers.push(self.modifier_invocation()?);
+                    continue;
+                }
+                Some(_) => return self.error(format!("unexpected {} in function header", self.describe())),
+            }
+            self.pos += 1;
+        }
+
+        function.header = self.since(start);
+        if self.is("{") {
+            function.body = Some(self.block()?);
+        } else {
+            self.expect(";")?;
+        }
+        function.span = self.since(start);
+        Ok(function)
+    }
+
+    fn modifier_invocation(&mut self) -> PResult<ModifierInvocation> {
+        let start = self.span();
+        let mut name = self.ident()?;
+        while self.eat(".") {
+            name = self.ident()?;
+        }
+        let arguments = if self.is("(") { self.arguments()? } else { Vec::new() };
+        Ok(ModifierInvocation { name, arguments, span: self.since(start) })
+    }
+
+    /// `(T a, T b)` in a function header, event or catch clause
+    fn parameters(&mut self) -> PResult<Vec<VariableDeclaration>> {
+        self.expect("(")?;
+        let mut parameters = Vec::new();
+        while !self.is(")") {
+            let start = self.span();
+            let type_name = self.type_name()?;
+            let mut location = None;
+            while let Some(word) = self.peek().filter(|word| DATA_LOCATIONS.contains(word) || *word == "indexed") {
+                if word != "indexed" {
+                    location = Some(word.to_string());
+                }
+                self.pos += 1;
+            }
+            let name = match self.peek() {
+                Some(word) if self.kind() == Some(TokenKind::Ident) && !RESERVED.contains(&word) => Some(self.ident()?),
+                _ => None,
+            };
+            parameters.push(VariableDeclaration {
+                type_name,
+                name,
+                location,
+                constant: false,
+                visibility: None,
+                value: None,
+                span: self.since(start),
+            });
+            if !self.eat(",") {
+                break;
+            }
+        }
+        self.expect(")")?;
+        Ok(parameters)
+    }
+
+    fn type_name(&mut self) -> PResult<TypeName> {
+        let start = self.span();
+        match self.peek() {
+            Some("mapping") => {
+                self.pos += 1;
+                self.skip_group()?;
+            }
+            Some("function") => {
+                self.pos += 1;
+                self.skip_group()?;
+                while let Some(word) = self.peek() {
+                    match word {
+                        "internal" | "external" | "pure" | "view" | "payable" => self.pos += 1,
+                        "returns" => {
+                            self.pos += 1;
+                            self.skip_group()?;
+                        }
+                        _ => break,
+                    }
+                }
+            }
+            _ => {
+                let first = self.ident()?;
+                if RESERVED.contains(&first.name.as_str()) && first.name != "payable" {
+                    return Err(ParseError { message: format!("expected a type, found '{}'", first.name), span: first.span });
+                }
+                if first.name == "address" {
+                    self.eat("payable");
+                }
+                while self.is(".") && self.tokens.get(self.pos + 1).is_some_and(|t| t.kind == TokenKind::Ident) {
+                    self.pos += 2;
+                }
+            }
+        }
+        while self.is("[") {
+            self.skip_group()?;
+        }
+        let span = self.since(start);
+        Ok(TypeName { text: self.source[span.start..span.end].to_string(), span })
+    }
+
+    // ------------------------------------------------------------------
+    // Statements
+    // ------------------------------------------------------------------
+
+    fn block(&mut self) -> PResult<Block> {
+        let start = self.span();
+        let unchecked = self.eat("unchecked");
+        self.expect("{")?;
+        let mut statements = Vec::new();
+        while !self.is("}") && !self.at_end() {
+            let statement_start = self.pos;
+            match self.statement() {
+                Ok(statement) => statements.push(statement),
+                Err(e) => {
+                    self.errors.push(e);
+                    self.pos = statement_start;
+                    let span = self.span();
+                    self.skip_item();
+                    statements.push(Statement { kind: StatementKind::Unparsed, span: self.since(span) });
+                }
+            }
+            if self.pos == statement_start {
+                self.pos += 1;
+            }
+        }
+        self.expect("}")?;
+        Ok(Block { statements, unchecked, span: self.since(start) })
+    }
+
+    fn statement(&mut self) -> PResult<Statement> {
+        self.nested(Self::single_statement)
+    }
+
+    fn single_statement(&mut self) -> PResult<Statement> {
+        let start = self.span();
+        let kind = match self.peek() {
+            Some("{") => StatementKind::Block(self.block()?),
+            Some("unchecked") if self.peek_at(1) == Some("{") => StatementKind::Block(self.block()?),
+            Some("if") => {
+                self.pos += 1;
+                self.expect("(")?;
+                let condition = self.expression()?;
+                self.expect(")")?;
+                let then_branch = Box::new(self.statement()?);
+                let else_branch = if self.eat("else") { Some(Box::new(self.statement()?)) } else { None };
+                StatementKind::If { condition, then_branch, else_branch }
+            }
+            Some("while") => {
+                self.pos += 1;
+                self.expect("(")?;
+                let condition = self.expression()?;
+                self.expect(")")?;
+                StatementKind::While { condition, body: Box::new(self.statement()?) }
+            }
+            Some("do") => {
+                self.pos += 1;
+                let body = Box::new(self.statement()?);
+                self.expect("while")?;
+                self.expect("(")?;
+                let condition = self.expression()?;
+                self.expect(")")?;
+                self.expect(";")?;
+                StatementKind::DoWhile { body, condition }
+            }
+            Some("for") => {
+                self.pos += 1;
+                self.expect("(")?;
+                let init = if self.eat(";") { None } else { Some(Box::new(self.simple_statement()?)) };
+                let condition = if self.is(";") { None } else { Some(self.expression()?) };
+                self.expect(";")?;
+                let update = if self.is(")") { None } else { Some(self.expression()?) };
+                self.expect(")")?;
+                StatementKind::For { init, condition, update, body: Box::new(self.statement()?) }
+            }
+            Some("return") => {
+                self.pos += 1;
+                let value = if self.is(";") { None } else { Some(self.expression()?) };
+                self.expect(";")?;
+                StatementKind::Return(value)
+            }
+            Some("emit") => {
+                self.pos += 1;
+                let event = self.expression()?;
+                self.expect(";")?;
+                StatementKind::Emit(event)
+            }
+            Some("revert") if self.peek_at(1) != Some("(") && self.peek_at(1) != Some(";") => {
+                self.pos += 1;
+                let error = self.expression()?;
+                self.expect(";")?;
+                StatementKind::Revert(error)
+            }
+            Some("try") => {
+                self.pos += 1;
+                let call = self.expression()?;
+                if self.eat("returns") {
+                    self.parameters()?;
+                }
+                let body = self.block()?;
+                let mut catches = Vec::new();
+                while self.eat("catch") {
+                    if self.kind() == Some(TokenKind::Ident) {
+                        self.pos += 1;
+                    }
+                    if self.is("(") {
+                        self.parameters()?;
+                    }
+                    catches.push(self.block()?);
+                }
+                StatementKind::Try { call, body, catches }
+            }
+            Some("break") => {
+                self.pos += 1;
+                self.expect(";")?;
+                StatementKind::Break
+            }
+            Some("continue") => {
+                self.pos += 1;
+                self.expect(";")?;
+                StatementKind::Continue
+            }
+            Some("assembly") => {
+                self.pos += 1;
+                if self.kind() == Some(TokenKind::Str) {
+                    self.pos += 1;
+                }
+                if self.is("(") {
+                    self.skip_group()?;
+                }
+                self.skip_group()?;
+                StatementKind::Assembly
+            }
+            Some("_") if self.peek_at(1) == Some(";") => {
+                self.pos += 2;
+                StatementKind::Placeholder
+            }
+            _ => return self.simple_statement(),
+        };
+        Ok(Statement { kind, span: self.since(start) })
+    }
+
+    /// Variable declaration or expression statement, with its `;`
+    fn simple_statement(&mut self) -> PResult<Statement> {
+        let start = self.span();
+        let kind = match self.attempt(Self::variable_declaration) {
+            Some(kind) => kind,
+            None => {
+                let expression = self.expression()?;
+                self.expect(";")?;
+                StatementKind::Expression(expression)
+            }
+        };
+        Ok(Statement { kind, span: self.since(start) })
+    }
+
+    fn variable_declaration(&mut self) -> PResult<StatementKind> {
+        let declarations = if self.is("(") {
+            self.pos += 1;
+            let mut declarations = Vec::new();
+            loop {
+                if self.is(",") || self.is(")") {
+                    declarations.push(None);
+                } else {
+                    declarations.push(Some(self.local_variable()?));
+                }
+                if !self.eat(",") {
+                    break;
+                }
+            }
+            self.expect(")")?;
+            if declarations.iter().all(Option::is_none) {
+                return self.error("empty declaration");
+            }
+            self.expect("=")?;
+            declarations
+        } else {
+            let declaration = self.local_variable()?;
+            if !self.is("=") && !self.is(";") {
+                return self.error("expected '=' or ';' after a declaration");
+            }
+            self.eat("=");
+            vec![Some(declaration)]
+        };
+
+        let value = if self.is(";") { None } else { Some(self.expression()?) };
+        self.expect(";")?;
+        Ok(StatementKind::VariableDeclaration { declarations, value })
+    }
+
+    fn local_variable(&mut self) -> PResult<VariableDeclaration> {
+        let start = self.span();
+        let type_name = self.type_name()?;
+        let location = match self.peek() {
+            Some(word) if DATA_LOCATIONS.contains(&word) => {
+                self.pos += 1;
+                Some(word.to_string())
+            }
+            _ => None,
+        };
+        let name = self.ident()?;
+        if RESERVED.contains(&name.name.as_str()) {
+            return Err(ParseError { message: format!("'{}' cannot name a variable", name.name), span: name.span });
+        }
+        Ok(VariableDeclaration {
+            type_name,
+            name: Some(name),
+            location,
+            constant: false,
+            visibility: None,
+            value: None,
+            span: self.since(start),
+        })
+    }
+
+    // ------------------------------------------------------------------
+    // Expressions
+    // ------------------------------------------------------------------
+
+    fn expression(&mut self) -> PResult<Expression> {
+        self.nested(Self::assignment)
+    }
+
+    fn assignment(&mut self) -> PResult<Expression> {
+        let start = self.span();
+        let target = self.conditional()?;
+        match self.peek() {
+            Some(operator) if ASSIGNMENT_OPERATORS.contains(&operator) => {
+                self.pos += 1;
+                let value = self.expression()?;
+                Ok(Expression {
+                    kind: ExpressionKind::Assign {
+                        operator: operator.to_string(),
+                        target: Box::new(target),
+                        value: Box::new(value),
+                    },
+                    span: self.since(start),
+                })
+            }
+            _ => Ok(target),
+        }
+    }
+
+    fn conditional(&mut self) -> PResult<Expression> {
+        let start = self.span();
+        let condition = self.binary(1)?;
+        if !self.eat("?") {
+            return Ok(condition);
+        }
+        let then_value = self.expression()?;
+        self.expect(":")?;
+        let else_value = self.expression()?;
+        Ok(Expression {
+            kind: ExpressionKind::Conditional {
+                condition: Box::new(condition),
+                then_value: Box::new(then_value),
+                else_value: Box::new(else_value),
+            },
+            span: self.since(start),
+        })
+    }
+
+    fn binary(&mut self, min_precedence: u8) -> PResult<Expression> {
+        let start = self.span();
+        let mut left = self.unary()?;
+        let mut links = 0;
+        while let Some(operator) = self.peek().filter(|_| self.kind() == Some(TokenKind::Punct)) {
+            let Some(precedence) = precedence(operator).filter(|p| *p >= min_precedence) else {
+                break;
+            };
+            self.pos += 1;
+            // Each operator of a chain nests the ones before it
+            links += 1;
+            self.check_chain(links)?;
+            // `**` is right-associative
+            let next = if operator == "**" { precedence } else { precedence + 1 };
+            let right = self.nested(|parser| parser.binary(next))?;
+            left = Expression {
+                kind: ExpressionKind::Binary {
+                    operator: operator.to_string(),
+                    left: Box::new(left),
+                    right: Box::new(right),
+                },
+                span: self.since(start),
+            };
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> PResult<Expression> {
+        let start = self.span();
+        match self.peek() {
+            Some(operator @ ("!" | "~" | "-" | "+" | "++" | "--" | "delete")) => {
+                self.pos += 1;
+                let operand = self.nested(Self::unary)?;
+                Ok(Expression {
+                    kind: ExpressionKind::Unary {
+                        operator: operator.to_string(),
+                        operand: Box::new(operand),
+                        prefix: true,
+                    },
+                    span: self.since(start),
+                })
+            }
+            _ => self.postfix(),
+        }
+    }
+
+    fn postfix(&mut self) -> PResult<Expression> {
+        let start = self.span();
+        let mut expression = self.primary()?;
+        let mut links = 0;
+        loop {
+            let kind = match self.peek() {
+                Some(".") => {
+                    self.pos += 1;
+                    let member = self.ident()?;
+                    ExpressionKind::Member { expr: Box::new(expression), member }
+                }
+                Some("[") => {
+                    self.pos += 1;
+                    let index = if self.is("]") {
+                        None
+                    } else if self.eat(":") {
+                        (!self.is("]")).then(|| self.expression()).transpose()?.map(Box::new)
+                    } else {
+                        let index = self.expression()?;
+                        if self.eat(":") && !self.is("]") {
+                            self.expression()?;
+                        }
+                        Some(Box::new(index))
+                    };
+                    self.expect("]")?;
+                    ExpressionKind::Index { base: Box::new(expression), index }
+                }
+                Some("(") => {
+                    let arguments = self.arguments()?;
+                    ExpressionKind::Call { callee: Box::new(expression), arguments }
+                }
+                Some("{") if self.kind_at(1) == Some(TokenKind::Ident) && self.peek_at(2) == Some(":") => {
+                    let options = self.named_values()?;
+                    ExpressionKind::CallOptions { callee: Box::new(expression), options }
+                }
+                Some(operator @ ("++" | "--")) => {
+                    self.pos += 1;
+                    ExpressionKind::Unary {
+                        operator: operator.to_string(),
+                        operand: Box::new(expression),
+                        prefix: false,
+                    }
+                }
+                _ => return Ok(expression),
+            };
+            expression = Expression { kind, span: self.since(start) };
+            // Each member, index or call nests the expression before it
+            links += 1;
+            self.check_chain(links)?;
+        }
+    }
+
+    fn kind_at(&self, ahead: usize) -> Option<TokenKind> {
+        self.tokens.get(self.pos + ahead).map(|token| token.kind)
+    }
+
+    /// `(a, b)` or `({name: a, other: b})`
+    fn arguments(&mut self) -> PResult<Vec<Expression>> {
+        self.expect("(")?;
+        let mut arguments = Vec::new();
+        if self.is("{") {
+            arguments = self.named_values()?.into_iter().map(|(_, value)| value).collect();
+        } else {
+            while !self.is(")") {
+                arguments.push(self.expression()?);
+                if !self.eat(",") {
+                    break;
+                }
+            }
+        }
+        self.expect(")")?;
+        Ok(arguments)
+    }
+
+    /// `{name: value, ...}`
+    fn named_values(&mut self) -> PResult<Vec<(Ident, Expression)>> {
+        self.expect("{")?;
+        let mut values = Vec::new();
+        while !self.is("}") {
+            let name = self.ident()?;
+            self.expect(":")?;
+            values.push((name, self.expression()?));
+            if !self.eat(",") {
+                break;
+            }
+        }
+        self.expect("}")?;
+        Ok(values)
+    }
+
+    fn primary(&mut self) -> PResult<Expression> {
+        let start = self.span();
+        let text = match self.peek() {
+            Some(text) => text,
+            None => return self.error("expected an expression, found end of input"),
+        };
+
+        let kind = match self.kind() {
+            Some(TokenKind::Number) => {
+                self.pos += 1;
+                if self.peek().is_some_and(|unit| UNITS.contains(&unit)) {
+                    self.pos += 1;
+                }
+                ExpressionKind::Number(text.to_string())
+            }
+            Some(TokenKind::Str) => {
+                // Adjacent literals concatenate
+                while self.kind() == Some(TokenKind::Str) {
+                    self.pos += 1;
+                }
+                ExpressionKind::String(self.source[start.start..self.prev_end()].to_string())
+            }
+            Some(TokenKind::Ident) => match text {
+                "true" | "false" => {
+                    self.pos += 1;
+                    ExpressionKind::Bool(text == "true")
+                }
+                "new" => {
+                    self.pos += 1;
+                    ExpressionKind::New(self.type_name()?)
+                }
+                "mapping" | "function" => ExpressionKind::Type(self.type_name()?),
+                _ => {
+                    self.pos += 1;
+                    ExpressionKind::Identifier(text.to_string())
+                }
+            },
+            Some(TokenKind::Punct) if text == "(" => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                loop {
+                    if self.is(",") || self.is(")") {
+                        items.push(None);
+                    } else {
+                        items.push(Some(self.expression()?));
+                    }
+                    if !self.eat(",") {
+                        break;
+                    }
+                }
+                self.expect(")")?;
+                ExpressionKind::Tuple(items)
+            }
+            Some(TokenKind::Punct) if text == "[" => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                while !self.is("]") {
+                    items.push(self.expression()?);
+                    if !self.eat(",") {
+                        break;
+                    }
+                }
+                self.expect("]")?;
+                ExpressionKind::Array(items)
+            }
+            _ => return self.error(format!("expected an expression, found {}", self.describe())),
+        };
+        Ok(Expression { kind, span: self.since(start) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::solidity::lexer::tokenize;
+
+    fn parse_source(source: &str) -> (SourceUnit, Vec<ParseError>) {
+        parse(source, &tokenize(source))
+    }
+
+    const VAULT: &str = r#"
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.19;
+
+import "@openzeppelin/contracts/security/ReentrancyGuard.sol";
+
+contract Vault is ReentrancyGuard, Ownable(msg.sender) {
+    using SafeERC20 for IERC20;
+
+    mapping(address => uint256) public balances;
+    address immutable owner;
+    event Withdrawn(address indexed to, uint256 amount);
+    error Empty();
+
+    modifier onlyOwner() {
+        require(msg.sender == owner, "owner");
+        _;
+    }
+
+    function withdraw(uint256 amount) external nonReentrant {
+        (bool success, ) = msg.sender.call{value: amount}("");
+        require(success);
+        balances[msg.sender] -= amount;
+        unchecked { total = total - amount; }
+        emit Withdrawn(msg.sender, amount);
+    }
+
+    function kill() public onlyOwner {
+        selfdestruct(payable(owner));
+    }
+
+    receive() external payable {}
+}
+"#;
+
+    #[test]
+    fn test_parses_declarations() {
+        let (unit, errors) = parse_source(VAULT);
+        assert_eq!(errors, []);
+
+        assert_eq!(unit.pragmas[0].name, "solidity");
+        assert_eq!(unit.pragmas[0].value, "^0.8.19");
+        assert_eq!(unit.imports[0].path, "@openzeppelin/contracts/security/ReentrancyGuard.sol");
+
+        let vault = &unit.contracts[0];
+        assert_eq!(vault.name.name, "Vault");
+        assert_eq!(vault.bases.iter().map(|b| b.name.as_str()).collect::<Vec<_>>(), ["ReentrancyGuard", "Ownable"]);
+        assert_eq!(vault.using[0].name, "SafeERC20");
+        assert_eq!(vault.state_variables.len(), 2);
+        assert_eq!(vault.state_variables[0].type_name.text, "mapping(address => uint256)");
+        assert_eq!(vault.modifiers[0].display_name(), "onlyOwner");
+        assert_eq!(vault.errors[0].name, "Empty");
+
+        let names: Vec<&str> = vault.functions.iter().map(Function::display_name).collect();
+        assert_eq!(names, ["withdraw", "kill", "receive"]);
+        let withdraw = &vault.functions[0];
+        assert_eq!(withdraw.visibility, Some(Visibility::External));
+        assert!(withdraw.has_modifier("nonReentrant"));
+        assert!(vault.functions[2].is_externally_callable());
+    }
+
+    #[test]
+    fn test_parses_statements_and_expressions() {
+        let (unit, _) = parse_source(VAULT);
+        let body = unit.contracts[0].functions[0].body.as_ref().unwrap();
+        assert_eq!(body.statements.len(), 5);
+
+        let StatementKind::VariableDeclaration { declarations, value } = &body.statements[0].kind else {
+            panic!("expected a declaration, found {:?}", body.statements[0].kind);
+        };
+        assert_eq!(declarations.len(), 2);
+        assert_eq!(declarations[0].as_ref().unwrap().name.as_ref().unwrap().name, "success");
+        let (object, member) = value.as_ref().unwrap().member_call().unwrap();
+        assert!(object.is_member_of("msg", "sender"));
+        assert_eq!(member.name, "call");
+
+        assert_eq!(body.statements[1].expressions()[0].called_name(), Some("require"));
+        let StatementKind::Expression(Expression { kind: ExpressionKind::Assign { operator, target, .. }, .. }) =
+            &body.statements[2].kind
+        else {
+            panic!("expected an assignment");
+        };
+        assert_eq!(operator, "-=");
+        assert!(matches!(target.kind, ExpressionKind::Index { .. }));
+        assert!(matches!(&body.statements[3].kind, StatementKind::Block(block) if block.unchecked));
+        assert!(matches!(body.statements[4].kind, StatementKind::Emit(_)));
+
+        let modifier = unit.contracts[0].modifiers[0].body.as_ref().unwrap();
+        assert!(matches!(modifier.statements[1].kind, StatementKind::Placeholder));
+    }
+
+    #[test]
+    fn test_recovers_from_unparseable_statements() {
+        let source = "contract C {\n    function f() public {\n        x = = 1;\n        y = 2 ** 3 ** 2;\n    }\n    function g() external {}\n}\n";
+        let (unit, errors) = parse_source(source);
+
+        assert_eq!(errors.len(), 1);
+        let functions = &unit.contracts[0].functions;
+        assert_eq!(functions.len(), 2);
+        let body = functions[0].body.as_ref().unwrap();
+        assert!(matches!(body.statements[0].kind, StatementKind::Unparsed));
+        let StatementKind::Expression(assign) = &body.statements[1].kind else {
+            panic!("expected an expression");
+        };
+        let ExpressionKind::Assign { value, .. } = &assign.kind else {
+            panic!("expected an assignment");
+        };
+        // 2 ** (3 ** 2)
+        assert!(matches!(&value.kind, ExpressionKind::Binary { right, .. }
+            if matches!(right.kind, ExpressionKind::Binary { .. })));
+    }
+
+    #[test]
+    fn test_deep_nesting_is_an_error_not_a_stack_overflow() {
+        let deep = 2000;
+        let source = format!(
+            "contract C {{\n    function f() public {{ uint x = {}1{}; }}\n    function g() public {{ {}x = 1;{} }}\n    function h() public {{ x = {}y; z = a{}; }}\n    function k() public {{ x = ((1)); }}\n}}\n",
+            "(".repeat(deep),
+            ")".repeat(deep),
+            "{".repeat(deep),
+            "}".repeat(deep),
+            "!".repeat(deep),
+            ".b".repeat(deep),
+        );
+        let (unit, errors) = parse_source(&source);
+
+        // The declaration falls back to an expression statement, which
+        // fails on its own
+        assert_eq!(errors.len(), 4, "{:?}", errors);
+        let too_deep = format!("nesting deeper than {} levels", MAX_DEPTH);
+        assert_eq!(errors.iter().filter(|e| e.message == too_deep).count(), 3);
+
+        // The statement going too deep is skipped and the rest of the file
+        // parsed; blocks are kept down to the deepest one allowed
+        let functions = &unit.contracts[0].functions;
+        assert_eq!(functions.len(), 4);
+        let first = |index: usize| &functions[index].body.as_ref().unwrap().statements[0].kind;
+        assert!(matches!(first(0), StatementKind::Unparsed));
+        assert!(matches!(first(1), StatementKind::Block(_)));
+        assert!(matches!(first(2), StatementKind::Unparsed));
+        let k = functions[3].body.as_ref().unwrap();
+        assert!(matches!(k.statements[0].kind, StatementKind::Expression(_)));
+
+        let nested = format!("contract C {{ function f() public {{ x = {}1{}; }} }}", "(".repeat(40), ")".repeat(40));
+        assert!(parse_source(&nested).1.is_empty());
+    }
+}
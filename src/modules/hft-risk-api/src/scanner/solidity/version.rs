@@ -0,0 +1,346 @@
+//! Compiler versions and `pragma solidity` ranges
+//!
+//! Ranges follow solc's semver matching: `^`, `~`, the comparison
+//! operators, exact and partial versions (`0.7.6`, `0.7`, `0.7.x`), hyphen
+//! ranges and `||`. Prereleases are not distinguished.
+
+use std::fmt;
+
+use super::ast::SourceUnit;
+
+/// A solc release, e.g. `0.8.19`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl Version {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self { major, minor, patch }
+    }
+
+    /// Parse `0.8.19`, `v0.8.19` or `0.8.19+commit.7dd6d404`
+    pub fn parse(text: &str) -> Option<Self> {
+        let core = text.trim().trim_start_matches('v').split(['+', '-']).next()?;
+        let mut parts = core.split('.').map(|part| part.parse::<u32>().ok());
+        let version = Self::new(parts.next()??, parts.next()??, parts.next()??);
+        parts.next().is_none().then_some(version)
+    }
+
+    fn next_patch(self) -> Self {
+        Self::new(self.major, self.minor, self.patch + 1)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Versions from `lower` up to, not including, `upper`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Interval {
+    lower: Version,
+    /// `None` for no upper bound
+    upper: Option<Version>,
+}
+
+impl Interval {
+    const ANY: Interval = Interval { lower: Version::new(0, 0, 0), upper: None };
+
+    fn is_empty(&self) -> bool {
+        self.upper.is_some_and(|upper| upper <= self.lower)
+    }
+
+    fn intersect(&self, other: &Interval) -> Interval {
+        let upper = match (self.upper, other.upper) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        Interval { lower: self.lower.max(other.lower), upper }
+    }
+}
+
+/// Set of compiler versions admitted by a pragma
+///
+/// Kept as sorted, disjoint intervals; no intervals means no compiler
+/// satisfies it, e.g. after intersecting `^0.6.0` and `^0.8.0`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionRange {
+    intervals: Vec<Interval>,
+}
+
+/// Version written in a range, possibly partial: `0.7` or `0.7.x`
+#[derive(Debug, Clone, Copy)]
+struct Partial {
+    major: Option<u32>,
+    minor: Option<u32>,
+    patch: Option<u32>,
+}
+
+impl Partial {
+    fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim_start_matches('v');
+        let mut parts = [None; 3];
+        for (index, part) in text.split('.').enumerate() {
+            if index == 3 {
+                return Err(format!("invalid version '{}'", text));
+            }
+            parts[index] = match part {
+                "x" | "X" | "*" => break,
+                _ => Some(part.parse::<u32>().map_err(|_| format!("invalid version '{}'", text))?),
+            };
+        }
+        Ok(Self { major: parts[0], minor: parts[1], patch: parts[2] })
+    }
+
+    /// Lowest version matching, missing parts as zero
+    fn floor(&self) -> Version {
+        Version::new(self.major.unwrap_or(0), self.minor.unwrap_or(0), self.patch.unwrap_or(0))
+    }
+
+    /// First version past every version matching; `None` for `*`
+    fn ceiling(&self) -> Option<Version> {
+        match (self.major, self.minor, self.patch) {
+            (Some(major), Some(minor), Some(patch)) => Some(Version::new(major, minor, patch + 1)),
+            (Some(major), Some(minor), None) => Some(Version::new(major, minor + 1, 0)),
+            (Some(major), None, _) => Some(Version::new(major + 1, 0, 0)),
+            (None, ..) => None,
+        }
+    }
+
+    /// Versions matched by one comparator such as `^0.8.0` or `<0.8`
+    fn comparator(operator: &str, version: &str) -> Result<Interval, String> {
+        let partial = Self::parse(version)?;
+        let floor = partial.floor();
+        let interval = |lower, upper| Interval { lower, upper };
+        Ok(match operator {
+            "" | "=" => interval(floor, partial.ceiling()),
+            ">=" => interval(floor, None),
+            ">" => match partial.ceiling() {
+                Some(ceiling) => interval(ceiling, None),
+                // `>*` admits nothing
+                None => interval(floor, Some(floor)),
+            },
+            "<" => interval(Version::new(0, 0, 0), Some(floor)),
+            "<=" => interval(Version::new(0, 0, 0), partial.ceiling()),
+            "~" => {
+                let upper = match (partial.major, partial.minor) {
+                    (Some(major), Some(minor)) => Some(Version::new(major, minor + 1, 0)),
+                    _ => partial.ceiling(),
+                };
+                interval(floor, upper)
+            }
+            // Bumps the first nonzero part given
+            "^" => {
+                let upper = match (partial.major, partial.minor, partial.patch) {
+                    (Some(0), Some(0), Some(patch)) => Some(Version::new(0, 0, patch + 1)),
+                    (Some(0), Some(minor), _) => Some(Version::new(0, minor + 1, 0)),
+                    _ => partial.major.map(|major| Version::new(major + 1, 0, 0)),
+                };
+                interval(floor, upper)
+            }
+            _ => return Err(format!("unknown operator '{}'", operator)),
+        })
+    }
+}
+
+impl VersionRange {
+    /// Every version
+    pub fn any() -> Self {
+        Self { intervals: vec![Interval::ANY] }
+    }
+
+    /// `version` or newer
+    pub fn at_least(version: Version) -> Self {
+        Self { intervals: vec![Interval { lower: version, upper: None }] }
+    }
+
+    /// Exactly `version`
+    pub fn exact(version: Version) -> Self {
+        Self { intervals: vec![Interval { lower: version, upper: Some(version.next_patch()) }] }
+    }
+
+    /// Parse the value of a `pragma solidity` directive, e.g. `>=0.6.0 <0.8.0`
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut intervals = Vec::new();
+        for alternative in text.split("||") {
+            intervals.push(Self::comparator_set(alternative)?);
+        }
+        Ok(Self::normalized(intervals))
+    }
+
+    /// Comparators that must all hold: `>=0.6.0 <0.8.0`, `>= 0.6.0`, or a
+    /// hyphen range `0.6.0 - 0.7.6`
+    fn comparator_set(text: &str) -> Result<Interval, String> {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        if let [from, "-", to] = words[..] {
+            let upper = Partial::parse(to)?.ceiling();
+            return Ok(Interval { lower: Partial::parse(from)?.floor(), upper });
+        }
+
+        let mut interval = Interval::ANY;
+        let mut pending_operator: Option<&str> = None;
+        for word in words {
+            let split = word
+                .find(|c: char| c.is_ascii_alphanumeric() || c == '*')
+                .unwrap_or(word.len());
+            let (operator, version) = word.split_at(split);
+            let operator = match pending_operator.take() {
+                Some(pending) if operator.is_empty() => pending,
+                Some(pending) => return Err(format!("operator '{}' has no version", pending)),
+                None => operator,
+            };
+            if version.is_empty() {
+                pending_operator = Some(operator);
+                continue;
+            }
+            interval = interval.intersect(&Partial::comparator(operator, version)?);
+        }
+        if let Some(operator) = pending_operator {
+            return Err(format!("operator '{}' has no version", operator));
+        }
+        Ok(interval)
+    }
+
+    /// Sort, drop empty intervals and merge overlapping ones
+    fn normalized(mut intervals: Vec<Interval>) -> Self {
+        intervals.retain(|interval| !interval.is_empty());
+        intervals.sort_by_key(|interval| interval.lower);
+        let mut merged: Vec<Interval> = Vec::with_capacity(intervals.len());
+        for interval in intervals {
+            match merged.last_mut() {
+                Some(last) if last.upper.is_none_or(|upper| interval.lower <= upper) => {
+                    last.upper = match (last.upper, interval.upper) {
+                        (Some(a), Some(b)) => Some(a.max(b)),
+                        _ => None,
+                    };
+                }
+                _ => merged.push(interval),
+            }
+        }
+        Self { intervals: merged }
+    }
+
+    /// Versions admitted by both ranges
+    pub fn intersect(&self, other: &VersionRange) -> VersionRange {
+        Self::normalized(
+            self.intervals
+                .iter()
+                .flat_map(|a| other.intervals.iter().map(move |b| a.intersect(b)))
+                .collect(),
+        )
+    }
+
+    /// Whether no compiler is admitted
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    pub fn contains(&self, version: Version) -> bool {
+        self.intervals
+            .iter()
+            .any(|interval| interval.lower <= version && interval.upper.is_none_or(|upper| version < upper))
+    }
+
+    /// Lowest admitted version
+    pub fn min(&self) -> Option<Version> {
+        self.intervals.first().map(|interval| interval.lower)
+    }
+
+    /// Whether some admitted version is older than `version`
+    pub fn admits_below(&self, version: Version) -> bool {
+        self.min().is_some_and(|min| min < version)
+    }
+
+    /// Whether some admitted version is `version` or newer
+    pub fn admits_from(&self, version: Version) -> bool {
+        self.intervals.last().is_some_and(|last| last.upper.is_none_or(|upper| upper > version))
+    }
+
+    /// Whether every admitted version is older than `version`; false for
+    /// an empty range
+    pub fn is_below(&self, version: Version) -> bool {
+        !self.is_empty() && !self.admits_from(version)
+    }
+}
+
+impl fmt::Display for VersionRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, interval) in self.intervals.iter().enumerate() {
+            if index > 0 {
+                write!(f, " || ")?;
+            }
+            match interval.upper {
+                Some(upper) if upper == interval.lower.next_patch() => write!(f, "={}", interval.lower)?,
+                Some(upper) => write!(f, ">={} <{}", interval.lower, upper)?,
+                None => write!(f, ">={}", interval.lower)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compiler range admitted by every parsable `pragma solidity` in `unit`;
+/// `None` without one
+pub fn pragma_range(unit: &SourceUnit) -> Option<VersionRange> {
+    unit.pragmas
+        .iter()
+        .filter(|pragma| pragma.name == "solidity")
+        .filter_map(|pragma| VersionRange::parse(&pragma.value).ok())
+        .reduce(|a, b| a.intersect(&b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(text: &str) -> String {
+        VersionRange::parse(text).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_pragma_forms() {
+        assert_eq!(range("^0.4.24"), ">=0.4.24 <0.5.0");
+        assert_eq!(range("^0.0.3"), "=0.0.3");
+        assert_eq!(range("~0.5"), ">=0.5.0 <0.6.0");
+        assert_eq!(range("~0.5.2"), ">=0.5.2 <0.6.0");
+        assert_eq!(range("0.7.6"), "=0.7.6");
+        assert_eq!(range("=0.7.6"), "=0.7.6");
+        assert_eq!(range("0.7"), ">=0.7.0 <0.8.0");
+        assert_eq!(range("0.7.x"), ">=0.7.0 <0.8.0");
+        assert_eq!(range(">=0.6.0 <0.8.0"), ">=0.6.0 <0.8.0");
+        assert_eq!(range(">= 0.6.0 < 0.8.0"), ">=0.6.0 <0.8.0");
+        assert_eq!(range(">0.6.2 <=0.7.6"), ">=0.6.3 <0.7.7");
+        assert_eq!(range("0.5.0 - 0.6"), ">=0.5.0 <0.7.0");
+        assert_eq!(range("^0.5.0 || ^0.7.0"), ">=0.5.0 <0.6.0 || >=0.7.0 <0.8.0");
+        assert_eq!(range(">=0.4.22"), ">=0.4.22");
+        assert_eq!(range("*"), ">=0.0.0");
+        assert!(VersionRange::parse(">= ").is_err());
+        assert!(VersionRange::parse("^0.8.a").is_err());
+    }
+
+    #[test]
+    fn test_intersection_and_bounds() {
+        let legacy = VersionRange::parse(">=0.6.0 <0.8.0").unwrap();
+        let modern = VersionRange::parse("^0.7.6").unwrap();
+        let both = legacy.intersect(&modern);
+        assert_eq!(both.to_string(), ">=0.7.6 <0.8.0");
+        assert!(both.is_below(Version::new(0, 8, 0)));
+        assert!(both.contains(Version::new(0, 7, 6)));
+        assert_eq!(both.min(), Some(Version::new(0, 7, 6)));
+
+        let open = VersionRange::parse(">=0.6.0").unwrap();
+        assert!(open.admits_below(Version::new(0, 8, 0)));
+        assert!(!open.is_below(Version::new(0, 8, 0)));
+
+        let conflict = modern.intersect(&VersionRange::parse("^0.8.0").unwrap());
+        assert!(conflict.is_empty());
+        assert!(!conflict.admits_below(Version::new(0, 8, 0)) && !conflict.is_below(Version::new(0, 8, 0)));
+
+        assert_eq!(Version::parse("v0.8.19+commit.7dd6d404"), Some(Version::new(0, 8, 19)));
+    }
+}
@@ -0,0 +1,199 @@
+//! Source Text - Line-indexed, parsed contract source for located findings
+
+use std::sync::Arc;
+
+use crate::models::Location;
+use crate::scanner::solidity::ast::{SourceUnit, Span};
+use crate::scanner::solidity::lexer::TokenKind;
+use crate::scanner::solidity::version::{self, VersionRange};
+use crate::scanner::solidity::{self, Syntax};
+
+/// Contract source with a line index and its syntax tree
+///
+/// Lines and columns are 1-based; columns count characters, not bytes.
+/// Cloning shares the text and the tree, so one parse serves every rule of
+/// a scan.
+#[derive(Debug, Clone)]
+pub struct SourceText {
+    text: Arc<str>,
+    /// Byte offset of the start of each line
+    line_starts: Vec<usize>,
+    syntax: Arc<Syntax>,
+    /// Compilers the file may be built with: its own `pragma solidity`
+    /// range, narrowed by the other files of a submission
+    compiler_range: Option<VersionRange>,
+    /// Syntax trees of the submitted files this one imports, directly or
+    /// through other imports
+    imported: Vec<Arc<Syntax>>,
+}
+
+impl SourceText {
+    pub fn new(text: &str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        let syntax = solidity::parse(text);
+        Self {
+            text: text.into(),
+            line_starts,
+            compiler_range: version::pragma_range(&syntax.unit),
+            syntax: Arc::new(syntax),
+            imported: Vec::new(),
+        }
+    }
+
+    /// The same source with the files of its import closure
+    pub fn with_imports<'s>(self, imported: impl IntoIterator<Item = &'s SourceText>) -> Self {
+        let imported = imported.into_iter().map(|file| Arc::clone(&file.syntax)).collect();
+        Self { imported, ..self }
+    }
+
+    /// The same source built with compilers in `range`
+    pub fn with_compiler_range(self, range: Option<VersionRange>) -> Self {
+        Self { compiler_range: range, ..self }
+    }
+
+    /// Compiler versions the source admits; `None` without a pragma
+    pub fn compiler_range(&self) -> Option<&VersionRange> {
+        self.compiler_range.as_ref()
+    }
+
+    /// Syntax tree of the source
+    pub fn ast(&self) -> &SourceUnit {
+        &self.syntax.unit
+    }
+
+    /// Syntax tree of the source followed by those of the files it imports,
+    /// where contracts look up their bases
+    pub fn units(&self) -> Vec<&SourceUnit> {
+        std::iter::once(&self.syntax.unit)
+            .chain(self.imported.iter().map(|syntax| &syntax.unit))
+            .collect()
+    }
+
+    /// Tokens and parse errors alongside the tree
+    pub fn syntax(&self) -> &Syntax {
+        &self.syntax
+    }
+
+    /// Source text of `span`
+    pub fn slice(&self, span: Span) -> &str {
+        &self.text[span.start..span.end]
+    }
+
+    /// Identifiers and keywords written in code, outside comments and
+    /// string literals
+    pub fn identifiers(&self) -> impl Iterator<Item = (&str, Span)> + '_ {
+        self.syntax
+            .tokens
+            .iter()
+            .filter(|token| token.kind == TokenKind::Ident)
+            .map(|token| (self.slice(token.span), token.span))
+    }
+
+    /// Full source text
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Length of the text in bytes
+    pub fn len(&self) -> usize {
+        self.text.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    pub fn contains(&self, pattern: &str) -> bool {
+        self.text.contains(pattern)
+    }
+
+    /// Lines with their 1-based line numbers
+    pub fn lines(&self) -> impl Iterator<Item = (u32, &str)> + '_ {
+        self.text.lines().enumerate().map(|(i, line)| (i as u32 + 1, line))
+    }
+
+    /// Location of the byte range `start..end`
+    pub fn location(&self, start: usize, end: usize) -> Location {
+        let (start_line, start_column) = self.line_column(start);
+        let (end_line, end_column) = self.line_column(end);
+        Location::Source {
+            start_line,
+            start_column,
+            end_line,
+            end_column,
+        }
+    }
+
+    /// Location of a syntax tree node
+    pub fn span_location(&self, span: Span) -> Location {
+        self.location(span.start, span.end)
+    }
+
+    /// 1-based line containing byte `offset`
+    pub fn line_of(&self, offset: usize) -> u32 {
+        self.line_column(offset).0
+    }
+
+    /// Locations of every occurrence of `pattern`
+    pub fn occurrences<'s>(&'s self, pattern: &'s str) -> impl Iterator<Item = Location> + 's {
+        self.text
+            .match_indices(pattern)
+            .map(|(start, matched)| self.location(start, start + matched.len()))
+    }
+
+    /// Locations of every occurrence of `word` not embedded in an identifier
+    pub fn word_occurrences<'s>(&'s self, word: &'s str) -> impl Iterator<Item = Location> + 's {
+        let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '$';
+        self.text
+            .match_indices(word)
+            .filter(move |(start, matched)| {
+                let before = self.text[..*start].chars().next_back();
+                let after = self.text[start + matched.len()..].chars().next();
+                !before.is_some_and(is_ident) && !after.is_some_and(is_ident)
+            })
+            .map(|(start, matched)| self.location(start, start + matched.len()))
+    }
+
+    /// Location spanning the whole of line `line`, without its line break
+    pub fn line_location(&self, line: u32) -> Location {
+        let start = self.line_starts[(line - 1) as usize];
+        let end = self.text[start..]
+            .find('\n')
+            .map_or(self.text.len(), |i| start + i);
+        let end = if self.text[..end].ends_with('\r') { end - 1 } else { end };
+        self.location(start, end)
+    }
+
+    fn line_column(&self, offset: usize) -> (u32, u32) {
+        let line = self.line_starts.partition_point(|start| *start <= offset);
+        let line_start = self.line_starts[line - 1];
+        let column = self.text[line_start..offset].chars().count() + 1;
+        (line as u32, column as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_occurrences_are_one_based_ranges() {
+        let source = SourceText::new("a = now;\nb = known + now;\n");
+        let found: Vec<Location> = source.word_occurrences("now").collect();
+
+        assert_eq!(
+            found,
+            [
+                Location::Source { start_line: 1, start_column: 5, end_line: 1, end_column: 8 },
+                Location::Source { start_line: 2, start_column: 13, end_line: 2, end_column: 16 },
+            ]
+        );
+        assert_eq!(source.occurrences("now").count(), 3);
+        assert_eq!(
+            source.line_location(2),
+            Location::Source { start_line: 2, start_column: 1, end_line: 2, end_column: 17 }
+        );
+    }
+}
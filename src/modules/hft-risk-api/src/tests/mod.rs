@@ -2,10 +2,8 @@
 
 #[cfg(test)]
 mod tests {
-    use super::*;
-
     mod models_tests {
-        use crate::models::{RiskLevel, Severity};
+        use crate::models::RiskLevel;
 
         #[test]
         fn test_risk_level_from_score() {
@@ -29,7 +27,7 @@ mod tests {
     }
 
     mod engine_tests {
-        use crate::risk::engine::{RiskEngine, EngineConfig};
+        use crate::risk::engine::RiskEngine;
         use crate::models::RiskAssessmentRequest;
 
         #[tokio::test]
@@ -48,6 +46,11 @@ mod tests {
                 chain: "ethereum".to_string(),
                 transaction_data: None,
                 amount: None,
+                scoring_model: None,
+                policy: None,
+                token_data: None,
+                rules: None,
+                source: None,
             };
 
             let response = engine.assess(request).await;
@@ -63,6 +66,11 @@ mod tests {
                 chain: "ethereum".to_string(),
                 transaction_data: None,
                 amount: None,
+                scoring_model: None,
+                policy: None,
+                token_data: None,
+                rules: None,
+                source: None,
             };
 
             // First call - should miss cache
@@ -80,14 +88,25 @@ mod tests {
 
     mod scanner_tests {
         use crate::scanner::OwaspScanner;
-        use crate::scanner::ScanRule;
 
         #[test]
         fn test_scanner_creation() {
             let scanner = OwaspScanner::new();
             let rules = scanner.available_rules();
             assert!(!rules.is_empty());
-            assert!(rules.contains(&"AccessControl".to_string()));
+            assert!(rules.contains(&"ACCESS_CONTROL".to_string()));
+        }
+
+        #[test]
+        fn test_catalogue_has_stable_ids() {
+            let scanner = OwaspScanner::new();
+            let catalogue = scanner.catalogue();
+            let access_control = catalogue
+                .iter()
+                .find(|rule| rule.name == "ACCESS_CONTROL")
+                .expect("ACCESS_CONTROL in catalogue");
+            assert_eq!(access_control.id, "AC-001");
+            assert_eq!(access_control.owasp, "OWASP-SC01");
         }
 
         #[test]
@@ -96,9 +115,9 @@ mod tests {
             let findings = scanner.scan_bytecode(
                 "0x1234",
                 "608060405234801561001057600080fd5b50"
-            );
-            // Should return findings based on patterns
-            assert!(findings.len() >= 0);
+            ).findings;
+            // Constructor prologue: no CALLER check and no DELEGATECALL
+            assert!(findings.is_empty());
         }
     }
 
@@ -0,0 +1,202 @@
+//! Usage Export - Hourly billing files in CSV or JSONL
+//!
+//! Each replica exports its own counters for every completed hour, tagged
+//! with a replica id; billing sums the files across replicas.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use super::{hour_start, UsageMeter};
+use crate::models::UsageCounters;
+
+/// Export file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Jsonl,
+}
+
+impl ExportFormat {
+    /// Parse `csv` or `jsonl`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "csv" => Some(ExportFormat::Csv),
+            "jsonl" => Some(ExportFormat::Jsonl),
+            _ => None,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Jsonl => "jsonl",
+        }
+    }
+}
+
+/// Usage of one key in one hour
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsageRecord {
+    pub key_id: String,
+    /// Unix timestamp of the start of the hour
+    pub hour: i64,
+    pub counters: UsageCounters,
+}
+
+#[derive(Serialize)]
+struct JsonRecord<'a> {
+    replica: &'a str,
+    key_id: &'a str,
+    hour_start: String,
+    #[serde(flatten)]
+    counters: UsageCounters,
+}
+
+/// Render records in the given format
+pub fn render(records: &[UsageRecord], replica: &str, format: ExportFormat) -> String {
+    let mut out = String::new();
+
+    match format {
+        ExportFormat::Csv => {
+            out.push_str("replica,key_id,hour_start");
+            for field in UsageCounters::FIELDS {
+                out.push(',');
+                out.push_str(field);
+            }
+            out.push('\n');
+
+            for record in records {
+                let _ = write!(out, "{},{},{}", replica, record.key_id, rfc3339(record.hour));
+                for value in record.counters.values() {
+                    let _ = write!(out, ",{}", value);
+                }
+                out.push('\n');
+            }
+        }
+        ExportFormat::Jsonl => {
+            for record in records {
+                let line = JsonRecord {
+                    replica,
+                    key_id: &record.key_id,
+                    hour_start: rfc3339(record.hour),
+                    counters: record.counters,
+                };
+                if let Ok(json) = serde_json::to_string(&line) {
+                    out.push_str(&json);
+                    out.push('\n');
+                }
+            }
+        }
+    }
+
+    out
+}
+
+fn rfc3339(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|time| time.to_rfc3339())
+        .unwrap_or_default()
+}
+
+/// Writes one file per completed hour range
+pub struct UsageExporter {
+    dir: PathBuf,
+    format: ExportFormat,
+    replica: String,
+    /// Hours before this have been exported
+    exported_before: i64,
+}
+
+impl UsageExporter {
+    /// Create an exporter writing into `dir`
+    pub fn new(dir: impl Into<PathBuf>, format: ExportFormat, replica: impl Into<String>) -> Self {
+        Self {
+            dir: dir.into(),
+            format,
+            replica: replica.into(),
+            exported_before: 0,
+        }
+    }
+
+    /// Export hours completed since the last export, if any
+    pub fn export_due(&mut self, meter: &UsageMeter, now: i64) -> io::Result<Option<PathBuf>> {
+        let current_hour = hour_start(now);
+        if current_hour <= self.exported_before {
+            return Ok(None);
+        }
+
+        let records = meter.completed_records(self.exported_before, current_hour);
+        self.exported_before = current_hour;
+        if records.is_empty() {
+            return Ok(None);
+        }
+
+        let path = self.dir.join(format!(
+            "usage-{}-{}.{}",
+            self.replica,
+            chrono::DateTime::from_timestamp(current_hour, 0)
+                .map(|time| time.format("%Y%m%dT%H").to_string())
+                .unwrap_or_default(),
+            self.format.extension(),
+        ));
+        write_atomic(&path, &render(&records, &self.replica, self.format))?;
+        Ok(Some(path))
+    }
+}
+
+fn write_atomic(path: &Path, contents: &str) -> io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::risk::engine::Provenance;
+
+    #[test]
+    fn test_render_formats() {
+        let records = vec![UsageRecord {
+            key_id: "acme".to_string(),
+            hour: 1_767_225_600,
+            counters: UsageCounters::for_assessment(Provenance::Scan, 512),
+        }];
+
+        let csv = render(&records, "api-1", ExportFormat::Csv);
+        assert_eq!(
+            csv,
+            "replica,key_id,hour_start,assessments,batch_items,cache_hits,cold_scans,bytes_served\n\
+             api-1,acme,2026-01-01T00:00:00+00:00,1,0,0,1,512\n"
+        );
+
+        let jsonl = render(&records, "api-1", ExportFormat::Jsonl);
+        let line: serde_json::Value = serde_json::from_str(jsonl.trim()).unwrap();
+        assert_eq!(line["key_id"], "acme");
+        assert_eq!(line["cold_scans"], 1);
+        assert_eq!(line["hour_start"], "2026-01-01T00:00:00+00:00");
+    }
+
+    #[test]
+    fn test_export_only_completed_hours() {
+        let dir = std::env::temp_dir().join(format!("usage-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let meter = UsageMeter::new(None);
+        let hour = 1_767_225_600;
+        meter.record_at("acme", hour + 5, UsageCounters::for_assessment(Provenance::Scan, 1));
+
+        let mut exporter = UsageExporter::new(&dir, ExportFormat::Csv, "api-1");
+        assert!(exporter.export_due(&meter, hour + 60).unwrap().is_none());
+
+        let path = exporter.export_due(&meter, hour + 3600 + 60).unwrap().unwrap();
+        assert!(fs::read_to_string(&path).unwrap().contains("api-1,acme,"));
+        assert!(exporter.export_due(&meter, hour + 3600 + 120).unwrap().is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
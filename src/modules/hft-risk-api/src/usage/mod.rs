@@ -0,0 +1,241 @@
+//! Usage Metering - Per-API-key usage counters for billing
+//!
+//! Features:
+//! - Hourly counters per key, distinguishing cache hits from cold scans
+//! - Deltas flushed to Redis periodically, so usage is summed across replicas
+//! - Periodic CSV/JSONL export of completed hours for billing
+
+pub mod export;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use dashmap::DashMap;
+
+use crate::cache::RedisCache;
+use crate::models::UsageCounters;
+use crate::risk::engine::Provenance;
+
+pub use export::{ExportFormat, UsageExporter, UsageRecord};
+
+/// Seconds per metering bucket
+pub const HOUR_SECS: i64 = 3600;
+
+/// Longest range a single usage query may cover
+pub const MAX_QUERY_RANGE_SECS: i64 = 31 * 24 * 3600;
+
+/// How long hourly counters are kept in Redis
+const REDIS_RETENTION_SECS: u64 = 90 * 24 * 3600;
+
+/// How long hourly counters are kept in memory
+const LOCAL_RETENTION_HOURS: i64 = 48;
+
+/// Start of the hour containing `timestamp`
+pub fn hour_start(timestamp: i64) -> i64 {
+    timestamp.div_euclid(HOUR_SECS) * HOUR_SECS
+}
+
+impl UsageCounters {
+    /// Counters for one assessment served with `bytes` of response body
+    pub fn for_assessment(provenance: Provenance, bytes: u64) -> Self {
+        let cached = provenance.is_cache_hit();
+        Self {
+            assessments: 1,
+            cache_hits: cached as u64,
+            cold_scans: (!cached) as u64,
+            bytes_served: bytes,
+            ..Self::default()
+        }
+    }
+}
+
+/// Per-key usage meter
+pub struct UsageMeter {
+    /// This replica's counters by (key id, hour)
+    hourly: DashMap<(String, i64), UsageCounters>,
+    /// Deltas not yet flushed to Redis
+    pending: DashMap<(String, i64), UsageCounters>,
+    redis: Option<Arc<RedisCache>>,
+}
+
+impl UsageMeter {
+    /// Create a meter; without Redis usage is only tracked per replica
+    pub fn new(redis: Option<Arc<RedisCache>>) -> Self {
+        Self {
+            hourly: DashMap::new(),
+            pending: DashMap::new(),
+            redis,
+        }
+    }
+
+    /// Record usage for a key in the current hour
+    pub fn record(&self, key_id: &str, delta: UsageCounters) {
+        self.record_at(key_id, chrono::Utc::now().timestamp(), delta);
+    }
+
+    fn record_at(&self, key_id: &str, timestamp: i64, delta: UsageCounters) {
+        let bucket = (key_id.to_string(), hour_start(timestamp));
+        self.hourly.entry(bucket.clone()).or_default().add(&delta);
+        if self.redis.is_some() {
+            self.pending.entry(bucket).or_default().add(&delta);
+        }
+    }
+
+    /// Push pending deltas to Redis; failed deltas are kept for the next flush
+    pub async fn flush(&self) {
+        let Some(redis) = &self.redis else {
+            return;
+        };
+
+        let buckets: Vec<(String, i64)> = self.pending.iter().map(|entry| entry.key().clone()).collect();
+        let mut by_key: std::collections::HashMap<String, Vec<(i64, UsageCounters)>> =
+            std::collections::HashMap::new();
+        for bucket in buckets {
+            if let Some(((key_id, hour), delta)) = self.pending.remove(&bucket) {
+                by_key.entry(key_id).or_default().push((hour, delta));
+            }
+        }
+
+        for (key_id, hours) in by_key {
+            if let Err(e) = redis.add_usage(&key_id, &hours, REDIS_RETENTION_SECS).await {
+                tracing::warn!("Failed to flush usage for {}: {}", key_id, e);
+                for (hour, delta) in hours {
+                    self.pending.entry((key_id.clone(), hour)).or_default().add(&delta);
+                }
+            }
+        }
+    }
+
+    /// Hourly usage of a key for the hours overlapping `[from, to]`
+    ///
+    /// Reads the cluster-wide totals from Redis when available, plus this
+    /// replica's unflushed deltas; otherwise only this replica's counters.
+    /// Hours more than [`MAX_QUERY_RANGE_SECS`] after `from` are left out.
+    pub async fn query(&self, key_id: &str, from: i64, to: i64) -> Vec<(i64, UsageCounters)> {
+        let last = hour_start(to.min(from.saturating_add(MAX_QUERY_RANGE_SECS)));
+        let hours: Vec<i64> = (hour_start(from)..=last)
+            .step_by(HOUR_SECS as usize)
+            .collect();
+
+        let shared = match &self.redis {
+            Some(redis) => match redis.get_usage(key_id, &hours).await {
+                Ok(counters) => Some(counters),
+                Err(e) => {
+                    tracing::warn!("Usage lookup in Redis failed, using local counters: {}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+
+        hours
+            .iter()
+            .enumerate()
+            .map(|(index, &hour)| {
+                let bucket = (key_id.to_string(), hour);
+                let counters = match &shared {
+                    Some(shared) => {
+                        let mut counters = shared[index];
+                        if let Some(pending) = self.pending.get(&bucket) {
+                            counters.add(&pending);
+                        }
+                        counters
+                    }
+                    None => self.hourly.get(&bucket).map(|c| *c).unwrap_or_default(),
+                };
+                (hour, counters)
+            })
+            .filter(|(_, counters)| !counters.is_empty())
+            .collect()
+    }
+
+    /// This replica's records for completed hours in `[from_hour, before_hour)`
+    pub fn completed_records(&self, from_hour: i64, before_hour: i64) -> Vec<UsageRecord> {
+        let mut records: Vec<UsageRecord> = self
+            .hourly
+            .iter()
+            .filter(|entry| entry.key().1 >= from_hour && entry.key().1 < before_hour)
+            .map(|entry| UsageRecord {
+                key_id: entry.key().0.clone(),
+                hour: entry.key().1,
+                counters: *entry.value(),
+            })
+            .collect();
+        records.sort_by(|a, b| (a.hour, &a.key_id).cmp(&(b.hour, &b.key_id)));
+        records
+    }
+
+    /// Drop in-memory counters older than the local retention
+    pub fn prune(&self, now: i64) {
+        let cutoff = hour_start(now) - LOCAL_RETENTION_HOURS * HOUR_SECS;
+        self.hourly.retain(|(_, hour), _| *hour >= cutoff);
+    }
+}
+
+/// Flush usage to Redis and run the billing export in the background
+pub fn spawn_usage_tasks(
+    meter: &'static UsageMeter,
+    flush_interval: Duration,
+    exporter: Option<UsageExporter>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut exporter = exporter;
+        let mut ticker = tokio::time::interval(flush_interval);
+        loop {
+            ticker.tick().await;
+            meter.flush().await;
+
+            let now = chrono::Utc::now().timestamp();
+            if let Some(exporter) = exporter.as_mut() {
+                match exporter.export_due(meter, now) {
+                    Ok(Some(path)) => tracing::info!("Exported usage to {}", path.display()),
+                    Ok(None) => {}
+                    Err(e) => tracing::warn!("Usage export failed: {}", e),
+                }
+            }
+            meter.prune(now);
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_meter_separates_hits_from_scans() {
+        let meter = UsageMeter::new(None);
+        let now = 1_767_225_600; // 2026-01-01T00:00:00Z
+
+        meter.record_at("acme", now + 10, UsageCounters::for_assessment(Provenance::Scan, 400));
+        meter.record_at("acme", now + 20, UsageCounters::for_assessment(Provenance::LocalCache, 300));
+        meter.record_at("acme", now + HOUR_SECS, UsageCounters::for_assessment(Provenance::SharedCache, 100));
+        meter.record_at("other", now, UsageCounters::for_assessment(Provenance::Scan, 50));
+
+        let usage = meter.query("acme", now, now + 2 * HOUR_SECS).await;
+        assert_eq!(usage.len(), 2);
+        assert_eq!(usage[0].0, now);
+        assert_eq!(
+            usage[0].1,
+            UsageCounters {
+                assessments: 2,
+                batch_items: 0,
+                cache_hits: 1,
+                cold_scans: 1,
+                bytes_served: 700,
+            }
+        );
+        assert_eq!(usage[1].1.cache_hits, 1);
+
+        let records = meter.completed_records(now, now + HOUR_SECS);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].key_id, "acme");
+
+        // Inverted and overlong ranges stay bounded
+        assert!(meter.query("acme", now + HOUR_SECS, now).await.is_empty());
+        assert_eq!(meter.query("acme", now, i64::MAX).await.len(), 2);
+
+        meter.prune(now + (LOCAL_RETENTION_HOURS + 2) * HOUR_SECS);
+        assert!(meter.query("acme", now, now + 2 * HOUR_SECS).await.is_empty());
+    }
+}